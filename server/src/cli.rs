@@ -0,0 +1,396 @@
+use crate::setup::{
+  backup,
+  config::{Config, StoreBackend},
+  db::{db_client, wait_for_database},
+  github, integrity,
+  metrics::Metrics,
+  migration,
+  seed::{self, GenerateOptions},
+  sqlite,
+  tracing::start_cli_logging,
+};
+use mongodb::{Client, Database};
+use std::{collections::HashMap, path::Path, sync::Arc, time::Duration};
+
+pub const USAGE: &str = "Usage:
+  rust_web_server                         start the http server
+  rust_web_server migrate up [VERSION]    apply the pending migrations, up to VERSION when given
+  rust_web_server migrate down [VERSION]  revert the migrations newer than VERSION, only the last one when omitted
+  rust_web_server migrate status          list the migrations and when they were applied
+  rust_web_server seed load DIR [--drop]  insert the seed files of DIR after validating them, into the sqlite
+                                          database when it is the configured store
+  rust_web_server seed validate DIR       check the seed files of DIR against the models and their references
+  rust_web_server seed generate [--users N] [--organizations N] [--repositories N] [--seed N] [--out DIR] [--drop]
+                                          generate a synthetic dataset, written to DIR or inserted in the database
+  rust_web_server check-integrity [--repair]
+                                          report the dangling references and one-sided relations, and fix them
+  rust_web_server export DIR              write every collection to DIR as NDJSON with a manifest of the counts
+  rust_web_server import DIR [--drop] [--dry-run]
+                                          check a backup of DIR then insert it into empty collections, --drop
+                                          replaces them once the backup is inserted and --dry-run only checks
+  rust_web_server import-github DIR [--dry-run]
+                                          upsert the users, organizations and repositories of the GitHub GraphQL
+                                          responses saved in DIR, --dry-run only lists what would be imported";
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+  Serve,
+  Migrate(MigrateCommand),
+  Seed(SeedCommand),
+  CheckIntegrity { repair: bool },
+  Export { dir: String },
+  Import { dir: String, drop: bool, dry_run: bool },
+  ImportGithub { dir: String, dry_run: bool },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MigrateCommand {
+  Up(Option<u32>),
+  Down(Option<u32>),
+  Status,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SeedCommand {
+  Load {
+    dir: String,
+    drop: bool,
+  },
+  Validate {
+    dir: String,
+  },
+  Generate {
+    options: GenerateOptions,
+    out: Option<String>,
+    drop: bool,
+  },
+}
+
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+  let args: Vec<String> = args.into_iter().collect();
+  let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+  match args.as_slice() {
+    [] | ["serve"] => Ok(Command::Serve),
+    ["migrate", "up", rest @ ..] => Ok(Command::Migrate(MigrateCommand::Up(parse_version(rest)?))),
+    ["migrate", "down", rest @ ..] => Ok(Command::Migrate(MigrateCommand::Down(parse_version(rest)?))),
+    ["migrate", "status"] => Ok(Command::Migrate(MigrateCommand::Status)),
+    ["seed", "load", dir, rest @ ..] => {
+      let options = parse_options(rest, &[], &["--drop"])?;
+      Ok(Command::Seed(SeedCommand::Load {
+        dir: dir.to_string(),
+        drop: options.contains_key("--drop"),
+      }))
+    }
+    ["seed", "validate", dir] => Ok(Command::Seed(SeedCommand::Validate { dir: dir.to_string() })),
+    ["seed", "generate", rest @ ..] => {
+      let options = parse_options(
+        rest,
+        &["--users", "--organizations", "--repositories", "--seed", "--out"],
+        &["--drop"],
+      )?;
+      let number = |name: &str, default: u64| -> Result<u64, String> {
+        match options.get(name) {
+          Some(value) => value.parse().map_err(|_| format!("invalid {} \"{}\"", name, value)),
+          None => Ok(default),
+        }
+      };
+      Ok(Command::Seed(SeedCommand::Generate {
+        options: GenerateOptions {
+          users: number("--users", 1000)? as usize,
+          organizations: number("--organizations", 100)? as usize,
+          repositories: number("--repositories", 5000)? as usize,
+          seed: number("--seed", 0)?,
+        },
+        out: options.get("--out").map(|dir| dir.to_string()),
+        drop: options.contains_key("--drop"),
+      }))
+    }
+    ["check-integrity", rest @ ..] => {
+      let options = parse_options(rest, &[], &["--repair"])?;
+      Ok(Command::CheckIntegrity {
+        repair: options.contains_key("--repair"),
+      })
+    }
+    ["export", dir] => Ok(Command::Export { dir: dir.to_string() }),
+    ["import", dir, rest @ ..] => {
+      let options = parse_options(rest, &[], &["--drop", "--dry-run"])?;
+      Ok(Command::Import {
+        dir: dir.to_string(),
+        drop: options.contains_key("--drop"),
+        dry_run: options.contains_key("--dry-run"),
+      })
+    }
+    ["import-github", dir, rest @ ..] => {
+      let options = parse_options(rest, &[], &["--dry-run"])?;
+      Ok(Command::ImportGithub {
+        dir: dir.to_string(),
+        dry_run: options.contains_key("--dry-run"),
+      })
+    }
+    _ => Err(format!("unknown command \"{}\"", args.join(" "))),
+  }
+}
+
+fn parse_version(args: &[&str]) -> Result<Option<u32>, String> {
+  match args {
+    [] => Ok(None),
+    [version] => version
+      .parse()
+      .map(Some)
+      .map_err(|_| format!("invalid version \"{}\"", version)),
+    _ => Err(format!("unexpected arguments \"{}\"", args.join(" "))),
+  }
+}
+
+/// Parses `--name value` options and `--name` switches, a switch is mapped to an empty value
+fn parse_options<'a>(
+  args: &[&'a str],
+  options: &[&str],
+  switches: &[&str],
+) -> Result<HashMap<&'a str, &'a str>, String> {
+  let mut parsed = HashMap::new();
+  let mut args = args.iter();
+  while let Some(arg) = args.next() {
+    if switches.contains(arg) {
+      parsed.insert(*arg, "");
+    } else if options.contains(arg) {
+      let value = args.next().ok_or(format!("missing the value of {}", arg))?;
+      parsed.insert(*arg, *value);
+    } else {
+      return Err(format!("unexpected argument \"{}\"", arg));
+    }
+  }
+
+  Ok(parsed)
+}
+
+#[actix_web::main]
+pub async fn run(command: Command, config: Config) -> Result<(), String> {
+  start_cli_logging();
+
+  match command {
+    Command::Serve => Err("the serve command is handled by the http server".to_string()),
+    Command::Migrate(command) => migrate(command, &config).await,
+    Command::Seed(command) => seed(command, &config).await,
+    Command::CheckIntegrity { repair } => check_integrity(repair, &config).await,
+    Command::Export { dir } => export(&dir, &config).await,
+    Command::Import { dir, drop, dry_run } => import(&dir, drop, dry_run, &config).await,
+    Command::ImportGithub { dir, dry_run } => import_github(&dir, dry_run, &config).await,
+  }
+}
+
+async fn connect(config: &Config) -> Result<Database, String> {
+  connect_client(config).await.map(|(_, db)| db)
+}
+
+/// The admin commands, like the renames of `import --drop`, need the client of the database
+async fn connect_client(config: &Config) -> Result<(Client, Database), String> {
+  let client = db_client(&config.database, Arc::new(Metrics::new()))
+    .await
+    .map_err(|err| err.to_string())?;
+  let db = client.database(config.database.name.as_str());
+  wait_for_database(&db, &config.database, Some(config.database.retry.max_attempts)).await?;
+  Ok((client, db))
+}
+
+async fn migrate(command: MigrateCommand, config: &Config) -> Result<(), String> {
+  let db = connect(config).await?;
+  let lock_timeout = Duration::from_secs(config.migrations.lock_timeout_secs);
+
+  match command {
+    MigrateCommand::Up(target) => {
+      let applied = migration::up(&db, target, lock_timeout)
+        .await
+        .map_err(|err| err.to_string())?;
+      println!("Applied {} migration(s) {:?}", applied.len(), applied);
+    }
+    MigrateCommand::Down(target) => {
+      let reverted = migration::down(&db, target, lock_timeout)
+        .await
+        .map_err(|err| err.to_string())?;
+      println!("Reverted {} migration(s) {:?}", reverted.len(), reverted);
+    }
+    MigrateCommand::Status => {
+      for status in migration::status(&db).await.map_err(|err| err.to_string())? {
+        let applied_at = match status.applied_at {
+          Some(applied_at) => applied_at.try_to_rfc3339_string().unwrap_or_default(),
+          None => "pending".to_string(),
+        };
+        println!("{:>4}  {:<30}  {}", status.version, status.name, applied_at);
+      }
+    }
+  }
+
+  Ok(())
+}
+
+async fn seed(command: SeedCommand, config: &Config) -> Result<(), String> {
+  let (data, drop) = match command {
+    SeedCommand::Validate { dir } => {
+      let data = seed::load_dir(Path::new(&dir)).map_err(|err| err.to_string())?;
+      return report_issues(&data);
+    }
+    SeedCommand::Load { dir, drop } => {
+      let data = seed::load_dir(Path::new(&dir)).map_err(|err| err.to_string())?;
+      report_issues(&data)?;
+      (data, drop)
+    }
+    SeedCommand::Generate { options, out, drop } => {
+      let data = seed::generate(&options);
+      if let Some(out) = out {
+        seed::write_dir(Path::new(&out), &data).map_err(|err| err.to_string())?;
+        println!(
+          "Wrote {} users, {} organizations and {} repositories to {}",
+          data.users.len(),
+          data.organizations.len(),
+          data.repositories.len(),
+          out
+        );
+        return Ok(());
+      }
+      (data, drop)
+    }
+  };
+
+  match config.store.backend {
+    StoreBackend::Mongo => {
+      let db = connect(config).await?;
+      let inserted = seed::insert(&db, &data, drop).await.map_err(|err| err.to_string())?;
+      for (collection, count) in inserted {
+        println!("Inserted {} documents into {}", count, collection);
+      }
+    }
+    StoreBackend::Sqlite => {
+      let path = Path::new(&config.store.sqlite_path);
+      let mut connection = sqlite::open(path).map_err(|err| err.to_string())?;
+      sqlite::import(&mut connection, &data, drop).map_err(|err| err.to_string())?;
+      println!(
+        "Imported {} users, {} organizations and {} repositories into {}",
+        data.users.len(),
+        data.organizations.len(),
+        data.repositories.len(),
+        config.store.sqlite_path
+      );
+    }
+    StoreBackend::Memory => {
+      return Err("the memory store is filled from store.seed_dir when the server starts".to_string());
+    }
+  }
+
+  Ok(())
+}
+
+fn report_issues(data: &seed::SeedData) -> Result<(), String> {
+  let issues = seed::validate(data);
+  for issue in &issues {
+    eprintln!("{}", issue);
+  }
+
+  match issues.len() {
+    0 => Ok(()),
+    count => Err(format!("the seed has {} issue(s)", count)),
+  }
+}
+
+async fn check_integrity(repair: bool, config: &Config) -> Result<(), String> {
+  let db = connect(config).await?;
+  let data = integrity::load_relations(&db).await.map_err(|err| err.to_string())?;
+  let issues = integrity::find_issues(&data);
+  for issue in &issues {
+    println!("{}", issue);
+  }
+
+  let remaining = match repair {
+    true => {
+      let repaired = integrity::repair_issues(&db, &issues)
+        .await
+        .map_err(|err| err.to_string())?;
+      println!("Repaired {} of {} issue(s)", repaired, issues.len());
+      issues.len() - repaired
+    }
+    false => issues.len(),
+  };
+
+  match remaining {
+    0 => Ok(()),
+    _ if repair => Err(format!("{} issue(s) need to be fixed by hand", remaining)),
+    _ => Err(format!("found {} issue(s), run with --repair to fix them", remaining)),
+  }
+}
+
+async fn export(dir: &str, config: &Config) -> Result<(), String> {
+  let db = connect(config).await?;
+  let manifest = backup::export(&db, Path::new(dir))
+    .await
+    .map_err(|err| err.to_string())?;
+  for (collection, count) in manifest.collections {
+    println!("Exported {} documents of {}", count, collection);
+  }
+
+  Ok(())
+}
+
+async fn import(dir: &str, drop: bool, dry_run: bool, config: &Config) -> Result<(), String> {
+  let dir = Path::new(dir);
+  if dry_run {
+    let manifest = backup::check(dir).map_err(|err| err.to_string())?;
+    println!("The backup of {} is consistent", manifest.created_at);
+    return Ok(());
+  }
+
+  let (client, db) = connect_client(config).await?;
+  let manifest = backup::import(&client, &db, dir, drop)
+    .await
+    .map_err(|err| err.to_string())?;
+  for (collection, count) in manifest.collections {
+    println!("Imported {} documents into {}", count, collection);
+  }
+
+  Ok(())
+}
+
+async fn import_github(dir: &str, dry_run: bool, config: &Config) -> Result<(), String> {
+  let dump = github::load_dir(Path::new(dir)).map_err(|err| err.to_string())?;
+  let ids = match dry_run {
+    true => HashMap::new(),
+    false => github::stored_ids(&connect(config).await?, &dump)
+      .await
+      .map_err(|err| err.to_string())?,
+  };
+  let upserts = github::upserts(&dump, &ids);
+
+  let issues = github::validate(&upserts, &ids);
+  for issue in &issues {
+    eprintln!("{}", issue);
+  }
+  if !issues.is_empty() {
+    return Err(format!("the dump has {} issue(s)", issues.len()));
+  }
+
+  if dry_run {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for upsert in &upserts {
+      *counts.entry(upsert.collection).or_default() += 1;
+    }
+    for collection in backup::COLLECTIONS {
+      println!(
+        "Found {} documents of {}",
+        counts.get(collection).unwrap_or(&0),
+        collection
+      );
+    }
+    return Ok(());
+  }
+
+  let db = connect(config).await?;
+  let counts = github::apply(&db, &upserts).await.map_err(|err| err.to_string())?;
+  for (collection, (created, updated)) in counts {
+    println!(
+      "Created {} and updated {} documents of {}",
+      created, updated, collection
+    );
+  }
+
+  Ok(())
+}
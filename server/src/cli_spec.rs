@@ -0,0 +1,137 @@
+use crate::cli::{parse_args, Command, MigrateCommand, SeedCommand};
+use crate::setup::seed::GenerateOptions;
+use pretty_assertions::assert_eq;
+
+fn args(line: &str) -> Vec<String> {
+  line.split_whitespace().map(String::from).collect()
+}
+
+#[test]
+fn should_serve_without_arguments() {
+  assert_eq!(parse_args(args("")), Ok(Command::Serve));
+  assert_eq!(parse_args(args("serve")), Ok(Command::Serve));
+}
+
+#[test]
+fn should_parse_the_migrate_commands() {
+  assert_eq!(
+    parse_args(args("migrate up")),
+    Ok(Command::Migrate(MigrateCommand::Up(None)))
+  );
+  assert_eq!(
+    parse_args(args("migrate up 3")),
+    Ok(Command::Migrate(MigrateCommand::Up(Some(3))))
+  );
+  assert_eq!(
+    parse_args(args("migrate down")),
+    Ok(Command::Migrate(MigrateCommand::Down(None)))
+  );
+  assert_eq!(
+    parse_args(args("migrate down 0")),
+    Ok(Command::Migrate(MigrateCommand::Down(Some(0))))
+  );
+  assert_eq!(
+    parse_args(args("migrate status")),
+    Ok(Command::Migrate(MigrateCommand::Status))
+  );
+}
+
+#[test]
+fn should_reject_unknown_commands_and_invalid_versions() {
+  assert!(parse_args(args("migrate")).is_err());
+  assert!(parse_args(args("migrate sideways")).is_err());
+  assert!(parse_args(args("migrate up latest")).is_err());
+  assert!(parse_args(args("migrate up 1 2")).is_err());
+}
+
+#[test]
+fn should_parse_the_seed_commands() {
+  assert_eq!(
+    parse_args(args("seed load database_seed --drop")),
+    Ok(Command::Seed(SeedCommand::Load {
+      dir: "database_seed".to_string(),
+      drop: true
+    }))
+  );
+  assert_eq!(
+    parse_args(args("seed validate database_seed")),
+    Ok(Command::Seed(SeedCommand::Validate {
+      dir: "database_seed".to_string()
+    }))
+  );
+  assert_eq!(
+    parse_args(args("seed generate --users 10 --repositories 20 --out /tmp/seed")),
+    Ok(Command::Seed(SeedCommand::Generate {
+      options: GenerateOptions {
+        users: 10,
+        organizations: 100,
+        repositories: 20,
+        seed: 0,
+      },
+      out: Some("/tmp/seed".to_string()),
+      drop: false,
+    }))
+  );
+}
+
+#[test]
+fn should_reject_invalid_seed_options() {
+  assert!(parse_args(args("seed load")).is_err());
+  assert!(parse_args(args("seed load database_seed --force")).is_err());
+  assert!(parse_args(args("seed generate --users")).is_err());
+  assert!(parse_args(args("seed generate --users many")).is_err());
+}
+
+#[test]
+fn should_parse_the_check_integrity_command() {
+  assert_eq!(
+    parse_args(args("check-integrity")),
+    Ok(Command::CheckIntegrity { repair: false })
+  );
+  assert_eq!(
+    parse_args(args("check-integrity --repair")),
+    Ok(Command::CheckIntegrity { repair: true })
+  );
+  assert!(parse_args(args("check-integrity --fix")).is_err());
+}
+
+#[test]
+fn should_parse_the_backup_commands() {
+  assert_eq!(
+    parse_args(args("export backup")),
+    Ok(Command::Export {
+      dir: "backup".to_string()
+    })
+  );
+  assert_eq!(
+    parse_args(args("import backup --drop")),
+    Ok(Command::Import {
+      dir: "backup".to_string(),
+      drop: true,
+      dry_run: false,
+    })
+  );
+  assert_eq!(
+    parse_args(args("import backup --dry-run")),
+    Ok(Command::Import {
+      dir: "backup".to_string(),
+      drop: false,
+      dry_run: true,
+    })
+  );
+  assert!(parse_args(args("export")).is_err());
+  assert!(parse_args(args("import backup --force")).is_err());
+}
+
+#[test]
+fn should_parse_the_github_import_command() {
+  assert_eq!(
+    parse_args(args("import-github dumps --dry-run")),
+    Ok(Command::ImportGithub {
+      dir: "dumps".to_string(),
+      dry_run: true,
+    })
+  );
+  assert!(parse_args(args("import-github")).is_err());
+  assert!(parse_args(args("import-github dumps --drop")).is_err());
+}
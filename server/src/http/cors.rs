@@ -1,13 +1,59 @@
+use crate::setup::config::CorsConfig;
 use actix_cors::Cors;
-use actix_web::http;
-use std::env;
-
-pub fn get_cors() -> Cors {
-  let origins = env::var("CORS_ALLOWED_ORIGINS").unwrap();
-  Cors::default()
-    .allowed_origin(origins.as_str())
-    .allowed_methods(vec!["GET", "POST", "PUT", "DELETE"])
-    .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT])
-    .allowed_header(http::header::CONTENT_TYPE)
-    .max_age(3600)
+
+pub fn get_cors(config: &CorsConfig) -> Cors {
+  let (patterns, origins): (Vec<String>, Vec<String>) = config
+    .allowed_origins
+    .iter()
+    .cloned()
+    .partition(|origin| origin.contains("://*."));
+
+  let cors = origins
+    .iter()
+    .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin))
+    .allowed_methods(config.allowed_methods.iter().map(String::as_str))
+    .allowed_headers(config.allowed_headers.iter().map(String::as_str))
+    .expose_headers(config.exposed_headers.iter().map(String::as_str))
+    .max_age(config.max_age);
+
+  let cors = if patterns.is_empty() {
+    cors
+  } else {
+    cors.allowed_origin_fn(move |origin, _req_head| match origin.to_str() {
+      Ok(origin) => patterns.iter().any(|pattern| origin_matches_pattern(origin, pattern)),
+      Err(_) => false,
+    })
+  };
+
+  if config.supports_credentials {
+    cors.supports_credentials()
+  } else {
+    cors
+  }
+}
+
+/// Matches origins against patterns like `https://*.example.com`, the wildcard stands for one or more subdomains
+pub fn origin_matches_pattern(origin: &str, pattern: &str) -> bool {
+  let (pattern_scheme, pattern_host) = match pattern.split_once("://*.") {
+    Some(parts) => parts,
+    None => return origin == pattern,
+  };
+  let (scheme, host) = match origin.split_once("://") {
+    Some(parts) => parts,
+    None => return false,
+  };
+
+  match host
+    .strip_suffix(pattern_host)
+    .and_then(|prefix| prefix.strip_suffix('.'))
+  {
+    Some(subdomain) => {
+      scheme == pattern_scheme
+        && !subdomain.is_empty()
+        && subdomain
+          .split('.')
+          .all(|label| !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+    }
+    None => false,
+  }
 }
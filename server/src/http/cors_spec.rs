@@ -0,0 +1,92 @@
+use crate::http::cors::{get_cors, origin_matches_pattern};
+use crate::setup::config::CorsConfig;
+use actix_web::{http::header, test, web, App, HttpResponse};
+use pretty_assertions::assert_eq;
+
+fn cors_config() -> CorsConfig {
+  CorsConfig {
+    allowed_origins: vec![
+      "https://example.com".to_string(),
+      "https://*.staging.example.com".to_string(),
+    ],
+    ..CorsConfig::default()
+  }
+}
+
+async fn allowed_origin_of(origin: &str) -> Option<String> {
+  let app = test::init_service(
+    App::new()
+      .wrap(get_cors(&cors_config()))
+      .route("/", web::get().to(HttpResponse::Ok)),
+  )
+  .await;
+  let req = test::TestRequest::get()
+    .insert_header((header::ORIGIN, origin))
+    .to_request();
+  let res = test::call_service(&app, req).await;
+
+  res
+    .headers()
+    .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+    .map(|value| value.to_str().unwrap().to_string())
+}
+
+#[actix_rt::test]
+async fn should_allow_an_exact_origin() {
+  let allowed_origin = allowed_origin_of("https://example.com").await;
+
+  assert_eq!(allowed_origin, Some("https://example.com".to_string()));
+}
+
+#[actix_rt::test]
+async fn should_allow_an_origin_matching_a_subdomain_pattern() {
+  let allowed_origin = allowed_origin_of("https://client.staging.example.com").await;
+
+  assert_eq!(allowed_origin, Some("https://client.staging.example.com".to_string()));
+}
+
+#[actix_rt::test]
+async fn should_not_allow_an_unknown_origin() {
+  let allowed_origin = allowed_origin_of("https://example.org").await;
+
+  assert_eq!(allowed_origin, None);
+}
+
+#[actix_rt::test]
+async fn should_expose_the_configured_headers() {
+  let app = test::init_service(
+    App::new()
+      .wrap(get_cors(&cors_config()))
+      .route("/", web::get().to(HttpResponse::Ok)),
+  )
+  .await;
+  let req = test::TestRequest::get()
+    .insert_header((header::ORIGIN, "https://example.com"))
+    .to_request();
+  let res = test::call_service(&app, req).await;
+  let exposed_headers = res
+    .headers()
+    .get(header::ACCESS_CONTROL_EXPOSE_HEADERS)
+    .unwrap()
+    .to_str()
+    .unwrap()
+    .to_lowercase();
+
+  assert!(exposed_headers.contains("etag"));
+  assert!(exposed_headers.contains("link"));
+}
+
+#[actix_rt::test]
+async fn should_match_origins_against_subdomain_patterns() {
+  let pattern = "https://*.example.com";
+
+  assert_eq!(origin_matches_pattern("https://api.example.com", pattern), true);
+  assert_eq!(origin_matches_pattern("https://a.b.example.com", pattern), true);
+  assert_eq!(origin_matches_pattern("https://example.com", pattern), false);
+  assert_eq!(origin_matches_pattern("http://api.example.com", pattern), false);
+  assert_eq!(
+    origin_matches_pattern("https://api.example.com.evil.com", pattern),
+    false
+  );
+  assert_eq!(origin_matches_pattern("https://evilexample.com", pattern), false);
+}
@@ -0,0 +1,50 @@
+use crate::http::http_handler::HttpError;
+use crate::lib::fields::Fields;
+use actix_web::{dev::Payload, error::InternalError, web, Error, FromRequest, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use serde_json::Value;
+use std::future::{ready, Ready};
+
+#[derive(Deserialize)]
+struct FieldsArgument {
+  fields: Option<String>,
+}
+
+/// Reads `?fields=login,name,avatarUrl`, every field is asked when the parameter is missing
+pub fn fields_of(req: &HttpRequest) -> Result<Fields, String> {
+  let argument = web::Query::<FieldsArgument>::from_query(req.query_string()).map_err(|err| err.to_string())?;
+  match &argument.fields {
+    None => Ok(Fields::default()),
+    Some(fields) => Fields::parse(fields),
+  }
+}
+
+impl FromRequest for Fields {
+  type Error = Error;
+  type Future = Ready<Result<Self, Self::Error>>;
+
+  fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+    ready(fields_of(req).map_err(|message| {
+      let response = HttpResponse::BadRequest().json(HttpError::new(message.clone()));
+      InternalError::from_response(message, response).into()
+    }))
+  }
+}
+
+/// Removes the fields of an object that are not asked, the backends without projections return whole objects
+pub fn retain_fields(fields: &Fields, object: &mut Value) {
+  if let Value::Object(object) = object {
+    object.retain(|name, _| fields.contains(name));
+  }
+}
+
+/// Removes the fields of the nodes of a page that are not asked
+pub fn retain_node_fields(fields: &Fields, connection: &mut Value) {
+  if let Some(edges) = connection.get_mut("edges").and_then(Value::as_array_mut) {
+    for edge in edges {
+      if let Some(node) = edge.get_mut("node") {
+        retain_fields(fields, node);
+      }
+    }
+  }
+}
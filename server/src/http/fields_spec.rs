@@ -0,0 +1,67 @@
+use crate::http::route;
+use crate::model::store::Store;
+use crate::setup::{config::Config, mock};
+use actix_web::{dev::ServiceResponse, test};
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use std::sync::Arc;
+
+async fn call(uri: &str, accept: &str) -> (ServiceResponse, String) {
+  let sufix = mock::random_sufix();
+  let store: Arc<dyn Store> = Arc::new(mock::memory_store(&sufix));
+  let req = test::TestRequest::get()
+    .uri(&uri.replace("{sufix}", &sufix))
+    .insert_header(("Accept", accept));
+
+  (
+    mock::make_store_request(req, route::config_route, store, Config::default()).await,
+    sufix,
+  )
+}
+
+fn keys(object: &Value) -> Vec<&str> {
+  let mut keys: Vec<&str> = object.as_object().unwrap().keys().map(String::as_str).collect();
+  keys.sort();
+  keys
+}
+
+#[actix_rt::test]
+async fn should_answer_only_the_asked_fields_of_an_object() {
+  let (res, sufix) = call("/user/user_foo_{sufix}?fields=login,avatarUrl", "application/json").await;
+  let status = res.status();
+  let user: Value = test::read_body_json(res).await;
+
+  assert_eq!(status, 200);
+  assert_eq!(keys(&user), vec!["_id", "avatarUrl", "login"]);
+  assert_eq!(user["login"], format!("user_foo_{sufix}"));
+}
+
+#[actix_rt::test]
+async fn should_answer_only_the_asked_fields_of_the_nodes_of_a_page() {
+  let (res, _) = call("/user/user_foo_{sufix}/followers?fields=login", "application/json").await;
+  let page: Value = test::read_body_json(res).await;
+  let edges = page["edges"].as_array().unwrap();
+
+  assert_eq!(edges.len(), 2);
+  assert_eq!(keys(&edges[0]["node"]), vec!["_id", "login"]);
+  assert_eq!(keys(&page["pageInfo"]).is_empty(), false);
+}
+
+#[actix_rt::test]
+async fn should_answer_only_the_asked_columns_in_csv() {
+  let (res, _) = call("/user/user_foo_{sufix}/followers?fields=login,avatarUrl", "text/csv").await;
+  let body = test::read_body(res).await;
+  let header = std::str::from_utf8(&body).unwrap().lines().next().unwrap().to_string();
+
+  assert_eq!(header, "cursor,id,login,avatarUrl");
+}
+
+#[actix_rt::test]
+async fn should_answer_bad_request_to_an_invalid_field() {
+  let (res, _) = call("/user/user_foo_{sufix}?fields=login,bad-name", "application/json").await;
+  let status = res.status();
+  let error: Value = test::read_body_json(res).await;
+
+  assert_eq!(status, 400);
+  assert_eq!(error["message"], "Invalid field \"bad-name\"");
+}
@@ -0,0 +1,259 @@
+use crate::http::fields::retain_node_fields;
+use crate::lib::{cursor_connection::CursorConnection, fields::Fields};
+use crate::model::{organization::Organization, repository::Repository, user::User};
+use actix_web::{http::header, HttpRequest};
+use serde::Serialize;
+use std::io;
+
+/// The encodings of a response body, chosen with the `Accept` header of the request
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+  Json,
+  Ndjson,
+  Csv,
+  MessagePack,
+  Cbor,
+}
+impl Format {
+  /// The formats of a single object
+  pub const OBJECT: [Format; 3] = [Format::Json, Format::MessagePack, Format::Cbor];
+  /// The formats of a list, NDJSON streams the whole list instead of a page
+  pub const LIST: [Format; 5] = [
+    Format::Json,
+    Format::Ndjson,
+    Format::Csv,
+    Format::MessagePack,
+    Format::Cbor,
+  ];
+
+  /// The media types of the format, the first one is sent in the `Content-Type`
+  pub fn media_types(&self) -> &'static [&'static str] {
+    match self {
+      Format::Json => &["application/json"],
+      Format::Ndjson => &["application/x-ndjson"],
+      Format::Csv => &["text/csv"],
+      Format::MessagePack => &[
+        "application/msgpack",
+        "application/x-msgpack",
+        "application/vnd.msgpack",
+      ],
+      Format::Cbor => &["application/cbor"],
+    }
+  }
+
+  pub fn content_type(&self) -> &'static str {
+    self.media_types()[0]
+  }
+
+  /// Picks the supported format with the highest quality in the `Accept` header, the exact media types are preferred
+  /// to the wildcards of the same quality and the first supported format matches `*/*`. It is JSON when nothing
+  /// matches.
+  pub fn negotiate(req: &HttpRequest, supported: &[Format]) -> Format {
+    let media_ranges = req
+      .headers()
+      .get_all(header::ACCEPT)
+      .filter_map(|value| value.to_str().ok())
+      .flat_map(|value| value.split(','));
+
+    let mut best: Option<(Format, f32, u8)> = None;
+    for media_range in media_ranges {
+      let mut parameters = media_range.split(';');
+      let range = parameters.next().unwrap_or_default().trim().to_lowercase();
+      let quality = parameters
+        .filter_map(|parameter| parameter.trim().strip_prefix("q="))
+        .find_map(|quality| quality.parse::<f32>().ok())
+        .unwrap_or(1.0);
+      if quality <= 0.0 {
+        continue;
+      }
+
+      let found = supported.iter().find_map(|format| {
+        let specificity = format
+          .media_types()
+          .iter()
+          .filter_map(|media_type| specificity(&range, media_type))
+          .max()?;
+        Some((*format, specificity))
+      });
+      if let Some((format, specificity)) = found {
+        let is_better = match best {
+          None => true,
+          Some((_, best_quality, best_specificity)) => (quality, specificity) > (best_quality, best_specificity),
+        };
+        if is_better {
+          best = Some((format, quality, specificity));
+        }
+      }
+    }
+
+    best.map_or(Format::Json, |(format, _, _)| format)
+  }
+
+  /// Encodes an object, the formats without an object encoding fall back to JSON
+  pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, io::Error> {
+    match self {
+      Format::MessagePack => {
+        rmp_serde::to_vec_named(value).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+      }
+      Format::Cbor => {
+        let mut body = vec![];
+        ciborium::ser::into_writer(value, &mut body)
+          .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        Ok(body)
+      }
+      _ => serde_json::to_vec(value).map_err(io::Error::from),
+    }
+  }
+
+  /// Encodes a page of a list with the asked fields of its nodes, CSV has a line per edge with its cursor and the
+  /// asked columns of the node, `id` included
+  pub fn encode_list<T: Serialize + CsvColumns>(
+    &self,
+    connection: &CursorConnection<T>,
+    fields: &Fields,
+  ) -> Result<Vec<u8>, io::Error> {
+    match self {
+      Format::Csv => {
+        let columns: Vec<usize> = (0..T::HEADER.len())
+          .filter(|index| T::HEADER[*index] == "id" || fields.contains(T::HEADER[*index]))
+          .collect();
+        let mut writer = csv::Writer::from_writer(vec![]);
+        let header = std::iter::once("cursor").chain(columns.iter().map(|index| T::HEADER[*index]));
+        writer.write_record(header)?;
+        for edge in &connection.edges {
+          let mut values = edge.node.columns();
+          let record = std::iter::once(edge.cursor.clone()).chain(
+            columns
+              .iter()
+              .map(|index| escape_formula(std::mem::take(&mut values[*index]))),
+          );
+          writer.write_record(record)?;
+        }
+        writer
+          .into_inner()
+          .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+      }
+      _ if fields.is_all() => self.encode(connection),
+      _ => {
+        let mut connection = serde_json::to_value(connection)?;
+        retain_node_fields(fields, &mut connection);
+        self.encode(&connection)
+      }
+    }
+  }
+}
+
+/// Spreadsheets run the cells starting like a formula, they are prefixed with `'` to be read as text
+fn escape_formula(cell: String) -> String {
+  match cell.chars().next() {
+    Some('=' | '+' | '-' | '@' | '\t' | '\r') => format!("'{}", cell),
+    _ => cell,
+  }
+}
+
+/// `*/*` and `type/*` match with a lower specificity than the media type itself
+fn specificity(range: &str, media_type: &str) -> Option<u8> {
+  match range.split_once('/') {
+    _ if range == media_type => Some(2),
+    Some((range_type, "*")) if range_type == "*" || media_type.split('/').next() == Some(range_type) => {
+      Some(if range_type == "*" { 0 } else { 1 })
+    }
+    _ => None,
+  }
+}
+
+/// The fixed columns of a model in CSV, the nested values are flattened and the missing ones are left empty
+pub trait CsvColumns {
+  const HEADER: &'static [&'static str];
+
+  fn columns(&self) -> Vec<String>;
+}
+
+impl CsvColumns for User {
+  const HEADER: &'static [&'static str] = &[
+    "id",
+    "login",
+    "name",
+    "email",
+    "bio",
+    "avatarUrl",
+    "url",
+    "websiteUrl",
+    "viewerIsFollowing",
+    "isFollowingViewer",
+  ];
+
+  fn columns(&self) -> Vec<String> {
+    vec![
+      self._id.to_hex(),
+      self.login.clone(),
+      optional(&self.name),
+      optional(&self.email.as_ref().map(|email| email.0.clone())),
+      optional(&self.bio),
+      self.avatar_url.clone(),
+      self.url.clone(),
+      optional(&self.website_url),
+      optional(&self.viewer_is_following),
+      optional(&self.is_following_viewer),
+    ]
+  }
+}
+
+impl CsvColumns for Organization {
+  const HEADER: &'static [&'static str] = &[
+    "id",
+    "login",
+    "name",
+    "description",
+    "location",
+    "avatarUrl",
+    "url",
+    "websiteUrl",
+  ];
+
+  fn columns(&self) -> Vec<String> {
+    vec![
+      self._id.to_hex(),
+      self.login.clone(),
+      optional(&self.name),
+      optional(&self.description),
+      optional(&self.location),
+      self.avatar_url.clone(),
+      self.url.clone(),
+      optional(&self.website_url),
+    ]
+  }
+}
+
+impl CsvColumns for Repository {
+  const HEADER: &'static [&'static str] = &[
+    "id",
+    "name",
+    "description",
+    "forkCount",
+    "licenseName",
+    "languageName",
+    "languageColor",
+    "ownerId",
+    "viewerHasStarred",
+  ];
+
+  fn columns(&self) -> Vec<String> {
+    let language = self.primary_language.as_ref();
+    vec![
+      self._id.to_hex(),
+      self.name.clone(),
+      optional(&self.description),
+      self.fork_count.to_string(),
+      optional(&self.license_info.as_ref().map(|license| license.name.clone())),
+      optional(&language.map(|language| language.name.clone())),
+      optional(&language.map(|language| language.color.clone())),
+      self.owner._id.to_hex(),
+      optional(&self.viewer_has_starred),
+    ]
+  }
+}
+
+fn optional<T: ToString>(value: &Option<T>) -> String {
+  value.as_ref().map(ToString::to_string).unwrap_or_default()
+}
@@ -0,0 +1,175 @@
+use crate::http::{format::Format, route};
+use crate::lib::{cursor_connection::CursorConnection, fields::Fields};
+use crate::model::{
+  repository::{Language, Owner, Repository},
+  store::Store,
+  user::User,
+};
+use crate::setup::{config::Config, mock};
+use actix_web::{dev::ServiceResponse, test};
+use mongodb::bson::oid::ObjectId;
+use pretty_assertions::assert_eq;
+use std::sync::Arc;
+
+fn negotiate(accept: &str, supported: &[Format]) -> Format {
+  let req = test::TestRequest::default()
+    .insert_header(("Accept", accept))
+    .to_http_request();
+  Format::negotiate(&req, supported)
+}
+
+async fn call(uri: &str, accept: &str, store: Arc<dyn Store>) -> ServiceResponse {
+  let req = test::TestRequest::get().uri(uri).insert_header(("Accept", accept));
+  mock::make_store_request(req, route::config_route, store, Config::default()).await
+}
+
+#[actix_rt::test]
+async fn should_negotiate_the_format_with_the_accept_header() {
+  assert_eq!(negotiate("", &Format::OBJECT), Format::Json);
+  assert_eq!(negotiate("text/html", &Format::OBJECT), Format::Json);
+  assert_eq!(negotiate("text/csv", &Format::OBJECT), Format::Json);
+  assert_eq!(negotiate("text/csv", &Format::LIST), Format::Csv);
+  assert_eq!(negotiate("application/x-msgpack", &Format::OBJECT), Format::MessagePack);
+  assert_eq!(
+    negotiate("application/cbor;q=0.5, application/msgpack", &Format::OBJECT),
+    Format::MessagePack
+  );
+  assert_eq!(negotiate("application/msgpack;q=0, */*", &Format::OBJECT), Format::Json);
+  assert_eq!(negotiate("*/*, application/cbor", &Format::OBJECT), Format::Cbor);
+  assert_eq!(negotiate("text/*", &Format::LIST), Format::Csv);
+}
+
+#[actix_rt::test]
+async fn should_encode_a_page_as_csv_with_a_line_per_edge() {
+  let id = ObjectId::parse_str("5f1b2c3d4e5f6a7b8c9d0e1f").unwrap();
+  let owner_id = ObjectId::parse_str("5f1b2c3d4e5f6a7b8c9d0e20").unwrap();
+  let repository = Repository {
+    _id: id,
+    description: Some("Tools, \"quoted\"".to_string()),
+    fork_count: 3.0,
+    license_info: None,
+    name: "tux".to_string(),
+    owner: Owner { _id: owner_id },
+    primary_language: Some(Language {
+      color: "#dea584".to_string(),
+      name: "Rust".to_string(),
+    }),
+    viewer_has_starred: Some(true),
+  };
+  let connection = CursorConnection::new(vec![repository], false, false, |item| item._id.to_hex());
+
+  let body = Format::Csv.encode_list(&connection, &Fields::default()).unwrap();
+
+  assert_eq!(
+    String::from_utf8(body).unwrap(),
+    format!(
+      "cursor,id,name,description,forkCount,licenseName,languageName,languageColor,ownerId,viewerHasStarred\n\
+       {},{},tux,\"Tools, \"\"quoted\"\"\",3,,Rust,#dea584,{},true\n",
+      connection.edges[0].cursor, id, owner_id
+    )
+  );
+}
+
+#[actix_rt::test]
+async fn should_answer_a_list_in_csv() {
+  let sufix = mock::random_sufix();
+  let store: Arc<dyn Store> = Arc::new(mock::memory_store(&sufix));
+  let res = call(&format!("/user/user_foo_{sufix}/followers"), "text/csv", store).await;
+  let content_type = res.headers().get("Content-Type").cloned().unwrap();
+  let vary = res.headers().get("Vary").cloned().unwrap();
+  let body = test::read_body(res).await;
+  let lines: Vec<&str> = std::str::from_utf8(&body).unwrap().lines().collect();
+
+  assert_eq!(content_type, "text/csv");
+  assert_eq!(vary, "Accept");
+  assert_eq!(
+    lines[0],
+    "cursor,id,login,name,email,bio,avatarUrl,url,websiteUrl,viewerIsFollowing,isFollowingViewer"
+  );
+  assert_eq!(lines.len(), 3);
+  assert!(lines[1].contains(&format!(",user_bar_{sufix},")));
+}
+
+#[actix_rt::test]
+async fn should_answer_an_object_in_msgpack_and_cbor() {
+  let sufix = mock::random_sufix();
+  let store: Arc<dyn Store> = Arc::new(mock::memory_store(&sufix));
+  let uri = format!("/user/user_foo_{sufix}");
+
+  let res = call(&uri, "application/msgpack", store.clone()).await;
+  let content_type = res.headers().get("Content-Type").cloned().unwrap();
+  let msgpack_user: User = rmp_serde::from_slice(&test::read_body(res).await).unwrap();
+  let res = call(&uri, "application/cbor", store).await;
+  // bson reads an ObjectId from bytes in the binary formats, while it is written as `{ "$oid": ... }` like in JSON
+  let cbor_user: serde_json::Value = ciborium::de::from_reader(&test::read_body(res).await[..]).unwrap();
+
+  assert_eq!(content_type, "application/msgpack");
+  assert_eq!(msgpack_user.login, format!("user_foo_{sufix}"));
+  assert_eq!(cbor_user["login"], format!("user_foo_{sufix}"));
+  assert_eq!(cbor_user["_id"]["$oid"], msgpack_user._id.to_hex());
+}
+
+#[actix_rt::test]
+async fn should_answer_a_page_in_msgpack() {
+  let sufix = mock::random_sufix();
+  let store: Arc<dyn Store> = Arc::new(mock::memory_store(&sufix));
+  let res = call(
+    &format!("/user/user_foo_{sufix}/followers?first=1"),
+    "application/msgpack",
+    store,
+  )
+  .await;
+  let page: CursorConnection<User> = rmp_serde::from_slice(&test::read_body(res).await).unwrap();
+
+  assert_eq!(page.edges.len(), 1);
+  assert_eq!(page.page_info.has_next_page, true);
+}
+
+#[actix_rt::test]
+async fn should_escape_the_csv_cells_read_as_formulas() {
+  let user = User {
+    _id: ObjectId::parse_str("5f1b2c3d4e5f6a7b8c9d0e1f").unwrap(),
+    avatar_url: "https://foo.com/avatar.jpg".to_string(),
+    bio: Some("@here +1".to_string()),
+    email: None,
+    email_visibility: None,
+    login: "foo".to_string(),
+    name: Some("=HYPERLINK(\"http://evil.com\")".to_string()),
+    organizations: None,
+    url: "-2+3".to_string(),
+    website_url: None,
+    viewer_is_following: None,
+    is_following_viewer: None,
+    typename: "User".to_string(),
+  };
+  let connection = CursorConnection::new(vec![user], false, false, |item| item._id.to_hex());
+  let fields = Fields::parse("login,name,bio,url").unwrap();
+
+  let body = Format::Csv.encode_list(&connection, &fields).unwrap();
+
+  assert_eq!(
+    String::from_utf8(body).unwrap().lines().nth(1).unwrap(),
+    format!(
+      "{},5f1b2c3d4e5f6a7b8c9d0e1f,foo,\"'=HYPERLINK(\"\"http://evil.com\"\")\",'@here +1,'-2+3",
+      connection.edges[0].cursor
+    )
+  );
+}
+
+#[actix_rt::test]
+async fn should_link_a_csv_page_to_the_next_one() {
+  let sufix = mock::random_sufix();
+  let store: Arc<dyn Store> = Arc::new(mock::memory_store(&sufix));
+  let uri = format!("/user/user_foo_{sufix}/followers?first=1&fields=login");
+  let res = call(&uri, "text/csv", store.clone()).await;
+  let link = res.headers().get("Link").unwrap().to_str().unwrap().to_string();
+  let next_uri = link.trim_start_matches('<').split('>').next().unwrap().to_string();
+  let last_page = call(&next_uri, "text/csv", store).await;
+  let last_page_link = last_page.headers().get("Link").cloned();
+  let last_page_body = test::read_body(last_page).await;
+
+  assert!(next_uri.starts_with(&format!("/user/user_foo_{sufix}/followers?first=1&fields=login&after=")));
+  assert!(link.ends_with("; rel=\"next\""));
+  assert_eq!(std::str::from_utf8(&last_page_body).unwrap().lines().count(), 2);
+  assert_eq!(last_page_link, None);
+}
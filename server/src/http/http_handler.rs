@@ -1,26 +1,45 @@
-use actix_web::HttpResponse;
+use crate::{
+  http::{
+    fields::{fields_of, retain_fields},
+    format::{CsvColumns, Format},
+    request_id::RequestId,
+  },
+  lib::{
+    cursor_connection::{CursorConnection, PageInfo},
+    redact::redact_emails,
+  },
+};
+use actix_web::{
+  http::header::{self, HeaderValue},
+  HttpRequest, HttpResponse,
+};
 use serde::{Deserialize, Serialize};
+use std::io;
 use tracing;
 
 #[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct HttpError {
   pub message: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub request_id: Option<String>,
 }
 impl HttpError {
   pub fn new(message: String) -> Self {
     Self {
       message: message.to_string(),
+      request_id: RequestId::current().map(|request_id| request_id.to_string()),
     }
   }
 }
 
-pub fn into_response_object<T, E>(result: Result<Option<T>, E>, model_name: &str) -> HttpResponse
+pub fn into_response_object<T, E>(req: &HttpRequest, result: Result<Option<T>, E>, model_name: &str) -> HttpResponse
 where
   T: Serialize + std::fmt::Debug,
   E: std::fmt::Debug,
 {
   if let Err(err) = result {
-    tracing::error!("Internal Server Error: {:#?}", err);
+    tracing::error!("Internal Server Error: {}", redact_emails(&format!("{:#?}", err)));
     let result_error = HttpError::new("Internal Server Error".to_string());
     return HttpResponse::InternalServerError().json(result_error);
   }
@@ -34,20 +53,83 @@ where
   }
 
   let result = result.unwrap();
-  HttpResponse::Ok().json(result)
+  into_response_body(req, &result)
 }
 
-pub fn into_response_list<T, E>(result: Result<T, E>) -> HttpResponse
+pub fn into_response_list<T, E>(req: &HttpRequest, result: Result<CursorConnection<T>, E>) -> HttpResponse
 where
-  T: Serialize + std::fmt::Debug,
+  T: Serialize + CsvColumns + std::fmt::Debug,
   E: std::fmt::Debug,
 {
   if let Err(err) = result {
-    tracing::error!("Internal Server Error: {:#?}", err);
+    tracing::error!("Internal Server Error: {}", redact_emails(&format!("{:#?}", err)));
     let result_error = HttpError::new("Internal Server Error".to_string());
     return HttpResponse::InternalServerError().json(result_error);
   }
 
-  let result = result.unwrap();
-  HttpResponse::Ok().json(result)
+  let format = Format::negotiate(req, &Format::LIST);
+  let fields = fields_of(req).unwrap_or_default();
+  let connection = result.unwrap();
+  let mut res = into_response_encoded(format, format.encode_list(&connection, &fields));
+  // CSV has no room for the page info
+  let link = next_page_link(req, &connection.page_info).and_then(|link| HeaderValue::from_str(&link).ok());
+  if let (Format::Csv, Some(link), true) = (format, link, res.status().is_success()) {
+    res.headers_mut().insert(header::LINK, link);
+  }
+
+  res
+}
+
+/// The `Link` to the next page, the same request after its end cursor
+fn next_page_link(req: &HttpRequest, page_info: &PageInfo) -> Option<String> {
+  let end_cursor = page_info.end_cursor.as_ref().filter(|_| page_info.has_next_page)?;
+  let query = req
+    .query_string()
+    .split('&')
+    .filter(|pair| !pair.is_empty() && !matches!(pair.split('=').next(), Some("after" | "before" | "last")))
+    .map(String::from)
+    .chain(std::iter::once(format!("after={}", percent_encode(end_cursor))))
+    .collect::<Vec<_>>()
+    .join("&");
+
+  Some(format!("<{}?{}>; rel=\"next\"", req.path(), query))
+}
+
+fn percent_encode(value: &str) -> String {
+  value
+    .bytes()
+    .map(|byte| match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+      _ => format!("%{:02X}", byte),
+    })
+    .collect()
+}
+
+/// Responds with the asked fields of an object in the format accepted by the request
+pub fn into_response_body<T: Serialize>(req: &HttpRequest, value: &T) -> HttpResponse {
+  let format = Format::negotiate(req, &Format::OBJECT);
+  let body = match fields_of(req) {
+    Ok(fields) if !fields.is_all() => serde_json::to_value(value)
+      .map_err(io::Error::from)
+      .and_then(|mut value| {
+        retain_fields(&fields, &mut value);
+        format.encode(&value)
+      }),
+    _ => format.encode(value),
+  };
+  into_response_encoded(format, body)
+}
+
+fn into_response_encoded(format: Format, body: Result<Vec<u8>, io::Error>) -> HttpResponse {
+  match body {
+    Ok(body) => HttpResponse::Ok()
+      .content_type(format.content_type())
+      .insert_header((header::VARY, "Accept"))
+      .body(body),
+    Err(err) => {
+      tracing::error!("Internal Server Error: {}", err);
+      let result_error = HttpError::new("Internal Server Error".to_string());
+      HttpResponse::InternalServerError().json(result_error)
+    }
+  }
 }
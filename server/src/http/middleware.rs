@@ -1,6 +1,18 @@
+mod assign_request_id;
+mod conditional_get;
+mod record_metrics;
 mod validate_pagination_arguments;
 
+pub use assign_request_id::AssignRequestId;
+pub use conditional_get::ConditionalGet;
+pub use record_metrics::RecordMetrics;
 pub use validate_pagination_arguments::ValidatePaginationArguments;
 
+#[cfg(test)]
+mod assign_request_id_spec;
+#[cfg(test)]
+mod conditional_get_spec;
+#[cfg(test)]
+mod record_metrics_spec;
 #[cfg(test)]
 mod validate_pagination_arguments_spec;
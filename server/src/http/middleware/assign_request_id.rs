@@ -0,0 +1,71 @@
+use std::future::{ready, Ready};
+
+use actix_web::{
+  dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+  http::header::{HeaderName, HeaderValue},
+  Error, HttpMessage,
+};
+use futures_util::future::LocalBoxFuture;
+
+use crate::http::request_id::{RequestId, REQUEST_ID_HEADER};
+
+/// Reuses the incoming `X-Request-Id` or generates one, stores it in the request extensions, makes it available to
+/// `RequestId::current()` while the request is handled and echoes it in the response headers. It must wrap the
+/// `TracingLogger` so that the root span can record it.
+pub struct AssignRequestId;
+
+impl<S, B> Transform<S, ServiceRequest> for AssignRequestId
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+  S::Future: 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type InitError = ();
+  type Transform = AssignRequestIdMiddleware<S>;
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ready(Ok(AssignRequestIdMiddleware { service }))
+  }
+}
+
+pub struct AssignRequestIdMiddleware<S> {
+  service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for AssignRequestIdMiddleware<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+  S::Future: 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+  forward_ready!(service);
+
+  fn call(&self, req: ServiceRequest) -> Self::Future {
+    let request_id = req
+      .headers()
+      .get(REQUEST_ID_HEADER)
+      .and_then(|value| value.to_str().ok())
+      .and_then(RequestId::from_header)
+      .unwrap_or_else(RequestId::generate);
+    req.extensions_mut().insert(request_id.clone());
+
+    // inner middlewares may build their responses synchronously, so the id is also set while calling them
+    let fut = request_id.clone().sync_scope(|| self.service.call(req));
+    Box::pin(request_id.clone().scope(async move {
+      let mut res = fut.await?;
+      if let Ok(value) = HeaderValue::from_str(request_id.as_str()) {
+        res
+          .headers_mut()
+          .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+      }
+      Ok(res)
+    }))
+  }
+}
@@ -0,0 +1,78 @@
+use crate::http::{http_handler::HttpError, middleware};
+use actix_web::{http::StatusCode, test, web, App, HttpResponse};
+use pretty_assertions::assert_eq;
+
+#[actix_rt::test]
+async fn should_generate_a_request_id_when_the_client_does_not_send_one() {
+  let app = test::init_service(
+    App::new()
+      .route("/", web::get().to(HttpResponse::Ok))
+      .wrap(middleware::AssignRequestId),
+  )
+  .await;
+  let req = test::TestRequest::get().uri("/").to_request();
+  let res = test::call_service(&app, req).await;
+  let request_id = res.headers().get("x-request-id").unwrap().to_str().unwrap();
+
+  assert_eq!(request_id.len(), 36);
+}
+
+#[actix_rt::test]
+async fn should_echo_the_request_id_sent_by_the_client() {
+  let app = test::init_service(
+    App::new()
+      .route("/", web::get().to(HttpResponse::Ok))
+      .wrap(middleware::AssignRequestId),
+  )
+  .await;
+  let req = test::TestRequest::get()
+    .uri("/")
+    .insert_header(("X-Request-Id", "abc-123"))
+    .to_request();
+  let res = test::call_service(&app, req).await;
+  let request_id = res.headers().get("x-request-id").unwrap().to_str().unwrap();
+
+  assert_eq!(request_id, "abc-123");
+}
+
+#[actix_rt::test]
+async fn should_include_the_request_id_in_error_bodies() {
+  let app = test::init_service(
+    App::new()
+      .route(
+        "/",
+        web::get().to(|| async { HttpResponse::BadRequest().json(HttpError::new("Bad request".to_string())) }),
+      )
+      .wrap(middleware::AssignRequestId),
+  )
+  .await;
+  let req = test::TestRequest::get()
+    .uri("/")
+    .insert_header(("X-Request-Id", "abc-123"))
+    .to_request();
+  let res = test::call_service(&app, req).await;
+  let status = res.status();
+  let body: HttpError = test::read_body_json(res).await;
+
+  assert_eq!(status, StatusCode::BAD_REQUEST);
+  assert_eq!(body.request_id, Some("abc-123".to_string()));
+}
+
+#[actix_rt::test]
+async fn should_include_the_request_id_in_errors_built_by_inner_middlewares() {
+  let app = test::init_service(
+    App::new()
+      .route("/", web::get().to(HttpResponse::Ok))
+      .wrap(middleware::ValidatePaginationArguments)
+      .wrap(middleware::AssignRequestId),
+  )
+  .await;
+  let req = test::TestRequest::get()
+    .uri("/?first=1&last=2")
+    .insert_header(("X-Request-Id", "abc-123"))
+    .to_request();
+  let res = test::call_service(&app, req).await;
+  let body: HttpError = test::read_body_json(res).await;
+
+  assert_eq!(body.request_id, Some("abc-123".to_string()));
+}
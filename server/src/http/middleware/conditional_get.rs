@@ -0,0 +1,147 @@
+use std::future::{ready, Ready};
+
+use actix_web::{
+  body::{self, BodySize, BoxBody, MessageBody},
+  dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+  error::ErrorInternalServerError,
+  http::{
+    header::{self, HeaderValue},
+    Method, StatusCode,
+  },
+  web, Error, HttpMessage,
+};
+use futures_util::future::LocalBoxFuture;
+use sha2::{Digest, Sha256};
+
+use crate::{
+  http::{viewer::Viewer, AppState},
+  setup::config::CacheControlConfig,
+};
+
+/// Sets a strong `ETag` computed from the body of the successful GET responses and the `Cache-Control` policy of the
+/// route scope, the response is `304 Not Modified` without body when the tag is in `If-None-Match`. The responses to a
+/// viewer are `private` whatever the policy, and the streamed bodies are sent untouched.
+pub struct ConditionalGet {
+  scope: &'static str,
+}
+
+impl ConditionalGet {
+  pub fn new(scope: &'static str) -> Self {
+    Self { scope }
+  }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ConditionalGet
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+  S::Future: 'static,
+  B: MessageBody + 'static,
+{
+  type Response = ServiceResponse<BoxBody>;
+  type Error = Error;
+  type InitError = ();
+  type Transform = ConditionalGetMiddleware<S>;
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ready(Ok(ConditionalGetMiddleware {
+      service,
+      scope: self.scope,
+    }))
+  }
+}
+
+pub struct ConditionalGetMiddleware<S> {
+  service: S,
+  scope: &'static str,
+}
+
+impl<S, B> Service<ServiceRequest> for ConditionalGetMiddleware<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+  S::Future: 'static,
+  B: MessageBody + 'static,
+{
+  type Response = ServiceResponse<BoxBody>;
+  type Error = Error;
+  type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+  forward_ready!(service);
+
+  fn call(&self, req: ServiceRequest) -> Self::Future {
+    let is_get = req.method() == Method::GET;
+    let if_none_match = req
+      .headers()
+      .get(header::IF_NONE_MATCH)
+      .and_then(|value| value.to_str().ok())
+      .map(String::from);
+    let cache_control = match req.app_data::<web::Data<AppState>>() {
+      Some(state) => state.config.cache_control.policy(self.scope).to_string(),
+      None => CacheControlConfig::default().policy(self.scope).to_string(),
+    };
+
+    let fut = self.service.call(req);
+    Box::pin(async move {
+      let res = fut.await?;
+      if !is_get || res.status() != StatusCode::OK || matches!(res.response().body().size(), BodySize::Stream) {
+        return Ok(res.map_into_boxed_body());
+      }
+
+      // the viewer sees their private email and their relations with the other users
+      let cache_control = match res.request().extensions().get::<Viewer>() {
+        Some(_) => private_policy(&cache_control),
+        None => cache_control,
+      };
+      let (req, res) = res.into_parts();
+      let (mut res, body) = res.into_parts();
+      let body = body::to_bytes(body)
+        .await
+        .map_err(|err| ErrorInternalServerError(Into::<Box<dyn std::error::Error>>::into(err).to_string()))?;
+      let etag = strong_etag(&body);
+
+      let mut res = if matches!(&if_none_match, Some(tags) if matches_etag(tags, &etag)) {
+        *res.status_mut() = StatusCode::NOT_MODIFIED;
+        res.headers_mut().remove(header::CONTENT_TYPE);
+        res.set_body(BoxBody::new(()))
+      } else {
+        res.set_body(BoxBody::new(body))
+      };
+      let headers = res.headers_mut();
+      if let Ok(etag) = HeaderValue::from_str(&etag) {
+        headers.insert(header::ETAG, etag);
+      }
+      if let Ok(cache_control) = HeaderValue::from_str(&cache_control) {
+        headers.insert(header::CACHE_CONTROL, cache_control);
+      }
+
+      Ok(ServiceResponse::new(req, res))
+    })
+  }
+}
+
+/// A quoted tag of the first 128 bits of the SHA-256 of the body, the encodings of the same object have their own tag
+pub fn strong_etag(body: &[u8]) -> String {
+  let digest = Sha256::digest(body);
+  format!("\"{}\"", base64::encode_config(&digest[..16], base64::URL_SAFE_NO_PAD))
+}
+
+/// `If-None-Match` is a list of tags or `*`, compared with the weak comparison as required for GET
+fn matches_etag(if_none_match: &str, etag: &str) -> bool {
+  if_none_match
+    .split(',')
+    .map(|tag| tag.trim())
+    .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// Replaces the directives meant for the shared caches with `private`
+fn private_policy(policy: &str) -> String {
+  let directives = policy.split(',').map(str::trim).filter(|directive| {
+    let name = directive.split('=').next().unwrap_or_default().to_ascii_lowercase();
+    !directive.is_empty() && !matches!(name.as_str(), "public" | "private" | "s-maxage" | "proxy-revalidate")
+  });
+
+  std::iter::once("private")
+    .chain(directives)
+    .collect::<Vec<_>>()
+    .join(", ")
+}
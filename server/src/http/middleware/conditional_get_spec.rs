@@ -0,0 +1,121 @@
+use crate::http::{format::Format, middleware::conditional_get::strong_etag, route, viewer::Viewer};
+use crate::model::store::Store;
+use crate::setup::{config::Config, mock};
+use actix_web::{dev::ServiceResponse, http::StatusCode, test};
+use mongodb::bson::oid::ObjectId;
+use pretty_assertions::assert_eq;
+use std::sync::Arc;
+
+async fn call(req: test::TestRequest, store: Arc<dyn Store>) -> ServiceResponse {
+  call_as(req, None, store).await
+}
+
+async fn call_as(req: test::TestRequest, viewer: Option<Viewer>, store: Arc<dyn Store>) -> ServiceResponse {
+  let mut config = Config::default();
+  config.cache_control.profile = Some("public, max-age=60, s-maxage=300".to_string());
+
+  mock::make_viewer_request(req, viewer, route::config_route, store, config).await
+}
+
+fn header(res: &ServiceResponse, name: &str) -> Option<String> {
+  res.headers().get(name).map(|value| value.to_str().unwrap().to_string())
+}
+
+#[actix_rt::test]
+async fn should_tag_a_profile_with_the_hash_of_its_body() {
+  let sufix = mock::random_sufix();
+  let store: Arc<dyn Store> = Arc::new(mock::memory_store(&sufix));
+  let req = test::TestRequest::get().uri(&format!("/profile/user_foo_{sufix}"));
+  let res = call(req, store.clone()).await;
+  let etag = header(&res, "ETag");
+  let cache_control = header(&res, "Cache-Control");
+  let body = test::read_body(res).await;
+  let req = test::TestRequest::get().uri(&format!("/user/user_foo_{sufix}"));
+  let user_cache_control = header(&call(req, store).await, "Cache-Control");
+
+  assert_eq!(etag, Some(strong_etag(&body)));
+  assert_eq!(cache_control, Some("public, max-age=60, s-maxage=300".to_string()));
+  assert_eq!(user_cache_control, Some("no-cache".to_string()));
+}
+
+#[actix_rt::test]
+async fn should_answer_not_modified_when_the_tag_matches() {
+  let sufix = mock::random_sufix();
+  let store: Arc<dyn Store> = Arc::new(mock::memory_store(&sufix));
+  let uri = format!("/profile/user_foo_{sufix}");
+  let etag = header(&call(test::TestRequest::get().uri(&uri), store.clone()).await, "ETag").unwrap();
+  let conditional = |if_none_match: String| {
+    test::TestRequest::get()
+      .uri(&uri)
+      .insert_header(("If-None-Match", if_none_match))
+  };
+
+  let res = call(conditional(etag.clone()), store.clone()).await;
+  let status = res.status();
+  let not_modified_etag = header(&res, "ETag");
+  let body = test::read_body(res).await;
+  let weak = call(conditional(format!("\"other\", W/{etag}")), store.clone()).await;
+  let any = call(conditional("*".to_string()), store.clone()).await;
+  let other = call(conditional("\"other\"".to_string()), store).await;
+
+  assert_eq!(status, StatusCode::NOT_MODIFIED);
+  assert_eq!(not_modified_etag, Some(etag));
+  assert_eq!(body.len(), 0);
+  assert_eq!(weak.status(), StatusCode::NOT_MODIFIED);
+  assert_eq!(any.status(), StatusCode::NOT_MODIFIED);
+  assert_eq!(other.status(), StatusCode::OK);
+}
+
+#[actix_rt::test]
+async fn should_tag_each_format_of_an_object() {
+  let sufix = mock::random_sufix();
+  let store: Arc<dyn Store> = Arc::new(mock::memory_store(&sufix));
+  let uri = format!("/user/user_foo_{sufix}");
+  let json = call(test::TestRequest::get().uri(&uri), store.clone()).await;
+  let req = test::TestRequest::get()
+    .uri(&uri)
+    .insert_header(("Accept", "application/cbor"))
+    .insert_header(("If-None-Match", header(&json, "ETag").unwrap()));
+  let cbor = call(req, store).await;
+
+  assert_eq!(cbor.status(), StatusCode::OK);
+  assert_eq!(header(&cbor, "Vary"), Some("Accept".to_string()));
+  assert!(header(&cbor, "ETag").is_some());
+  assert!(header(&cbor, "ETag") != header(&json, "ETag"));
+}
+
+#[actix_rt::test]
+async fn should_not_tag_the_errors_and_the_streams() {
+  let sufix = mock::random_sufix();
+  let store: Arc<dyn Store> = Arc::new(mock::memory_store(&sufix));
+  let not_found = call(test::TestRequest::get().uri("/profile/nobody"), store.clone()).await;
+  let req = test::TestRequest::get()
+    .uri(&format!("/user/user_foo_{sufix}/followers"))
+    .insert_header(("Accept", Format::Ndjson.content_type()));
+  let stream = call(req, store).await;
+
+  assert_eq!(not_found.status(), StatusCode::NOT_FOUND);
+  assert_eq!(header(&not_found, "ETag"), None);
+  assert_eq!(stream.status(), StatusCode::OK);
+  assert_eq!(header(&stream, "ETag"), None);
+}
+
+#[actix_rt::test]
+async fn should_keep_the_responses_to_a_viewer_out_of_the_shared_caches() {
+  let sufix = mock::random_sufix();
+  let store: Arc<dyn Store> = Arc::new(mock::memory_store(&sufix));
+  let viewer = Viewer {
+    _id: ObjectId::new(),
+    login: format!("user_bar_{sufix}"),
+  };
+  let req = test::TestRequest::get().uri(&format!("/profile/user_foo_{sufix}"));
+  let profile = call_as(req, Some(viewer.clone()), store.clone()).await;
+  let req = test::TestRequest::get().uri(&format!("/user/user_foo_{sufix}"));
+  let user = call_as(req, Some(viewer), store).await;
+
+  assert_eq!(
+    header(&profile, "Cache-Control"),
+    Some("private, max-age=60".to_string())
+  );
+  assert_eq!(header(&user, "Cache-Control"), Some("private, no-cache".to_string()));
+}
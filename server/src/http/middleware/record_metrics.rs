@@ -0,0 +1,110 @@
+use std::{
+  future::{ready, Ready},
+  sync::Arc,
+  time::Instant,
+};
+
+use actix_web::{
+  dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+  Error,
+};
+use futures_util::future::LocalBoxFuture;
+use prometheus::IntGauge;
+
+use crate::setup::metrics::Metrics;
+
+/// Records the request count, latency and in-flight gauge labeled by the matched route pattern, so that
+/// `/user/foo` and `/user/bar` are both reported as `/user/{login}`
+pub struct RecordMetrics {
+  metrics: Arc<Metrics>,
+}
+
+impl RecordMetrics {
+  pub fn new(metrics: Arc<Metrics>) -> Self {
+    Self { metrics }
+  }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RecordMetrics
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+  S::Future: 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type InitError = ();
+  type Transform = RecordMetricsMiddleware<S>;
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ready(Ok(RecordMetricsMiddleware {
+      service,
+      metrics: self.metrics.clone(),
+    }))
+  }
+}
+
+pub struct RecordMetricsMiddleware<S> {
+  service: S,
+  metrics: Arc<Metrics>,
+}
+
+impl<S, B> Service<ServiceRequest> for RecordMetricsMiddleware<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+  S::Future: 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+  forward_ready!(service);
+
+  fn call(&self, req: ServiceRequest) -> Self::Future {
+    let metrics = self.metrics.clone();
+    let method = req.method().to_string();
+    let started_at = Instant::now();
+    let in_flight = InFlight::new(metrics.http_requests_in_flight.clone());
+
+    let fut = self.service.call(req);
+    Box::pin(async move {
+      let res = fut.await;
+      drop(in_flight);
+
+      let (route, status) = match &res {
+        Ok(res) => (
+          res.request().match_pattern().unwrap_or_else(|| "unmatched".to_string()),
+          res.status().as_u16().to_string(),
+        ),
+        Err(err) => (
+          "unmatched".to_string(),
+          err.as_response_error().status_code().as_u16().to_string(),
+        ),
+      };
+      let labels = [method.as_str(), route.as_str(), status.as_str()];
+      metrics.http_requests_total.with_label_values(&labels).inc();
+      metrics
+        .http_request_duration_seconds
+        .with_label_values(&labels)
+        .observe(started_at.elapsed().as_secs_f64());
+
+      res
+    })
+  }
+}
+
+/// Keeps the in-flight gauge right even when the request future is dropped before completing
+struct InFlight(IntGauge);
+impl InFlight {
+  fn new(gauge: IntGauge) -> Self {
+    gauge.inc();
+    Self(gauge)
+  }
+}
+impl Drop for InFlight {
+  fn drop(&mut self) {
+    self.0.dec();
+  }
+}
@@ -0,0 +1,46 @@
+use crate::http::middleware;
+use crate::setup::metrics::Metrics;
+use actix_web::{test, web, App, HttpResponse};
+use pretty_assertions::assert_eq;
+use std::sync::Arc;
+
+#[actix_rt::test]
+async fn should_count_requests_by_route_pattern_and_status() {
+  let metrics = Arc::new(Metrics::new());
+  let app = test::init_service(
+    App::new()
+      .route("/user/{login}", web::get().to(HttpResponse::Ok))
+      .wrap(middleware::RecordMetrics::new(metrics.clone())),
+  )
+  .await;
+
+  for uri in ["/user/foo", "/user/bar"] {
+    let req = test::TestRequest::get().uri(uri).to_request();
+    test::call_service(&app, req).await;
+  }
+
+  let counter = metrics
+    .http_requests_total
+    .with_label_values(&["GET", "/user/{login}", "200"]);
+  assert_eq!(counter.get(), 2);
+  assert_eq!(metrics.http_requests_in_flight.get(), 0);
+}
+
+#[actix_rt::test]
+async fn should_label_unmatched_requests_without_the_path() {
+  let metrics = Arc::new(Metrics::new());
+  let app = test::init_service(
+    App::new()
+      .route("/user/{login}", web::get().to(HttpResponse::Ok))
+      .wrap(middleware::RecordMetrics::new(metrics.clone())),
+  )
+  .await;
+
+  let req = test::TestRequest::get().uri("/unknown/path").to_request();
+  test::call_service(&app, req).await;
+
+  let counter = metrics
+    .http_requests_total
+    .with_label_values(&["GET", "unmatched", "404"]);
+  assert_eq!(counter.get(), 1);
+}
@@ -6,7 +6,10 @@ use actix_web::{
 };
 use futures_util::future::LocalBoxFuture;
 
-use crate::{http::http_handler::HttpError, lib::cursor_connection::PaginationArguments};
+use crate::{
+  http::{http_handler::HttpError, AppState},
+  lib::cursor_connection::PaginationArguments,
+};
 
 pub struct ValidatePaginationArguments;
 
@@ -61,6 +64,8 @@ where
       }
     }
 
+    observe_page_size(&req);
+
     let fut = self.service.call(req);
     Box::pin(async move {
       let res = fut.await?;
@@ -68,3 +73,14 @@ where
     })
   }
 }
+
+fn observe_page_size(req: &ServiceRequest) {
+  let query = req.uri().query().unwrap_or("");
+  let pagination_arguments = web::Query::<PaginationArguments>::from_query(query);
+
+  if let (Some(state), Ok(pagination_arguments)) = (req.app_data::<web::Data<AppState>>(), pagination_arguments) {
+    if let Ok((direction, page_size, _)) = pagination_arguments.into_inner().parse_args() {
+      state.metrics.observe_page_size(&direction, page_size);
+    }
+  }
+}
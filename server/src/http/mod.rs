@@ -1,48 +1,174 @@
 mod cors;
+pub mod fields;
+pub mod format;
 pub mod http_handler;
 mod middleware;
+pub mod ndjson;
+pub mod request_id;
+mod root_span;
 mod route;
+pub mod viewer;
 
-use crate::http::cors::get_cors;
-use crate::setup::{db::db_client_connection, tracing::start_tracing};
-use actix_web::{web, App, HttpServer};
-use std::env;
+use crate::http::{
+  cors::get_cors,
+  middleware::{AssignRequestId, RecordMetrics},
+  root_span::RootSpan,
+};
+use crate::model::store::{invalidate_on_changes, CachedStore, QueryCache, Store};
+use crate::setup::{
+  config::{Config, StoreBackend},
+  db::{db_client, open_store, wait_for_database},
+  lifecycle::{shutdown_gracefully, wait_for_termination, Lifecycle},
+  metrics::Metrics,
+  migration::migrate_at_startup,
+  schema::prepare_database,
+  tracing::{start_tracing, LogFilterHandle},
+};
+use actix_web::{rt, web, App, HttpServer};
+use std::{io, sync::Arc, time::Duration};
+use tokio::time;
 use tracing;
 use tracing_actix_web::TracingLogger;
 
 pub struct AppState {
   pub(crate) db: mongodb::Database,
+  pub(crate) store: Arc<dyn Store>,
+  pub(crate) cache: Option<Arc<QueryCache>>,
+  pub(crate) config: Config,
+  pub(crate) metrics: Arc<Metrics>,
+  pub(crate) log_filter: Option<LogFilterHandle>,
+  pub(crate) lifecycle: Arc<Lifecycle>,
 }
 
 #[actix_web::main]
-pub async fn main() -> std::io::Result<()> {
-  start_tracing();
+pub async fn main(config: Config) -> std::io::Result<()> {
+  let log_filter = start_tracing(&config.tracing);
+  config.report();
 
-  let db = db_client_connection().await.unwrap();
-  let server_uri = format!(
-    "{}:{}",
-    env::var("SERVER_HOST").unwrap(),
-    env::var("SERVER_PORT").unwrap()
-  );
+  let metrics = Arc::new(Metrics::new());
+  let client = db_client(&config.database, metrics.clone())
+    .await
+    .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+  let db = client.database(config.database.name.as_str());
+  let mut background_tasks = vec![];
+  if config.store.backend == StoreBackend::Mongo {
+    let max_attempts = Some(config.database.retry.max_attempts);
+    match wait_for_database(&db, &config.database, max_attempts).await {
+      Ok(_) => {
+        migrate_at_startup(&db, &config.migrations, &config.database.retry)
+          .await
+          .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        prepare_database(&db, &config.schema).await;
+      }
+      Err(_) if config.database.start_degraded => {
+        tracing::warn!("Starting without the database, the readiness probe fails until it is reachable");
+        background_tasks.push(rt::spawn({
+          let (db, config) = (db.clone(), config.clone());
+          async move {
+            if wait_for_database(&db, &config.database, None).await.is_ok() {
+              if let Err(err) = migrate_at_startup(&db, &config.migrations, &config.database.retry).await {
+                tracing::error!("Migrations fail with the error\n{}", err);
+              }
+              prepare_database(&db, &config.schema).await;
+            }
+          }
+        }));
+      }
+      Err(err) => return Err(io::Error::new(io::ErrorKind::NotConnected, err)),
+    }
+  }
+  let store = open_store(&config.store, &db).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+  let cache = config
+    .cache
+    .enabled
+    .then(|| Arc::new(QueryCache::new(&config.cache, metrics.clone())));
+  let store: Arc<dyn Store> = match &cache {
+    Some(cache) => {
+      if config.cache.change_streams {
+        background_tasks.push(rt::spawn(invalidate_on_changes(
+          db.clone(),
+          cache.clone(),
+          config.database.retry.clone(),
+        )));
+      }
+      Arc::new(CachedStore::new(store, cache.clone()))
+    }
+    None => store,
+  };
+  let server_uri = config.server_uri();
+  let state = web::Data::new(AppState {
+    db,
+    store,
+    cache,
+    config: config.clone(),
+    metrics,
+    log_filter: Some(log_filter),
+    lifecycle: Arc::new(Lifecycle::default()),
+  });
 
   tracing::info!("Web server REST started at {}", server_uri);
-  HttpServer::new(move || {
-    App::new()
-      .wrap(get_cors())
-      .app_data(web::Data::new(AppState { db: db.clone() }))
-      .configure(route::config_route)
-      .wrap(TracingLogger::default())
-      .default_service(route::not_found())
+  let server = HttpServer::new({
+    let state = state.clone();
+    move || {
+      App::new()
+        .wrap(get_cors(&state.config.cors))
+        .app_data(state.clone())
+        .configure(route::config_route)
+        .wrap(RecordMetrics::new(state.metrics.clone()))
+        .wrap(TracingLogger::<RootSpan>::new())
+        .wrap(AssignRequestId)
+        .default_service(route::not_found())
+    }
   })
+  .shutdown_timeout(config.shutdown.timeout_secs)
+  .disable_signals()
   .bind(server_uri)?
-  .run()
-  .await
-  .unwrap();
+  .run();
 
+  rt::spawn({
+    let (server, lifecycle, shutdown) = (server.handle(), state.lifecycle.clone(), config.shutdown.clone());
+    async move {
+      wait_for_termination().await;
+      shutdown_gracefully(server, lifecycle, shutdown).await;
+    }
+  });
+  server.await?;
+  tracing::info!("Web server stopped");
+
+  // the change stream and the sessions of the background tasks would keep the client from shutting down
+  for task in background_tasks {
+    task.abort();
+    let _ = task.await;
+  }
+  drop(state);
+  let shutdown_timeout = Duration::from_secs(config.shutdown.timeout_secs);
+  match time::timeout(shutdown_timeout, client.shutdown()).await {
+    Ok(_) => tracing::info!("Database connections closed"),
+    Err(_) => tracing::warn!(
+      "Database operations still running after {}s, closing their connections",
+      config.shutdown.timeout_secs
+    ),
+  }
+
+  // flushes the spans still waiting in the batch exporter
   opentelemetry::global::shutdown_tracer_provider();
 
   Ok(())
 }
 
+#[cfg(test)]
+mod cors_spec;
+#[cfg(test)]
+mod fields_spec;
+#[cfg(test)]
+mod format_spec;
+#[cfg(test)]
+mod ndjson_spec;
+#[cfg(test)]
+mod request_id_spec;
+#[cfg(test)]
+mod root_span_spec;
 #[cfg(test)]
 mod route_spec;
+#[cfg(test)]
+mod viewer_spec;
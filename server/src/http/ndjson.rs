@@ -0,0 +1,77 @@
+use crate::http::{fields::retain_fields, format::Format, http_handler::HttpError, AppState};
+use crate::lib::{cursor_connection::PaginationArguments, fields::Fields, redact::redact_emails};
+use crate::model::store::List;
+use actix_web::{rt, web, web::Bytes, HttpRequest, HttpResponse};
+use mongodb::bson::oid::ObjectId;
+use std::io;
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use tracing::{self, Instrument};
+
+/// Whether NDJSON is the preferred format of a list for the request
+pub fn accepts_ndjson(req: &HttpRequest) -> bool {
+  Format::negotiate(req, &Format::LIST) == Format::Ndjson
+}
+
+/// Streams a whole list with one edge per line, `{"cursor":"...","node":{...}}`, with the asked fields of the nodes.
+/// The list starts after the `after` cursor and has at most `first` items, capped by `stream.max_items`. The items are
+/// read by a task that waits while the buffer of the client is full and stops when the client goes away.
+pub fn into_response_stream(
+  state: web::Data<AppState>,
+  list: List,
+  pagination_arguments: PaginationArguments,
+  viewer_id: Option<ObjectId>,
+  fields: Fields,
+) -> HttpResponse {
+  if pagination_arguments.last.is_some() || pagination_arguments.before.is_some() {
+    let result_error = HttpError::new("A stream is read forward, with first and after".to_string());
+    return HttpResponse::BadRequest().json(result_error);
+  }
+
+  let max_items = state.config.stream.max_items;
+  let limit = pagination_arguments
+    .first
+    .map_or(max_items, |first| first.min(max_items));
+  // MongoDB refuses `$limit: 0`, and only once the headers are sent
+  if limit == 0 {
+    return HttpResponse::Ok().content_type(Format::Ndjson.content_type()).finish();
+  }
+  let (sender, receiver) = mpsc::channel::<Result<Bytes, io::Error>>(state.config.stream.buffer_size);
+
+  let task = async move {
+    let mut items = state
+      .store
+      .stream_list(&list, pagination_arguments.after, limit, &viewer_id, &fields);
+    let mut count: u32 = 0;
+    while let Some(item) = items.next().await {
+      let line = match item {
+        Ok(edge) => {
+          let mut edge = serde_json::to_value(&edge).unwrap();
+          retain_fields(&fields, &mut edge["node"]);
+          let mut line = serde_json::to_vec(&edge).unwrap();
+          line.push(b'\n');
+          Ok(Bytes::from(line))
+        }
+        Err(err) => {
+          tracing::error!("Stream interrupted: {}", redact_emails(&format!("{:#?}", err)));
+          Err(io::Error::new(io::ErrorKind::Interrupted, "Internal Server Error"))
+        }
+      };
+      let failed = line.is_err();
+      if sender.send(line).await.is_err() {
+        tracing::info!(count, "Stream closed by the client");
+        return;
+      }
+      if failed {
+        return;
+      }
+      count += 1;
+    }
+    tracing::info!(count, limit, "Stream completed");
+  };
+  rt::spawn(task.instrument(tracing::Span::current()));
+
+  HttpResponse::Ok()
+    .content_type(Format::Ndjson.content_type())
+    .streaming(ReceiverStream::new(receiver))
+}
@@ -0,0 +1,106 @@
+use crate::http::{format::Format, ndjson::accepts_ndjson, route};
+use crate::lib::cursor_connection::Edges;
+use crate::model::{store::Store, user::User};
+use crate::setup::{config::Config, mock};
+use actix_web::{dev::ServiceResponse, http::StatusCode, test};
+use pretty_assertions::assert_eq;
+use std::sync::Arc;
+
+async fn call(uri: &str, accept: &str, max_items: u32, store: Arc<dyn Store>) -> ServiceResponse {
+  let mut config = Config::default();
+  config.stream.max_items = max_items;
+  let req = test::TestRequest::get().uri(uri).insert_header(("Accept", accept));
+
+  mock::make_store_request(req, route::config_route, store, config).await
+}
+
+async fn read_lines(res: ServiceResponse) -> Vec<Edges<User>> {
+  let body = test::read_body(res).await;
+  std::str::from_utf8(&body)
+    .unwrap()
+    .lines()
+    .map(|line| serde_json::from_str(line).unwrap())
+    .collect()
+}
+
+#[actix_rt::test]
+async fn should_stream_a_list_with_one_edge_per_line() {
+  let sufix = mock::random_sufix();
+  let store: Arc<dyn Store> = Arc::new(mock::memory_store(&sufix));
+  let uri = format!("/user/user_foo_{sufix}/followers");
+  let res = call(&uri, Format::Ndjson.content_type(), 100, store.clone()).await;
+  let status = res.status();
+  let content_type = res.headers().get("Content-Type").cloned().unwrap();
+  let lines = read_lines(res).await;
+
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(content_type, Format::Ndjson.content_type());
+  assert_eq!(
+    lines.iter().map(|edge| edge.node.login.clone()).collect::<Vec<_>>(),
+    vec![format!("user_bar_{sufix}"), format!("user_dee_{sufix}")]
+  );
+}
+
+#[actix_rt::test]
+async fn should_cap_a_stream_and_continue_it_after_the_last_cursor() {
+  let sufix = mock::random_sufix();
+  let store: Arc<dyn Store> = Arc::new(mock::memory_store(&sufix));
+  let uri = format!("/user/user_foo_{sufix}/followers");
+  let first_lines = read_lines(call(&uri, Format::Ndjson.content_type(), 1, store.clone()).await).await;
+  let uri = format!("{uri}?after={}", first_lines[0].cursor);
+  let next_lines = read_lines(call(&uri, Format::Ndjson.content_type(), 1, store.clone()).await).await;
+
+  assert_eq!(first_lines.len(), 1);
+  assert_eq!(first_lines[0].node.login, format!("user_bar_{sufix}"));
+  assert_eq!(next_lines.len(), 1);
+  assert_eq!(next_lines[0].node.login, format!("user_dee_{sufix}"));
+}
+
+#[actix_rt::test]
+async fn should_send_an_empty_stream_for_zero_items() {
+  let sufix = mock::random_sufix();
+  let store: Arc<dyn Store> = Arc::new(mock::memory_store(&sufix));
+  let uri = format!("/user/user_foo_{sufix}/followers?first=0");
+  let res = call(&uri, Format::Ndjson.content_type(), 100, store.clone()).await;
+  let status = res.status();
+  let lines = read_lines(res).await;
+
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(lines.len(), 0);
+}
+
+#[actix_rt::test]
+async fn should_reject_a_backward_stream() {
+  let sufix = mock::random_sufix();
+  let store: Arc<dyn Store> = Arc::new(mock::memory_store(&sufix));
+  let uri = format!("/user/user_foo_{sufix}/followers?last=1");
+  let res = call(&uri, Format::Ndjson.content_type(), 100, store.clone()).await;
+
+  assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_rt::test]
+async fn should_keep_the_json_page_for_other_media_types() {
+  let sufix = mock::random_sufix();
+  let store: Arc<dyn Store> = Arc::new(mock::memory_store(&sufix));
+  let uri = format!("/user/user_foo_{sufix}/followers");
+  let res = call(&uri, "application/json", 100, store.clone()).await;
+
+  assert_eq!(res.headers().get("Content-Type").unwrap(), "application/json");
+}
+
+#[actix_rt::test]
+async fn should_find_ndjson_among_the_accepted_media_types() {
+  let accepts = |accept: &str| {
+    accepts_ndjson(
+      &test::TestRequest::default()
+        .insert_header(("Accept", accept))
+        .to_http_request(),
+    )
+  };
+
+  assert!(accepts("application/x-ndjson"));
+  assert!(accepts("application/json;q=0.5, Application/X-NDJSON; q=1"));
+  assert!(!accepts("application/json"));
+  assert!(!accepts("*/*"));
+}
@@ -0,0 +1,69 @@
+use actix_web::{dev::Payload, error::ErrorInternalServerError, Error, FromRequest, HttpMessage, HttpRequest};
+use std::future::{ready, Ready};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+  static CURRENT_REQUEST_ID: RequestId;
+}
+
+/// Identifies a request across logs, traces and error bodies. It is inserted into the request extensions by the
+/// `AssignRequestId` middleware, either reusing the `X-Request-Id` sent by the client or generating a new one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestId(String);
+
+impl RequestId {
+  pub fn generate() -> Self {
+    Self(Uuid::new_v4().to_string())
+  }
+
+  /// Accepts ids sent by clients and proxies as long as they can be safely written to logs and headers
+  pub fn from_header(value: &str) -> Option<Self> {
+    let is_valid = !value.is_empty()
+      && value.len() <= MAX_REQUEST_ID_LENGTH
+      && value
+        .chars()
+        .all(|char| char.is_ascii_alphanumeric() || ['-', '_', '.'].contains(&char));
+
+    match is_valid {
+      true => Some(Self(value.to_string())),
+      false => None,
+    }
+  }
+
+  pub fn as_str(&self) -> &str {
+    &self.0
+  }
+
+  /// Makes the id available to `current()` while the request is handled
+  pub async fn scope<F: std::future::Future>(self, future: F) -> F::Output {
+    CURRENT_REQUEST_ID.scope(self, future).await
+  }
+
+  pub fn sync_scope<F: FnOnce() -> R, R>(self, f: F) -> R {
+    CURRENT_REQUEST_ID.sync_scope(self, f)
+  }
+
+  /// The id of the request being handled, `None` outside of a request
+  pub fn current() -> Option<Self> {
+    CURRENT_REQUEST_ID.try_with(|request_id| request_id.clone()).ok()
+  }
+}
+
+impl std::fmt::Display for RequestId {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&self.0)
+  }
+}
+
+impl FromRequest for RequestId {
+  type Error = Error;
+  type Future = Ready<Result<Self, Self::Error>>;
+
+  fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+    let request_id = req.extensions().get::<RequestId>().cloned();
+    ready(request_id.ok_or_else(|| ErrorInternalServerError("Request id not assigned")))
+  }
+}
@@ -0,0 +1,26 @@
+use crate::http::request_id::RequestId;
+use pretty_assertions::assert_eq;
+
+#[actix_rt::test]
+async fn should_accept_a_request_id_sent_by_the_client() {
+  let request_id = RequestId::from_header("5b8c2f4e-1d2a-4b7e-9a41-0c6f2d3e8a90").unwrap();
+
+  assert_eq!(request_id.as_str(), "5b8c2f4e-1d2a-4b7e-9a41-0c6f2d3e8a90");
+}
+
+#[actix_rt::test]
+async fn should_reject_a_request_id_unsafe_for_logs_and_headers() {
+  assert_eq!(RequestId::from_header(""), None);
+  assert_eq!(RequestId::from_header("abc\ndef"), None);
+  assert_eq!(RequestId::from_header("abc def"), None);
+  assert_eq!(RequestId::from_header(&"a".repeat(129)), None);
+}
+
+#[actix_rt::test]
+async fn should_expose_the_current_request_id_only_inside_its_scope() {
+  let request_id = RequestId::from_header("abc").unwrap();
+  let current = request_id.clone().scope(async { RequestId::current() }).await;
+
+  assert_eq!(current, Some(request_id));
+  assert_eq!(RequestId::current(), None);
+}
@@ -0,0 +1,86 @@
+use actix_web::HttpMessage;
+use actix_web::{
+  dev::{ServiceRequest, ServiceResponse},
+  http::header::HeaderMap,
+  Error,
+};
+use opentelemetry::{propagation::Extractor, trace::TraceContextExt};
+use tracing::{field, Span};
+use tracing_actix_web::{DefaultRootSpanBuilder, RootSpanBuilder};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::http::request_id::RequestId;
+
+/// The fields of the default root span, with the `request_id` assigned by `AssignRequestId` instead of the one
+/// generated by `TracingLogger`. Probes from the orchestrator hitting `/health` don't produce spans or logs.
+pub struct RootSpan;
+
+impl RootSpanBuilder for RootSpan {
+  fn on_request_start(request: &ServiceRequest) -> Span {
+    if is_health_check(request.path()) {
+      return Span::none();
+    }
+
+    let request_id = request
+      .extensions()
+      .get::<RequestId>()
+      .cloned()
+      .unwrap_or_else(RequestId::generate);
+    let http_route = request.match_pattern().unwrap_or_else(|| "default".to_string());
+    let user_agent = request
+      .headers()
+      .get("User-Agent")
+      .and_then(|value| value.to_str().ok())
+      .unwrap_or_default();
+    let connection_info = request.connection_info();
+    let span = tracing::info_span!(
+      "HTTP request",
+      http.method = %request.method(),
+      http.route = %http_route,
+      http.flavor = ?request.version(),
+      http.scheme = %connection_info.scheme(),
+      http.host = %connection_info.host(),
+      http.client_ip = %connection_info.realip_remote_addr().unwrap_or_default(),
+      http.user_agent = %user_agent,
+      http.target = %request.uri().path_and_query().map(|path| path.as_str()).unwrap_or_default(),
+      http.status_code = field::Empty,
+      otel.name = %format!("HTTP {} {}", request.method(), http_route),
+      otel.kind = "server",
+      otel.status_code = field::Empty,
+      trace_id = field::Empty,
+      request_id = %request_id,
+      exception.message = field::Empty,
+      exception.details = field::Empty,
+    );
+    drop(connection_info);
+
+    let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+      propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    span.set_parent(parent);
+    let trace_id = span.context().span().span_context().trace_id();
+    span.record("trace_id", field::display(trace_id));
+
+    span
+  }
+
+  fn on_request_end<B>(span: Span, outcome: &Result<ServiceResponse<B>, Error>) {
+    DefaultRootSpanBuilder::on_request_end(span, outcome);
+  }
+}
+
+/// Reads the `traceparent` sent by the caller, so that the request joins its trace
+struct HeaderExtractor<'a>(&'a HeaderMap);
+impl<'a> Extractor for HeaderExtractor<'a> {
+  fn get(&self, key: &str) -> Option<&str> {
+    self.0.get(key).and_then(|value| value.to_str().ok())
+  }
+
+  fn keys(&self) -> Vec<&str> {
+    self.0.keys().map(|name| name.as_str()).collect()
+  }
+}
+
+pub fn is_health_check(path: &str) -> bool {
+  path == "/health" || path.starts_with("/health/")
+}
@@ -0,0 +1,69 @@
+use crate::http::{
+  request_id::RequestId,
+  root_span::{is_health_check, RootSpan},
+};
+use actix_web::{test, HttpMessage};
+use pretty_assertions::assert_eq;
+use std::sync::{Arc, Mutex};
+use tracing::{
+  field::{Field, Visit},
+  span::{Attributes, Id},
+  Subscriber,
+};
+use tracing_actix_web::RootSpanBuilder;
+use tracing_subscriber::{layer::Context, prelude::*, Layer, Registry};
+
+/// Keeps the fields every new span is created with
+#[derive(Clone, Default)]
+struct SpanFields(Arc<Mutex<Vec<(String, String)>>>);
+impl Visit for SpanFields {
+  fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+    self
+      .0
+      .lock()
+      .unwrap()
+      .push((field.name().to_string(), format!("{:?}", value)));
+  }
+}
+impl<S: Subscriber> Layer<S> for SpanFields {
+  fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+    attrs.record(&mut self.clone());
+  }
+}
+
+#[actix_rt::test]
+async fn should_not_create_spans_for_health_checks() {
+  let req = test::TestRequest::get().uri("/health/ready").to_srv_request();
+  let span = RootSpan::on_request_start(&req);
+
+  assert_eq!(span.is_none(), true);
+}
+
+#[actix_rt::test]
+async fn should_record_the_assigned_request_id_once() {
+  let req = test::TestRequest::get().uri("/user/foo").to_srv_request();
+  req.extensions_mut().insert(RequestId::from_header("abc-123").unwrap());
+  let fields = SpanFields::default();
+
+  tracing::subscriber::with_default(Registry::default().with(fields.clone()), || {
+    RootSpan::on_request_start(&req);
+  });
+  let ids: Vec<String> = fields
+    .0
+    .lock()
+    .unwrap()
+    .iter()
+    .filter(|(name, _)| name.ends_with("_id") && name != "trace_id")
+    .map(|(name, value)| format!("{}={}", name, value))
+    .collect();
+
+  assert_eq!(ids, vec!["request_id=abc-123".to_string()]);
+}
+
+#[actix_rt::test]
+async fn should_recognize_health_check_paths() {
+  assert_eq!(is_health_check("/health/live"), true);
+  assert_eq!(is_health_check("/health"), true);
+  assert_eq!(is_health_check("/healthy-user"), false);
+  assert_eq!(is_health_check("/user/health"), false);
+}
@@ -1,15 +1,21 @@
+mod admin;
+mod health;
+mod metrics;
 mod organization;
 mod profile;
 mod user;
 
-use crate::http::http_handler::HttpError;
+use crate::http::{http_handler::HttpError, middleware::ConditionalGet};
 use actix_web::{web, HttpResponse, Route};
 
 pub fn config_route(cfg: &mut web::ServiceConfig) {
   cfg
-    .service(profile::scope())
-    .service(user::scope())
-    .service(organization::scope());
+    .service(admin::scope())
+    .service(health::scope())
+    .service(metrics::scope())
+    .service(profile::scope().wrap(ConditionalGet::new("profile")))
+    .service(user::scope().wrap(ConditionalGet::new("user")))
+    .service(organization::scope().wrap(ConditionalGet::new("organization")));
 }
 
 pub fn not_found() -> Route {
@@ -19,6 +25,12 @@ pub fn not_found() -> Route {
   })
 }
 
+#[cfg(test)]
+mod admin_spec;
+#[cfg(test)]
+mod health_spec;
+#[cfg(test)]
+mod metrics_spec;
 #[cfg(test)]
 mod organization_spec;
 #[cfg(test)]
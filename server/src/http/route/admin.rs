@@ -0,0 +1,134 @@
+use crate::http::{http_handler::HttpError, AppState};
+use actix_web::{http::header, web, HttpRequest, HttpResponse, Responder, Scope};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct LogLevel {
+  /// Filter directive in the `RUST_LOG` format, e.g. `info,rust_web_server::model=debug`
+  pub directive: String,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct CacheInvalidation {
+  /// How many cached results were removed
+  pub removed: usize,
+}
+
+pub fn scope() -> Scope {
+  web::scope("/admin")
+    .route("/log-level", web::get().to(get_log_level))
+    .route("/log-level", web::put().to(set_log_level))
+    .route("/cache", web::delete().to(clear_cache))
+    .route("/cache/{login_or_id}", web::delete().to(invalidate_cache))
+}
+
+async fn get_log_level(req: HttpRequest, state: web::Data<AppState>) -> impl Responder {
+  if let Some(res) = reject_unauthorized(&req, &state) {
+    return res;
+  }
+
+  match &state.log_filter {
+    Some(log_filter) => HttpResponse::Ok().json(LogLevel {
+      directive: log_filter.current(),
+    }),
+    None => log_filter_unavailable(),
+  }
+}
+
+async fn set_log_level(req: HttpRequest, state: web::Data<AppState>, body: web::Json<LogLevel>) -> impl Responder {
+  if let Some(res) = reject_unauthorized(&req, &state) {
+    return res;
+  }
+  let log_filter = match &state.log_filter {
+    Some(log_filter) => log_filter,
+    None => return log_filter_unavailable(),
+  };
+
+  let previous = log_filter.current();
+  match log_filter.set(&body.directive) {
+    Ok(directive) => {
+      tracing::warn!(previous = %previous, current = %directive, "log filter changed");
+      HttpResponse::Ok().json(LogLevel { directive })
+    }
+    Err(err) => {
+      let result_error = HttpError::new(format!("Invalid log filter directive: {}", err));
+      HttpResponse::BadRequest().json(result_error)
+    }
+  }
+}
+
+async fn clear_cache(req: HttpRequest, state: web::Data<AppState>) -> impl Responder {
+  if let Some(res) = reject_unauthorized(&req, &state) {
+    return res;
+  }
+
+  match &state.cache {
+    Some(cache) => {
+      let removed = cache.clear();
+      tracing::warn!(removed, "cache cleared");
+      HttpResponse::Ok().json(CacheInvalidation { removed })
+    }
+    None => cache_disabled(),
+  }
+}
+
+/// Removes the cached results of a user, an organization or a repository, by login or hex `_id`, after it was changed
+async fn invalidate_cache(
+  req: HttpRequest,
+  state: web::Data<AppState>,
+  login_or_id: web::Path<String>,
+) -> impl Responder {
+  if let Some(res) = reject_unauthorized(&req, &state) {
+    return res;
+  }
+
+  match &state.cache {
+    Some(cache) => {
+      let removed = cache.invalidate(&login_or_id);
+      tracing::info!(removed, key = %login_or_id, "cache invalidated");
+      HttpResponse::Ok().json(CacheInvalidation { removed })
+    }
+    None => cache_disabled(),
+  }
+}
+
+/// Returns the error response when the request doesn't carry the admin bearer token
+fn reject_unauthorized(req: &HttpRequest, state: &AppState) -> Option<HttpResponse> {
+  let token = match &state.config.admin.token {
+    Some(token) => token,
+    None => {
+      let result_error = HttpError::new("Admin endpoints are disabled".to_string());
+      return Some(HttpResponse::Forbidden().json(result_error));
+    }
+  };
+
+  let bearer = req
+    .headers()
+    .get(header::AUTHORIZATION)
+    .and_then(|value| value.to_str().ok())
+    .and_then(|value| value.strip_prefix("Bearer "));
+
+  match bearer {
+    Some(bearer) if constant_time_eq(bearer.as_bytes(), token.as_bytes()) => None,
+    _ => {
+      tracing::warn!("Unauthorized request to an admin endpoint");
+      let result_error = HttpError::new("Unauthorized".to_string());
+      Some(HttpResponse::Unauthorized().json(result_error))
+    }
+  }
+}
+
+fn log_filter_unavailable() -> HttpResponse {
+  let result_error = HttpError::new("Log filter not reloadable".to_string());
+  HttpResponse::ServiceUnavailable().json(result_error)
+}
+
+fn cache_disabled() -> HttpResponse {
+  let result_error = HttpError::new("Cache disabled".to_string());
+  HttpResponse::ServiceUnavailable().json(result_error)
+}
+
+/// Compares the tokens without returning early, so the response time doesn't tell how many bytes matched
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+  left.len() == right.len() && left.iter().zip(right).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
@@ -0,0 +1,139 @@
+use crate::http::AppState;
+use crate::setup::{
+  config::StoreBackend,
+  db::ping,
+  tracing::{tracing_status, TracingStatus},
+};
+use actix_web::{web, HttpResponse, Responder, Scope};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, time::Duration};
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+  Pass,
+  Warn,
+  Fail,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentHealth {
+  pub status: Status,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub latency_ms: Option<f64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct Health {
+  pub status: Status,
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub components: BTreeMap<String, ComponentHealth>,
+}
+impl Health {
+  fn from_components(components: BTreeMap<String, ComponentHealth>) -> Self {
+    let status =
+      components
+        .values()
+        .map(|component| component.status)
+        .fold(Status::Pass, |status, component_status| {
+          match (status, component_status) {
+            (Status::Fail, _) | (_, Status::Fail) => Status::Fail,
+            (Status::Warn, _) | (_, Status::Warn) => Status::Warn,
+            _ => Status::Pass,
+          }
+        });
+
+    Self { status, components }
+  }
+}
+
+pub fn scope() -> Scope {
+  web::scope("/health")
+    .route("/live", web::get().to(live))
+    .route("/ready", web::get().to(ready))
+}
+
+async fn live() -> impl Responder {
+  HttpResponse::Ok().json(Health {
+    status: Status::Pass,
+    components: BTreeMap::new(),
+  })
+}
+
+async fn ready(state: web::Data<AppState>) -> impl Responder {
+  let timeout = Duration::from_millis(state.config.health.database_timeout_ms);
+  let database = match state.config.store.backend {
+    StoreBackend::Memory => ComponentHealth {
+      status: Status::Pass,
+      latency_ms: None,
+      message: Some("not used by the memory store".to_string()),
+    },
+    StoreBackend::Sqlite => ComponentHealth {
+      status: Status::Pass,
+      latency_ms: None,
+      message: Some("not used by the sqlite store".to_string()),
+    },
+    StoreBackend::Mongo => match ping(&state.db, timeout).await {
+      Ok(latency) => ComponentHealth {
+        status: Status::Pass,
+        latency_ms: Some(latency.as_secs_f64() * 1000.0),
+        message: None,
+      },
+      Err(message) => ComponentHealth {
+        status: Status::Fail,
+        latency_ms: None,
+        message: Some(message),
+      },
+    },
+  };
+  let tracing = match tracing_status() {
+    TracingStatus::Running => ComponentHealth {
+      status: Status::Pass,
+      latency_ms: None,
+      message: None,
+    },
+    TracingStatus::NotStarted => ComponentHealth {
+      status: Status::Warn,
+      latency_ms: None,
+      message: Some("tracing pipeline not started".to_string()),
+    },
+    TracingStatus::Disabled => ComponentHealth {
+      status: Status::Pass,
+      latency_ms: None,
+      message: Some("tracing exporter disabled".to_string()),
+    },
+    TracingStatus::Failing(message) => ComponentHealth {
+      status: Status::Warn,
+      latency_ms: None,
+      message: Some(message),
+    },
+  };
+
+  let server = match state.lifecycle.is_shutting_down() {
+    true => ComponentHealth {
+      status: Status::Fail,
+      latency_ms: None,
+      message: Some("shutting down".to_string()),
+    },
+    false => ComponentHealth {
+      status: Status::Pass,
+      latency_ms: None,
+      message: None,
+    },
+  };
+
+  let components = BTreeMap::from([
+    ("database".to_string(), database),
+    ("server".to_string(), server),
+    ("tracing".to_string(), tracing),
+  ]);
+  let health = Health::from_components(components);
+
+  match health.status {
+    Status::Fail => HttpResponse::ServiceUnavailable().json(health),
+    _ => HttpResponse::Ok().json(health),
+  }
+}
@@ -0,0 +1,29 @@
+use crate::http::route::health::{self, Health, Status};
+use crate::setup::mock;
+use actix_web::{http::StatusCode, test, App};
+use pretty_assertions::assert_eq;
+
+#[actix_rt::test]
+async fn should_report_the_process_as_live() {
+  let app = test::init_service(App::new().service(health::scope())).await;
+  let req = test::TestRequest::get().uri("/health/live").to_request();
+  let res = test::call_service(&app, req).await;
+  let status = res.status();
+  let body: Health = test::read_body_json(res).await;
+
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body.status, Status::Pass);
+}
+
+#[actix_rt::test]
+async fn should_report_the_database_as_ready() {
+  let sufix = mock::random_sufix();
+  let res = mock::make_request(mock::HttpMethod::Get, "/health/ready", health::scope(), &sufix).await;
+  let status = res.status();
+  let body: Health = test::read_body_json(res).await;
+  let database = body.components.get("database").unwrap();
+
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(database.status, Status::Pass);
+  assert!(database.latency_ms.is_some());
+}
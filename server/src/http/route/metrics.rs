@@ -0,0 +1,13 @@
+use crate::http::AppState;
+use actix_web::{web, HttpResponse, Responder, Scope};
+use prometheus::TEXT_FORMAT;
+
+pub fn scope() -> Scope {
+  web::scope("/metrics").route("", web::get().to(metrics))
+}
+
+async fn metrics(state: web::Data<AppState>) -> impl Responder {
+  HttpResponse::Ok()
+    .content_type(TEXT_FORMAT)
+    .body(state.metrics.encode())
+}
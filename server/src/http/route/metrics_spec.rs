@@ -0,0 +1,17 @@
+use crate::http::route::metrics;
+use crate::setup::mock;
+use actix_web::{http::StatusCode, test};
+use pretty_assertions::assert_eq;
+
+#[actix_rt::test]
+async fn should_expose_the_metrics_in_the_prometheus_text_format() {
+  let sufix = mock::random_sufix();
+  let res = mock::make_request(mock::HttpMethod::Get, "/metrics", metrics::scope(), &sufix).await;
+  let status = res.status();
+  let content_type = res.headers().get("content-type").unwrap().to_str().unwrap().to_string();
+  let body = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(content_type, "text/plain; version=0.0.4");
+  assert!(body.contains("# TYPE http_requests_in_flight gauge"));
+}
@@ -1,14 +1,12 @@
 use crate::http::{
   http_handler::{into_response_list, into_response_object},
+  ndjson::{accepts_ndjson, into_response_stream},
+  viewer::{viewer_id, Viewer},
   {middleware, AppState},
 };
-use crate::lib::cursor_connection::PaginationArguments;
-use crate::model::{
-  organization::{find_organization_by_login, find_people_by_login},
-  repository::{find_repositories_by_owner_id, repositories_to_cursor_connection},
-  user::users_to_cursor_connection,
-};
-use actix_web::{web, Responder, Scope};
+use crate::lib::{cursor_connection::PaginationArguments, fields::Fields};
+use crate::model::store::List;
+use actix_web::{web, HttpRequest, Responder, Scope};
 
 pub fn scope() -> Scope {
   web::scope("/organization/{login}")
@@ -25,44 +23,75 @@ pub fn scope() -> Scope {
     )
 }
 
-async fn organization(state: web::Data<AppState>, login: web::Path<String>) -> impl Responder {
-  let result = find_organization_by_login(&state.db, &login).await;
+async fn organization(
+  req: HttpRequest,
+  state: web::Data<AppState>,
+  login: web::Path<String>,
+  fields: Fields,
+) -> impl Responder {
+  let result = state.store.find_organization_by_login(&login, &fields).await;
 
-  into_response_object(result, "Organization")
+  into_response_object(&req, result, "Organization")
 }
 
 async fn people(
+  req: HttpRequest,
   state: web::Data<AppState>,
   login: web::Path<String>,
   web::Query(pagination_arguments): web::Query<PaginationArguments>,
+  viewer: Option<Viewer>,
+  fields: Fields,
 ) -> impl Responder {
-  let result = find_organization_by_login(&state.db, &login).await;
+  let viewer_id = viewer_id(&viewer);
+  let result = state.store.find_organization_by_login(&login, &Fields::default()).await;
 
   match result {
-    Err(_) => into_response_object(result, ""),
-    Ok(None) => into_response_object(result, "Organization"),
+    Err(_) => into_response_object(&req, result, ""),
+    Ok(None) => into_response_object(&req, result, "Organization"),
+    Ok(Some(_)) if accepts_ndjson(&req) => into_response_stream(
+      state,
+      List::People(login.into_inner()),
+      pagination_arguments,
+      viewer_id,
+      fields,
+    ),
     Ok(Some(_)) => {
-      let result = find_people_by_login(&state.db, &login, pagination_arguments).await;
-      let result = users_to_cursor_connection(&state.db, &login, result).await;
-      into_response_list(result)
+      let result = state
+        .store
+        .find_people(&login, pagination_arguments, &viewer_id, &fields)
+        .await;
+      into_response_list(&req, result)
     }
   }
 }
 
 async fn repositories(
+  req: HttpRequest,
   state: web::Data<AppState>,
   login: web::Path<String>,
   web::Query(pagination_arguments): web::Query<PaginationArguments>,
+  viewer: Option<Viewer>,
+  fields: Fields,
 ) -> impl Responder {
-  let result = find_organization_by_login(&state.db, &login).await;
+  let viewer_id = viewer_id(&viewer);
+  let result = state.store.find_organization_by_login(&login, &Fields::default()).await;
 
   match result {
-    Err(_) => into_response_object(result, ""),
-    Ok(None) => into_response_object(result, "Organization"),
+    Err(_) => into_response_object(&req, result, ""),
+    Ok(None) => into_response_object(&req, result, "Organization"),
+    Ok(Some(owner)) if accepts_ndjson(&req) => into_response_stream(
+      state,
+      List::RepositoriesByOwnerId(owner._id),
+      pagination_arguments,
+      viewer_id,
+      fields,
+    ),
     Ok(Some(owner)) => {
-      let result = find_repositories_by_owner_id(&state.db, &owner._id, pagination_arguments).await;
-      let result = repositories_to_cursor_connection(&state.db, &owner._id, result).await;
-      into_response_list(result)
+      let result = state
+        .store
+        .find_repositories_by_owner_id(&owner._id, pagination_arguments, &viewer_id, &fields)
+        .await;
+      into_response_list(&req, result)
     }
   }
 }
@@ -1,6 +1,10 @@
-use crate::http::{http_handler::HttpError, AppState};
-use crate::model::{organization::find_organization_by_login, user::find_user_by_login};
-use actix_web::{web, HttpResponse, Responder, Scope};
+use crate::http::{
+  http_handler::{into_response_body, HttpError},
+  viewer::{viewer_id, Viewer},
+  AppState,
+};
+use crate::lib::{fields::Fields, redact::redact_emails};
+use actix_web::{web, HttpRequest, HttpResponse, Responder, Scope};
 use futures::join;
 use tracing;
 
@@ -8,20 +12,27 @@ pub fn scope() -> Scope {
   web::scope("/profile/{login}").route("", web::get().to(profile))
 }
 
-async fn profile(state: web::Data<AppState>, login: web::Path<String>) -> impl Responder {
+async fn profile(
+  req: HttpRequest,
+  state: web::Data<AppState>,
+  login: web::Path<String>,
+  viewer: Option<Viewer>,
+  fields: Fields,
+) -> impl Responder {
+  let viewer_id = viewer_id(&viewer);
   let (user, organization) = join!(
-    find_user_by_login(&state.db, &login),
-    find_organization_by_login(&state.db, &login)
+    state.store.find_user_by_login(&login, &viewer_id, &fields),
+    state.store.find_organization_by_login(&login, &fields)
   );
 
   match (user, organization) {
     (Err(err), _) | (_, Err(err)) => {
-      tracing::error!("Internal server error: {:#?}", err);
+      tracing::error!("Internal server error: {}", redact_emails(&format!("{:#?}", err)));
       let result_error = HttpError::new("Internal server error".to_string());
       HttpResponse::InternalServerError().json(result_error)
     }
-    (Ok(Some(user)), _) => HttpResponse::Ok().json(user),
-    (_, Ok(Some(organization))) => HttpResponse::Ok().json(organization),
+    (Ok(Some(user)), _) => into_response_body(&req, &user),
+    (_, Ok(Some(organization))) => into_response_body(&req, &organization),
     _ => {
       tracing::info!("Profile {} not found", login);
       let result_error = HttpError::new("Profile not found".to_string());
@@ -1,17 +1,12 @@
 use crate::http::{
   http_handler::{into_response_list, into_response_object},
+  ndjson::{accepts_ndjson, into_response_stream},
+  viewer::{viewer_id, Viewer},
   {middleware, AppState},
 };
-use crate::lib::cursor_connection::PaginationArguments;
-use crate::model::{
-  organization::organizations_to_cursor_connection,
-  repository::{find_repositories_by_owner_id, repositories_to_cursor_connection},
-  user::{
-    find_followers_by_login, find_following_by_login, find_organizations_by_login, find_starred_repositories_by_login,
-    find_user_by_login, users_to_cursor_connection,
-  },
-};
-use actix_web::{web, Responder, Scope};
+use crate::lib::{cursor_connection::PaginationArguments, fields::Fields};
+use crate::model::store::List;
+use actix_web::{web, HttpRequest, Responder, Scope};
 
 pub fn scope() -> Scope {
   web::scope("/user/{login}")
@@ -43,98 +38,168 @@ pub fn scope() -> Scope {
     )
 }
 
-async fn user(state: web::Data<AppState>, login: web::Path<String>) -> impl Responder {
-  let result = find_user_by_login(&state.db, &login).await;
+async fn user(
+  req: HttpRequest,
+  state: web::Data<AppState>,
+  login: web::Path<String>,
+  viewer: Option<Viewer>,
+  fields: Fields,
+) -> impl Responder {
+  let viewer_id = viewer_id(&viewer);
+  let result = state.store.find_user_by_login(&login, &viewer_id, &fields).await;
 
-  into_response_object(result, "User")
+  into_response_object(&req, result, "User")
 }
 
 async fn organizations(
+  req: HttpRequest,
   state: web::Data<AppState>,
   login: web::Path<String>,
   web::Query(pagination_arguments): web::Query<PaginationArguments>,
+  fields: Fields,
 ) -> impl Responder {
-  let result = find_user_by_login(&state.db, &login).await;
+  let result = state.store.find_user_by_login(&login, &None, &Fields::default()).await;
 
   match result {
-    Err(_) => into_response_object(result, ""),
-    Ok(None) => into_response_object(result, "User"),
+    Err(_) => into_response_object(&req, result, ""),
+    Ok(None) => into_response_object(&req, result, "User"),
+    Ok(Some(_)) if accepts_ndjson(&req) => into_response_stream(
+      state,
+      List::OrganizationsOfUser(login.into_inner()),
+      pagination_arguments,
+      None,
+      fields,
+    ),
     Ok(Some(_)) => {
-      let result = find_organizations_by_login(&state.db, &login, pagination_arguments).await;
-      let result = organizations_to_cursor_connection(&state.db, &login, result).await;
-      into_response_list(result)
+      let result = state
+        .store
+        .find_organizations_of_user(&login, pagination_arguments, &fields)
+        .await;
+      into_response_list(&req, result)
     }
   }
 }
 
 async fn repositories(
+  req: HttpRequest,
   state: web::Data<AppState>,
   login: web::Path<String>,
   web::Query(pagination_arguments): web::Query<PaginationArguments>,
+  viewer: Option<Viewer>,
+  fields: Fields,
 ) -> impl Responder {
-  let result = find_user_by_login(&state.db, &login).await;
+  let viewer_id = viewer_id(&viewer);
+  let result = state.store.find_user_by_login(&login, &None, &Fields::default()).await;
 
   match result {
-    Err(_) => into_response_object(result, ""),
-    Ok(None) => into_response_object(result, "User"),
+    Err(_) => into_response_object(&req, result, ""),
+    Ok(None) => into_response_object(&req, result, "User"),
+    Ok(Some(owner)) if accepts_ndjson(&req) => into_response_stream(
+      state,
+      List::RepositoriesByOwnerId(owner._id),
+      pagination_arguments,
+      viewer_id,
+      fields,
+    ),
     Ok(Some(owner)) => {
-      let result = find_repositories_by_owner_id(&state.db, &owner._id, pagination_arguments).await;
-      let result = repositories_to_cursor_connection(&state.db, &owner._id, result).await;
-      into_response_list(result)
+      let result = state
+        .store
+        .find_repositories_by_owner_id(&owner._id, pagination_arguments, &viewer_id, &fields)
+        .await;
+      into_response_list(&req, result)
     }
   }
 }
 
 async fn starred_repositories(
+  req: HttpRequest,
   state: web::Data<AppState>,
   login: web::Path<String>,
   web::Query(pagination_arguments): web::Query<PaginationArguments>,
+  viewer: Option<Viewer>,
+  fields: Fields,
 ) -> impl Responder {
-  let result = find_user_by_login(&state.db, &login).await;
+  let viewer_id = viewer_id(&viewer);
+  let result = state.store.find_user_by_login(&login, &None, &Fields::default()).await;
 
   match result {
-    Err(_) => into_response_object(result, ""),
-    Ok(None) => into_response_object(result, "User"),
-    Ok(Some(owner)) => {
-      let result = find_starred_repositories_by_login(&state.db, &login, pagination_arguments).await;
-      let result = repositories_to_cursor_connection(&state.db, &owner._id, result).await;
-      into_response_list(result)
+    Err(_) => into_response_object(&req, result, ""),
+    Ok(None) => into_response_object(&req, result, "User"),
+    Ok(Some(_)) if accepts_ndjson(&req) => into_response_stream(
+      state,
+      List::StarredRepositories(login.into_inner()),
+      pagination_arguments,
+      viewer_id,
+      fields,
+    ),
+    Ok(Some(_)) => {
+      let result = state
+        .store
+        .find_starred_repositories(&login, pagination_arguments, &viewer_id, &fields)
+        .await;
+      into_response_list(&req, result)
     }
   }
 }
 
 async fn followers(
+  req: HttpRequest,
   state: web::Data<AppState>,
   login: web::Path<String>,
   web::Query(pagination_arguments): web::Query<PaginationArguments>,
+  viewer: Option<Viewer>,
+  fields: Fields,
 ) -> impl Responder {
-  let result = find_user_by_login(&state.db, &login).await;
+  let viewer_id = viewer_id(&viewer);
+  let result = state.store.find_user_by_login(&login, &None, &Fields::default()).await;
 
   match result {
-    Err(_) => into_response_object(result, ""),
-    Ok(None) => into_response_object(result, "User"),
+    Err(_) => into_response_object(&req, result, ""),
+    Ok(None) => into_response_object(&req, result, "User"),
+    Ok(Some(_)) if accepts_ndjson(&req) => into_response_stream(
+      state,
+      List::Followers(login.into_inner()),
+      pagination_arguments,
+      viewer_id,
+      fields,
+    ),
     Ok(Some(_)) => {
-      let result = find_followers_by_login(&state.db, &login, pagination_arguments).await;
-      let result = users_to_cursor_connection(&state.db, &login, result).await;
-      into_response_list(result)
+      let result = state
+        .store
+        .find_followers(&login, pagination_arguments, &viewer_id, &fields)
+        .await;
+      into_response_list(&req, result)
     }
   }
 }
 
 async fn following(
+  req: HttpRequest,
   state: web::Data<AppState>,
   login: web::Path<String>,
   web::Query(pagination_arguments): web::Query<PaginationArguments>,
+  viewer: Option<Viewer>,
+  fields: Fields,
 ) -> impl Responder {
-  let result = find_user_by_login(&state.db, &login).await;
+  let viewer_id = viewer_id(&viewer);
+  let result = state.store.find_user_by_login(&login, &None, &Fields::default()).await;
 
   match result {
-    Err(_) => into_response_object(result, ""),
-    Ok(None) => into_response_object(result, "User"),
+    Err(_) => into_response_object(&req, result, ""),
+    Ok(None) => into_response_object(&req, result, "User"),
+    Ok(Some(_)) if accepts_ndjson(&req) => into_response_stream(
+      state,
+      List::Following(login.into_inner()),
+      pagination_arguments,
+      viewer_id,
+      fields,
+    ),
     Ok(Some(_)) => {
-      let result = find_following_by_login(&state.db, &login, pagination_arguments).await;
-      let result = users_to_cursor_connection(&state.db, &login, result).await;
-      into_response_list(result)
+      let result = state
+        .store
+        .find_following(&login, pagination_arguments, &viewer_id, &fields)
+        .await;
+      into_response_list(&req, result)
     }
   }
 }
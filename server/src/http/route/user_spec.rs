@@ -17,6 +17,16 @@ async fn should_match_a_specified_user() {
   assert_eq!(body.login, format!("user_foo_{sufix}"));
 }
 
+#[actix_rt::test]
+async fn should_not_expose_a_private_email() {
+  let sufix = mock::random_sufix();
+  let login = format!("user_foo_{sufix}");
+  let res = mock::make_request(mock::HttpMethod::Get, &format!("/user/{login}"), user::scope(), &sufix).await;
+  let body: User = test::read_body_json(res).await;
+
+  assert_eq!(body.email, None);
+}
+
 // Organizations
 
 #[actix_rt::test]
@@ -0,0 +1,25 @@
+use actix_web::{dev::Payload, error::ErrorUnauthorized, Error, FromRequest, HttpMessage, HttpRequest};
+use mongodb::bson::oid::ObjectId;
+use std::future::{ready, Ready};
+
+/// The authenticated user performing the request. It is expected to be inserted into the request extensions by the
+/// authentication layer, handlers should extract it as `Option<Viewer>` to keep anonymous requests working.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Viewer {
+  pub _id: ObjectId,
+  pub login: String,
+}
+
+impl FromRequest for Viewer {
+  type Error = Error;
+  type Future = Ready<Result<Self, Self::Error>>;
+
+  fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+    let viewer = req.extensions().get::<Viewer>().cloned();
+    ready(viewer.ok_or_else(|| ErrorUnauthorized("Viewer not authenticated")))
+  }
+}
+
+pub fn viewer_id(viewer: &Option<Viewer>) -> Option<ObjectId> {
+  viewer.as_ref().map(|viewer| viewer._id)
+}
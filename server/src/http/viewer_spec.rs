@@ -0,0 +1,28 @@
+use crate::http::viewer::{viewer_id, Viewer};
+use actix_web::{test, FromRequest, HttpMessage};
+use mongodb::bson::oid::ObjectId;
+use pretty_assertions::assert_eq;
+
+#[actix_rt::test]
+async fn should_extract_the_viewer_from_request_extensions() {
+  let viewer = Viewer {
+    _id: ObjectId::new(),
+    login: "foo".to_string(),
+  };
+  let req = test::TestRequest::default().to_http_request();
+  req.extensions_mut().insert(viewer.clone());
+
+  let extracted = Viewer::extract(&req).await.unwrap();
+
+  assert_eq!(extracted, viewer);
+}
+
+#[actix_rt::test]
+async fn should_extract_none_when_there_is_no_viewer() {
+  let req = test::TestRequest::default().to_http_request();
+
+  let extracted = Option::<Viewer>::extract(&req).await.unwrap();
+
+  assert_eq!(extracted, None);
+  assert_eq!(viewer_id(&extracted), None);
+}
@@ -29,7 +29,7 @@ use serde::{Deserialize, Deserializer, Serialize};
 
 pub type ReferenceFrom<T> = fn(item: &T) -> String;
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Edges<T> {
   pub cursor: String,
   pub node: T,
@@ -46,7 +46,7 @@ impl<T> Edges<T> {
   }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct PageInfo {
   pub has_previous_page: bool,
@@ -77,7 +77,7 @@ impl PageInfo {
   }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct CursorConnection<T> {
   pub page_info: PageInfo,
@@ -98,7 +98,7 @@ pub enum Direction {
   Backward,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PaginationArguments {
   pub first: Option<u32>,
   #[serde(default, deserialize_with = "optional_string")]
@@ -200,7 +200,7 @@ pub fn cursor_to_reference(cursor: String) -> Result<String, Error> {
   Ok(result.to_string())
 }
 
-fn reference_to_cursor(reference: String) -> String {
+pub fn reference_to_cursor(reference: String) -> String {
   base64::encode(reference)
 }
 
@@ -0,0 +1,44 @@
+use mongodb::bson::{doc, Document};
+use std::collections::BTreeSet;
+
+/// The fields of the objects and of the nodes of the lists asked with `?fields=login,name,avatarUrl`, by their JSON
+/// name. `_id` is always kept as it is the cursor of the lists, every field is kept when none is asked.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Fields(Option<BTreeSet<String>>);
+
+impl Fields {
+  pub fn parse(value: &str) -> Result<Self, String> {
+    let names: BTreeSet<String> = value.split(',').map(|name| name.trim().to_string()).collect();
+    let invalid = names
+      .iter()
+      .find(|name| name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+
+    match invalid {
+      Some(name) => Err(format!("Invalid field {:?}", name)),
+      None => Ok(Self(Some(names))),
+    }
+  }
+
+  pub fn is_all(&self) -> bool {
+    self.0.is_none()
+  }
+
+  pub fn contains(&self, name: &str) -> bool {
+    match &self.0 {
+      None => true,
+      Some(names) => name == "_id" || names.contains(name),
+    }
+  }
+
+  /// The inclusion projection of the asked fields and of the `required` fields of the model, `None` when every field
+  /// is asked
+  pub fn projection(&self, required: &[&str]) -> Option<Document> {
+    let names = self.0.as_ref()?;
+    let mut projection = doc! { "_id": 1 };
+    for name in required.iter().copied().chain(names.iter().map(String::as_str)) {
+      projection.insert(name, 1);
+    }
+
+    Some(projection)
+  }
+}
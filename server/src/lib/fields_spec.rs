@@ -0,0 +1,36 @@
+use crate::lib::fields::*;
+use mongodb::bson::doc;
+use pretty_assertions::assert_eq;
+
+#[test]
+fn should_keep_every_field_by_default() {
+  let fields = Fields::default();
+
+  assert_eq!(fields.is_all(), true);
+  assert_eq!(fields.contains("login"), true);
+  assert_eq!(fields.projection(&["login"]), None);
+}
+
+#[test]
+fn should_parse_a_list_of_fields() {
+  let fields = Fields::parse("login, name,avatarUrl").unwrap();
+
+  assert_eq!(fields.is_all(), false);
+  assert_eq!(fields.contains("avatarUrl"), true);
+  assert_eq!(fields.contains("_id"), true);
+  assert_eq!(fields.contains("email"), false);
+  assert_eq!(
+    fields.projection(&["__typename", "login"]),
+    Some(doc! { "_id": 1, "__typename": 1, "login": 1, "avatarUrl": 1, "name": 1 })
+  );
+}
+
+#[test]
+fn should_reject_an_invalid_field() {
+  assert_eq!(Fields::parse("login,").unwrap_err(), "Invalid field \"\"");
+  assert_eq!(
+    Fields::parse("owner.login").unwrap_err(),
+    "Invalid field \"owner.login\""
+  );
+  assert_eq!(Fields::parse("$where").unwrap_err(), "Invalid field \"$where\"");
+}
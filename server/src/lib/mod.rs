@@ -1,4 +1,12 @@
 pub mod cursor_connection;
+pub mod fields;
+pub mod redact;
 
 #[cfg(test)]
 mod cursor_connection_spec;
+
+#[cfg(test)]
+mod fields_spec;
+
+#[cfg(test)]
+mod redact_spec;
@@ -0,0 +1,45 @@
+pub const REDACTED_EMAIL: &str = "[redacted email]";
+
+pub fn redact_emails(text: &str) -> String {
+  let bytes = text.as_bytes();
+  let mut result = String::with_capacity(text.len());
+  let mut copied_until = 0;
+  let mut index = 0;
+
+  while index < bytes.len() {
+    if bytes[index] != b'@' {
+      index += 1;
+      continue;
+    }
+
+    let start = (copied_until..index)
+      .rev()
+      .take_while(|&i| is_local_part_char(bytes[i]))
+      .last();
+    let end = (index + 1..bytes.len())
+      .take_while(|&i| is_domain_char(bytes[i]))
+      .last()
+      .map(|end| (index + 1..=end).rev().find(|&i| bytes[i] != b'.').unwrap_or(index));
+
+    match (start, end) {
+      (Some(start), Some(end)) if end > index && bytes[index + 1..end].contains(&b'.') => {
+        result.push_str(&text[copied_until..start]);
+        result.push_str(REDACTED_EMAIL);
+        copied_until = end + 1;
+        index = end + 1;
+      }
+      _ => index += 1,
+    }
+  }
+
+  result.push_str(&text[copied_until..]);
+  result
+}
+
+fn is_local_part_char(byte: u8) -> bool {
+  byte.is_ascii_alphanumeric() || b"._%+-".contains(&byte)
+}
+
+fn is_domain_char(byte: u8) -> bool {
+  byte.is_ascii_alphanumeric() || b".-".contains(&byte)
+}
@@ -0,0 +1,26 @@
+use crate::lib::redact::*;
+use pretty_assertions::assert_eq;
+
+#[test]
+fn should_redact_an_email() {
+  let text = redact_emails("foo@email.com");
+
+  assert_eq!(text, "[redacted email]");
+}
+
+#[test]
+fn should_redact_every_email_of_the_text() {
+  let text = redact_emails("duplicate key { email: \"foo.bar+baz@email.com\" } and dee@mail.co.uk.");
+
+  assert_eq!(
+    text,
+    "duplicate key { email: \"[redacted email]\" } and [redacted email]."
+  );
+}
+
+#[test]
+fn should_keep_text_without_email_untouched() {
+  let text = redact_emails("follow @belchior at twitter@ or foo@localhost");
+
+  assert_eq!(text, "follow @belchior at twitter@ or foo@localhost");
+}
@@ -1,11 +1,32 @@
+mod cli;
 mod http;
 mod lib;
 mod model;
 mod setup;
 
+#[cfg(test)]
+mod cli_spec;
+
+use cli::Command;
 use dotenv::dotenv;
+use setup::config::Config;
 
 fn main() -> () {
   dotenv().ok();
-  http::main().expect("start http server");
+  let command = cli::parse_args(std::env::args().skip(1)).unwrap_or_else(|err| {
+    eprintln!("{}\n\n{}", err, cli::USAGE);
+    std::process::exit(2);
+  });
+  let config = Config::load().unwrap_or_else(|err| {
+    eprintln!("Invalid configuration: {}", err);
+    std::process::exit(1);
+  });
+
+  match command {
+    Command::Serve => http::main(config).expect("start http server"),
+    command => cli::run(command, config).unwrap_or_else(|err| {
+      eprintln!("{}", err);
+      std::process::exit(1);
+    }),
+  }
 }
@@ -2,6 +2,8 @@ use mongodb::bson;
 
 pub mod organization;
 pub mod repository;
+pub mod schema;
+pub mod store;
 pub mod user;
 pub mod utils;
 
@@ -13,6 +15,12 @@ mod organization_spec;
 #[cfg(test)]
 mod repository_spec;
 
+#[cfg(test)]
+mod schema_spec;
+
+#[cfg(test)]
+mod store_spec;
+
 #[cfg(test)]
 mod user_spec;
 
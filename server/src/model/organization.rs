@@ -1,13 +1,21 @@
-use crate::lib::cursor_connection::{CursorConnection, PaginationArguments};
-use crate::model::{self, user::User};
+use crate::lib::{
+  cursor_connection::{CursorConnection, PaginationArguments},
+  fields::Fields,
+};
+use crate::model::{
+  self,
+  schema::{one_of, optional, references, required, required_fields, CollectionSchema},
+  user::User,
+};
 use mongodb::{
   bson::{doc, oid::ObjectId},
   error::Error as ModelError,
-  options::FindOneOptions,
+  options::{FindOneOptions, IndexOptions},
+  IndexModel,
 };
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Organization {
   #[serde(rename = "_id")]
@@ -29,14 +37,63 @@ pub struct Organization {
   pub typename: String,
 }
 
+/// The non optional fields of `Organization`
+pub const REQUIRED_FIELDS: &[&str] = &["avatarUrl", "login", "url", "__typename"];
+
+pub fn collection_schema() -> CollectionSchema {
+  CollectionSchema {
+    collection: "organizations",
+    indexes: vec![
+      IndexModel::builder()
+        .keys(doc! { "login": 1 })
+        .options(
+          IndexOptions::builder()
+            .name("login_unique".to_string())
+            .unique(true)
+            .build(),
+        )
+        .build(),
+      IndexModel::builder()
+        .keys(doc! { "login": "text", "name": "text", "description": "text" })
+        .options(IndexOptions::builder().name("search_text".to_string()).build())
+        .build(),
+    ],
+    json_schema: doc! {
+      "bsonType": "object",
+      "required": required_fields(REQUIRED_FIELDS),
+      "properties": {
+        "_id": required("objectId"),
+        "avatarUrl": required("string"),
+        "description": optional("string"),
+        "location": optional("string"),
+        "login": required("string"),
+        "name": optional("string"),
+        "people": references(),
+        "url": required("string"),
+        "websiteUrl": optional("string"),
+        "__typename": one_of(&["Organization"]),
+      },
+    },
+  }
+}
+
 pub async fn find_organization_by_login(
   db: &mongodb::Database,
   login: &String,
+  fields: &Fields,
 ) -> Result<Option<Organization>, ModelError> {
   let organization_collection = db.collection::<Organization>("organizations");
 
   let filter = doc! { "login": login };
-  let options = FindOneOptions::builder().projection(doc! { "people": 0 }).build();
+  // `people` is a list of references in the documents, it is only read as a page of users
+  let projection = match fields.projection(REQUIRED_FIELDS) {
+    Some(mut projection) => {
+      projection.remove("people");
+      projection
+    }
+    None => doc! { "people": 0 },
+  };
+  let options = FindOneOptions::builder().projection(projection).build();
   let organization = organization_collection.find_one(filter, options).await?;
 
   Ok(organization)
@@ -46,9 +103,11 @@ pub async fn find_people_by_login(
   db: &mongodb::Database,
   login: &String,
   pagination_arguments: PaginationArguments,
+  viewer_id: &Option<ObjectId>,
+  fields: &Fields,
 ) -> Result<Vec<User>, ModelError> {
   let organization_collection = db.collection::<Organization>("organizations");
-  let pipeline = pipeline_paginated_people(login, pagination_arguments);
+  let pipeline = pipeline_paginated_people(login, pagination_arguments, viewer_id, fields);
   let cursor = organization_collection.aggregate(pipeline, None).await?;
   let result = model::utils::collect_into_model(cursor).await;
 
@@ -65,7 +124,7 @@ pub async fn organizations_to_cursor_connection(
     let coll_name = "users";
     let field_name = "organizations";
     let first_item_id = result.first().unwrap()._id;
-    let last_item_id = result.first().unwrap()._id;
+    let last_item_id = result.last().unwrap()._id;
 
     model::utils::pages_previous_and_next(db, user_login, &first_item_id, &last_item_id, coll_name, field_name).await
   } else {
@@ -78,7 +137,12 @@ pub async fn organizations_to_cursor_connection(
   Ok(items)
 }
 
-fn pipeline_paginated_people(login: &String, pagination_arguments: PaginationArguments) -> model::Pipeline {
+pub fn pipeline_paginated_people(
+  login: &String,
+  pagination_arguments: PaginationArguments,
+  viewer_id: &Option<ObjectId>,
+  fields: &Fields,
+) -> model::Pipeline {
   let (direction, limit, cursor) = pagination_arguments.parse_args().unwrap();
   let user_id = model::utils::to_object_id(cursor);
   let order = model::utils::to_order(&direction);
@@ -123,5 +187,8 @@ fn pipeline_paginated_people(login: &String, pagination_arguments: PaginationArg
     .chain(lookup_with_users)
     .chain(filter_by_user_id)
     .chain(paginate_items)
+    .chain(model::user::pipeline_viewer_relations(viewer_id))
+    .chain(model::user::pipeline_email_visibility(viewer_id))
+    .chain(model::utils::pipeline_fields(fields, model::user::REQUIRED_FIELDS))
     .collect()
 }
@@ -1,4 +1,4 @@
-use crate::lib::cursor_connection::PaginationArguments;
+use crate::lib::{cursor_connection::PaginationArguments, fields::Fields};
 use crate::model::organization::*;
 use crate::setup::mock;
 use pretty_assertions::assert_eq;
@@ -8,17 +8,37 @@ async fn should_find_un_existing_organization() {
   let sufix = mock::random_sufix();
   let login = format!("organization_foo_{sufix}");
   let db = mock::setup(&sufix).await;
-  let organization = find_organization_by_login(&db, &login).await.unwrap().unwrap();
+  let organization = find_organization_by_login(&db, &login, &Fields::default())
+    .await
+    .unwrap()
+    .unwrap();
 
   assert_eq!(organization.login, format!("organization_foo_{sufix}"));
 }
 
+#[actix_rt::test]
+async fn should_project_an_organization_on_the_asked_and_required_fields() {
+  let sufix = mock::random_sufix();
+  let login = format!("organization_foo_{sufix}");
+  let db = mock::setup(&sufix).await;
+  let fields = Fields::parse("login,people").unwrap();
+
+  let organization = find_organization_by_login(&db, &login, &fields).await.unwrap().unwrap();
+
+  assert_eq!(organization.login, login);
+  assert_eq!(organization.url, "https://github.com/foo");
+  assert_eq!(organization.typename, "Organization");
+  assert_eq!(organization.people, None);
+}
+
 #[actix_rt::test]
 async fn should_not_panic_when_organization_is_not_found() {
   let sufix = mock::random_sufix();
   let login = format!("organization_xxx_{sufix}");
   let db = mock::setup(&sufix).await;
-  let organization = find_organization_by_login(&db, &login).await.unwrap();
+  let organization = find_organization_by_login(&db, &login, &Fields::default())
+    .await
+    .unwrap();
 
   assert_eq!(organization, None);
 }
@@ -35,7 +55,9 @@ async fn should_find_organizations_people() {
     before: None,
   };
 
-  let users = find_people_by_login(&db, &login, pagination_argument).await.unwrap();
+  let users = find_people_by_login(&db, &login, pagination_argument, &None, &Fields::default())
+    .await
+    .unwrap();
 
   assert_eq!(users.len(), 1);
   assert_eq!(users[0].login, format!("user_foo_{sufix}"));
@@ -53,7 +75,9 @@ async fn should_dont_panic_when_person_is_not_found() {
     before: None,
   };
 
-  let users = find_people_by_login(&db, &login, pagination_argument).await.unwrap();
+  let users = find_people_by_login(&db, &login, pagination_argument, &None, &Fields::default())
+    .await
+    .unwrap();
 
   assert_eq!(users.len(), 0);
 }
@@ -64,7 +88,7 @@ async fn should_convert_a_organization_list_into_cursor_connection_of_organizati
   let user_login = format!("user_foo_{sufix}");
   let organization_login = format!("organization_foo_{sufix}");
   let db = mock::setup(&sufix).await;
-  let organization = find_organization_by_login(&db, &organization_login)
+  let organization = find_organization_by_login(&db, &organization_login, &Fields::default())
     .await
     .unwrap()
     .unwrap();
@@ -99,7 +123,9 @@ async fn should_paginating_people_from_start_to_end() {
     before: None,
   };
 
-  let users = find_people_by_login(&db, &login, pagination_arguments).await.unwrap();
+  let users = find_people_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
+    .await
+    .unwrap();
 
   assert_eq!(users.len(), 1);
   assert_eq!(users[0].login, format!("user_foo_{sufix}"));
@@ -115,7 +141,9 @@ async fn should_paginating_people_from_start_to_end() {
     before: None,
   };
 
-  let users = find_people_by_login(&db, &login, pagination_arguments).await.unwrap();
+  let users = find_people_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
+    .await
+    .unwrap();
 
   assert_eq!(users.len(), 1);
   assert_eq!(users[0].login, format!("user_dee_{sufix}"));
@@ -131,7 +159,9 @@ async fn should_paginating_people_from_start_to_end() {
     before: None,
   };
 
-  let users = find_people_by_login(&db, &login, pagination_arguments).await.unwrap();
+  let users = find_people_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
+    .await
+    .unwrap();
 
   assert_eq!(users.len(), 0);
 }
@@ -151,7 +181,9 @@ async fn should_paginating_people_from_end_to_start() {
     before: None,
   };
 
-  let users = find_people_by_login(&db, &login, pagination_arguments).await.unwrap();
+  let users = find_people_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
+    .await
+    .unwrap();
 
   assert_eq!(users.len(), 1);
   assert_eq!(users[0].login, format!("user_dee_{sufix}"));
@@ -167,7 +199,9 @@ async fn should_paginating_people_from_end_to_start() {
     before: start_cursor,
   };
 
-  let users = find_people_by_login(&db, &login, pagination_arguments).await.unwrap();
+  let users = find_people_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
+    .await
+    .unwrap();
 
   assert_eq!(users.len(), 1);
   assert_eq!(users[0].login, format!("user_foo_{sufix}"));
@@ -183,7 +217,9 @@ async fn should_paginating_people_from_end_to_start() {
     before: start_cursor,
   };
 
-  let users = find_people_by_login(&db, &login, pagination_arguments).await.unwrap();
+  let users = find_people_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
+    .await
+    .unwrap();
 
   assert_eq!(users.len(), 0);
 }
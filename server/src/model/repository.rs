@@ -1,29 +1,37 @@
-use crate::lib::cursor_connection::{CursorConnection, PaginationArguments};
-use crate::model;
+use crate::lib::{
+  cursor_connection::{CursorConnection, PaginationArguments},
+  fields::Fields,
+};
+use crate::model::{
+  self,
+  schema::{optional, required, required_fields, CollectionSchema},
+};
 use mongodb::{
   bson::{doc, oid::ObjectId, Document},
   error::Error as ModelError,
+  options::IndexOptions,
+  IndexModel,
 };
 use serde::{Deserialize, Serialize};
 use tokio_stream::StreamExt;
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct Language {
   pub color: String,
   pub name: String,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct License {
   pub name: String,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct Owner {
   pub _id: ObjectId,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Repository {
   #[serde(rename = "_id")]
@@ -36,15 +44,55 @@ pub struct Repository {
   pub owner: Owner,
   #[serde(skip_serializing_if = "Option::is_none")]
   pub primary_language: Option<Language>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub viewer_has_starred: Option<bool>,
+}
+
+/// The non optional fields of `Repository`
+pub const REQUIRED_FIELDS: &[&str] = &["forkCount", "name", "owner"];
+
+pub fn collection_schema() -> CollectionSchema {
+  CollectionSchema {
+    collection: "repositories",
+    indexes: vec![
+      IndexModel::builder()
+        .keys(doc! { "owner._id": 1, "_id": 1 })
+        .options(IndexOptions::builder().name("owner_id".to_string()).build())
+        .build(),
+      IndexModel::builder()
+        .keys(doc! { "name": "text", "description": "text" })
+        .options(IndexOptions::builder().name("search_text".to_string()).build())
+        .build(),
+    ],
+    json_schema: doc! {
+      "bsonType": "object",
+      "required": required_fields(REQUIRED_FIELDS),
+      "properties": {
+        "_id": required("objectId"),
+        "description": optional("string"),
+        "forkCount": required("number"),
+        "licenseInfo": optional("object"),
+        "name": required("string"),
+        "owner": {
+          "bsonType": "object",
+          "required": ["_id"],
+          "properties": { "_id": required("objectId") },
+        },
+        "primaryLanguage": optional("object"),
+      },
+    },
+  }
 }
 
 pub async fn find_repositories_by_owner_id(
   db: &mongodb::Database,
   owner_id: &ObjectId,
   pagination_arguments: PaginationArguments,
+  viewer_id: &Option<ObjectId>,
+  fields: &Fields,
 ) -> Result<Vec<Repository>, ModelError> {
   let repo_collection = db.collection::<Repository>("repositories");
-  let pipeline = pipeline_paginated_repositories(pagination_arguments, owner_id);
+  let pipeline = pipeline_paginated_repositories(pagination_arguments, owner_id, viewer_id, fields);
   let cursor = repo_collection.aggregate(pipeline, None).await?;
   let items = model::utils::collect_into_model(cursor).await;
 
@@ -59,7 +107,7 @@ pub async fn repositories_to_cursor_connection(
   let result = result?;
   let (has_previous_page, has_next_page) = if result.len() > 0 {
     let first_item_id = result.first().unwrap()._id;
-    let last_item_id = result.first().unwrap()._id;
+    let last_item_id = result.last().unwrap()._id;
 
     pages_previous_and_next(db, owner_id, &first_item_id, &last_item_id).await
   } else {
@@ -118,7 +166,12 @@ fn pipeline_pages_previous_and_next(
   model::utils::pipeline_convert_result_values_into_booleans(pipeline_previous_page, pipeline_next_page)
 }
 
-fn pipeline_paginated_repositories(pagination_arguments: PaginationArguments, owner_id: &ObjectId) -> model::Pipeline {
+pub fn pipeline_paginated_repositories(
+  pagination_arguments: PaginationArguments,
+  owner_id: &ObjectId,
+  viewer_id: &Option<ObjectId>,
+  fields: &Fields,
+) -> model::Pipeline {
   let (direction, limit, cursor) = pagination_arguments.parse_args().unwrap();
   let order = model::utils::to_order(&direction);
   let operator = model::utils::to_operator(&direction);
@@ -135,5 +188,36 @@ fn pipeline_paginated_repositories(pagination_arguments: PaginationArguments, ow
     doc! { "$sort": { "_id": 1 } },
   ];
 
-  vec![].into_iter().chain(filter_by_owner_id).chain(paginate).collect()
+  vec![]
+    .into_iter()
+    .chain(filter_by_owner_id)
+    .chain(paginate)
+    .chain(pipeline_viewer_has_starred(viewer_id))
+    .chain(model::utils::pipeline_fields(fields, REQUIRED_FIELDS))
+    .collect()
+}
+
+pub fn pipeline_viewer_has_starred(viewer_id: &Option<ObjectId>) -> model::Pipeline {
+  match viewer_id {
+    None => vec![],
+    Some(viewer_id) => vec![
+      doc! { "$lookup": {
+        "from": "users",
+        "pipeline": [
+          { "$match": { "_id": viewer_id } },
+          { "$project": { "_id": 0, "starredRepositories": 1 } },
+        ],
+        "as": "viewer",
+      } },
+      doc! { "$addFields": {
+        "viewerHasStarred": {
+          "$in": [
+            "$_id",
+            { "$ifNull": [ { "$arrayElemAt": [ "$viewer.starredRepositories._id", 0 ] }, [] ] }
+          ]
+        },
+      } },
+      doc! { "$project": { "viewer": 0 } },
+    ],
+  }
 }
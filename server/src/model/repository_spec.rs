@@ -1,4 +1,4 @@
-use crate::lib::cursor_connection::PaginationArguments;
+use crate::lib::{cursor_connection::PaginationArguments, fields::Fields};
 use crate::model::{organization, repository::*, user};
 use crate::setup::mock;
 use pretty_assertions::assert_eq;
@@ -8,7 +8,10 @@ async fn should_find_users_repositories() {
   let sufix = mock::random_sufix();
   let login = format!("user_bar_{sufix}");
   let db = mock::setup(&sufix).await;
-  let user = user::find_user_by_login(&db, &login).await.unwrap().unwrap();
+  let user = user::find_user_by_login(&db, &login, &None, &Fields::default())
+    .await
+    .unwrap()
+    .unwrap();
   let pagination_argument = PaginationArguments {
     first: Some(1),
     after: None,
@@ -16,7 +19,7 @@ async fn should_find_users_repositories() {
     before: None,
   };
 
-  let repositories = find_repositories_by_owner_id(&db, &user._id, pagination_argument)
+  let repositories = find_repositories_by_owner_id(&db, &user._id, pagination_argument, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -29,7 +32,10 @@ async fn should_dont_panic_when_repository_is_not_found() {
   let sufix = mock::random_sufix();
   let login = format!("empty_user_{sufix}");
   let db = mock::setup(&sufix).await;
-  let user = user::find_user_by_login(&db, &login).await.unwrap().unwrap();
+  let user = user::find_user_by_login(&db, &login, &None, &Fields::default())
+    .await
+    .unwrap()
+    .unwrap();
   let pagination_argument = PaginationArguments {
     first: Some(1),
     after: None,
@@ -37,19 +43,56 @@ async fn should_dont_panic_when_repository_is_not_found() {
     before: None,
   };
 
-  let repositories = find_repositories_by_owner_id(&db, &user._id, pagination_argument)
+  let repositories = find_repositories_by_owner_id(&db, &user._id, pagination_argument, &None, &Fields::default())
     .await
     .unwrap();
 
   assert_eq!(repositories.len(), 0);
 }
 
+#[actix_rt::test]
+async fn should_compute_whether_the_viewer_has_starred_the_repositories() {
+  let sufix = mock::random_sufix();
+  let owner_login = format!("organization_acme_{sufix}");
+  let viewer_login = format!("user_bar_{sufix}");
+  let db = mock::setup(&sufix).await;
+  let owner = organization::find_organization_by_login(&db, &owner_login, &Fields::default())
+    .await
+    .unwrap()
+    .unwrap();
+  let viewer = user::find_user_by_login(&db, &viewer_login, &None, &Fields::default())
+    .await
+    .unwrap()
+    .unwrap();
+  let pagination_argument = PaginationArguments {
+    first: Some(2),
+    after: None,
+    last: None,
+    before: None,
+  };
+
+  let repositories = find_repositories_by_owner_id(
+    &db,
+    &owner._id,
+    pagination_argument,
+    &Some(viewer._id),
+    &Fields::default(),
+  )
+  .await
+  .unwrap();
+
+  assert_eq!(repositories[0].name, format!("repository_tux_{sufix}"));
+  assert_eq!(repositories[0].viewer_has_starred, Some(true));
+  assert_eq!(repositories[1].name, format!("repository_mar_{sufix}"));
+  assert_eq!(repositories[1].viewer_has_starred, Some(false));
+}
+
 #[actix_rt::test]
 async fn should_convert_a_repository_list_into_cursor_connection_of_repositories() {
   let sufix = mock::random_sufix();
   let owner_login = format!("organization_acme_{sufix}");
   let db = mock::setup(&sufix).await;
-  let organization = organization::find_organization_by_login(&db, &owner_login)
+  let organization = organization::find_organization_by_login(&db, &owner_login, &Fields::default())
     .await
     .unwrap()
     .unwrap();
@@ -61,9 +104,10 @@ async fn should_convert_a_repository_list_into_cursor_connection_of_repositories
     before: None,
   };
 
-  let repositories = find_repositories_by_owner_id(&db, &organization._id, pagination_arguments)
-    .await
-    .unwrap();
+  let repositories =
+    find_repositories_by_owner_id(&db, &organization._id, pagination_arguments, &None, &Fields::default())
+      .await
+      .unwrap();
 
   let cursor_connection = repositories_to_cursor_connection(&db, &organization._id, Ok(repositories))
     .await
@@ -82,7 +126,7 @@ async fn should_paginating_repositories_from_start_to_end() {
   let sufix = mock::random_sufix();
   let login = format!("organization_acme_{sufix}");
   let db = mock::setup(&sufix).await;
-  let owner = organization::find_organization_by_login(&db, &login).await;
+  let owner = organization::find_organization_by_login(&db, &login, &Fields::default()).await;
   let owner = owner.unwrap().unwrap();
 
   // should find the first repository
@@ -94,7 +138,7 @@ async fn should_paginating_repositories_from_start_to_end() {
     before: None,
   };
 
-  let repositories = find_repositories_by_owner_id(&db, &owner._id, pagination_arguments)
+  let repositories = find_repositories_by_owner_id(&db, &owner._id, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -112,7 +156,7 @@ async fn should_paginating_repositories_from_start_to_end() {
     before: None,
   };
 
-  let repositories = find_repositories_by_owner_id(&db, &owner._id, pagination_arguments)
+  let repositories = find_repositories_by_owner_id(&db, &owner._id, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -130,7 +174,7 @@ async fn should_paginating_repositories_from_start_to_end() {
     before: None,
   };
 
-  let repositories = find_repositories_by_owner_id(&db, &owner._id, pagination_arguments)
+  let repositories = find_repositories_by_owner_id(&db, &owner._id, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -142,7 +186,7 @@ async fn should_paginating_repositories_from_end_to_start() {
   let sufix = mock::random_sufix();
   let login = format!("organization_acme_{sufix}");
   let db = mock::setup(&sufix).await;
-  let owner = organization::find_organization_by_login(&db, &login).await;
+  let owner = organization::find_organization_by_login(&db, &login, &Fields::default()).await;
   let owner = owner.unwrap().unwrap();
 
   // should find the last repository
@@ -154,7 +198,7 @@ async fn should_paginating_repositories_from_end_to_start() {
     before: None,
   };
 
-  let repositories = find_repositories_by_owner_id(&db, &owner._id, pagination_arguments)
+  let repositories = find_repositories_by_owner_id(&db, &owner._id, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -172,7 +216,7 @@ async fn should_paginating_repositories_from_end_to_start() {
     before: start_cursor,
   };
 
-  let repositories = find_repositories_by_owner_id(&db, &owner._id, pagination_arguments)
+  let repositories = find_repositories_by_owner_id(&db, &owner._id, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -190,7 +234,7 @@ async fn should_paginating_repositories_from_end_to_start() {
     before: start_cursor,
   };
 
-  let repositories = find_repositories_by_owner_id(&db, &owner._id, pagination_arguments)
+  let repositories = find_repositories_by_owner_id(&db, &owner._id, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -0,0 +1,51 @@
+use crate::model::{organization, repository, user};
+use mongodb::{
+  bson::{doc, Document},
+  IndexModel,
+};
+
+/// What a collection needs from the database: the indexes its queries rely on and the `$jsonSchema` mirroring its
+/// model struct. Fields that are not part of the struct are allowed, the seed data carries more than the API exposes.
+pub struct CollectionSchema {
+  pub collection: &'static str,
+  pub indexes: Vec<IndexModel>,
+  pub json_schema: Document,
+}
+
+pub fn collection_schemas() -> Vec<CollectionSchema> {
+  vec![
+    organization::collection_schema(),
+    repository::collection_schema(),
+    user::collection_schema(),
+  ]
+}
+
+/// The `required` list of a model, its `_id` and the `REQUIRED_FIELDS` of its struct. The same constants are kept
+/// by every projection, a document missing one of them could not be deserialized.
+pub fn required_fields(fields: &[&'static str]) -> Vec<&'static str> {
+  std::iter::once("_id").chain(fields.iter().copied()).collect()
+}
+
+pub fn required(bson_type: &str) -> Document {
+  doc! { "bsonType": bson_type }
+}
+
+pub fn optional(bson_type: &str) -> Document {
+  doc! { "bsonType": [bson_type, "null"] }
+}
+
+pub fn one_of(values: &[&str]) -> Document {
+  doc! { "enum": values }
+}
+
+/// References to documents of other collections, stored as `{ _id, ref }`
+pub fn references() -> Document {
+  doc! {
+    "bsonType": "array",
+    "items": {
+      "bsonType": "object",
+      "required": ["_id"],
+      "properties": { "_id": required("objectId") },
+    },
+  }
+}
@@ -0,0 +1,162 @@
+use crate::model::{organization::Organization, repository::Repository, schema::collection_schemas, user::User};
+use mongodb::bson::{self, oid::ObjectId, Bson, Document};
+use pretty_assertions::assert_eq;
+use serde::{
+  de::{self, value, DeserializeOwned, Visitor},
+  forward_to_deserialize_any, Deserializer,
+};
+
+#[test]
+fn should_name_every_index() {
+  let unnamed_indexes = collection_schemas()
+    .into_iter()
+    .flat_map(|schema| schema.indexes)
+    .filter(|index| {
+      index
+        .options
+        .as_ref()
+        .and_then(|options| options.name.as_ref())
+        .is_none()
+    })
+    .count();
+
+  assert_eq!(unnamed_indexes, 0);
+}
+
+#[test]
+fn should_require_the_login_to_be_unique() {
+  for schema in collection_schemas()
+    .into_iter()
+    .filter(|schema| schema.collection != "repositories")
+  {
+    let login_index = schema
+      .indexes
+      .iter()
+      .find(|index| index.keys.get_i32("login") == Ok(1))
+      .unwrap();
+
+    assert_eq!(login_index.options.as_ref().unwrap().unique, Some(true));
+  }
+}
+
+#[test]
+fn should_require_the_fields_the_models_can_not_miss() {
+  let users = collection_schemas()
+    .into_iter()
+    .find(|schema| schema.collection == "users")
+    .unwrap();
+  let required = users.json_schema.get_array("required").unwrap();
+
+  assert!(required.iter().any(|field| field.as_str() == Some("login")));
+  assert!(!required.iter().any(|field| field.as_str() == Some("email")));
+}
+
+/// A deserializer reading the names of the fields of a struct from its derived `Deserialize`
+struct FieldNames<'a>(&'a mut Vec<&'static str>);
+
+impl<'de, 'a> Deserializer<'de> for FieldNames<'a> {
+  type Error = value::Error;
+
+  fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+    Err(de::Error::custom("not a struct"))
+  }
+
+  fn deserialize_struct<V: Visitor<'de>>(
+    self,
+    _name: &'static str,
+    fields: &'static [&'static str],
+    _visitor: V,
+  ) -> Result<V::Value, Self::Error> {
+    self.0.extend(fields);
+    Err(de::Error::custom("fields read"))
+  }
+
+  forward_to_deserialize_any! {
+    bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit unit_struct
+    newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+  }
+}
+
+fn field_names<T: DeserializeOwned>() -> Vec<&'static str> {
+  let mut names = vec![];
+  let _ = T::deserialize(FieldNames(&mut names));
+  names
+}
+
+/// The smallest document valid for a `$jsonSchema`, made of its required fields
+fn sample_document(json_schema: &Document) -> Document {
+  let properties = json_schema.get_document("properties").unwrap();
+  json_schema
+    .get_array("required")
+    .unwrap()
+    .iter()
+    .map(|name| {
+      let name = name.as_str().unwrap();
+      (name.to_string(), sample(properties.get_document(name).unwrap()))
+    })
+    .collect()
+}
+
+fn sample(property: &Document) -> Bson {
+  if let Ok(values) = property.get_array("enum") {
+    return values[0].clone();
+  }
+  match property.get("bsonType") {
+    Some(Bson::String(bson_type)) if bson_type == "objectId" => Bson::ObjectId(ObjectId::new()),
+    Some(Bson::String(bson_type)) if bson_type == "string" => Bson::String("sample".to_string()),
+    Some(Bson::String(bson_type)) if bson_type == "number" => Bson::Double(1.0),
+    Some(Bson::String(bson_type)) if bson_type == "array" => Bson::Array(vec![]),
+    _ => Bson::Document(sample_document(property)),
+  }
+}
+
+/// Whether the model of the collection reads a document, and the fields of its struct stored in the documents
+fn model_of(collection: &str) -> (fn(Document) -> bool, Vec<&'static str>) {
+  match collection {
+    "organizations" => (
+      |document| bson::from_document::<Organization>(document).is_ok(),
+      field_names::<Organization>(),
+    ),
+    "repositories" => (
+      |document| bson::from_document::<Repository>(document).is_ok(),
+      field_names::<Repository>(),
+    ),
+    "users" => (
+      |document| bson::from_document::<User>(document).is_ok(),
+      field_names::<User>(),
+    ),
+    collection => panic!("No model for {}", collection),
+  }
+}
+
+#[test]
+fn should_match_the_validators_with_the_model_structs() {
+  // computed by the queries for the viewer, never stored
+  let computed = ["viewerHasStarred", "viewerIsFollowing", "isFollowingViewer"];
+
+  for schema in collection_schemas() {
+    let (reads, fields) = model_of(schema.collection);
+    let document = sample_document(&schema.json_schema);
+    let properties = schema.json_schema.get_document("properties").unwrap();
+
+    assert!(reads(document.clone()), "{} misses a required field", schema.collection);
+    for name in document.keys() {
+      let mut partial = document.clone();
+      partial.remove(name);
+      assert!(
+        !reads(partial),
+        "{}.{} is optional in the model",
+        schema.collection,
+        name
+      );
+    }
+    for name in fields.iter().filter(|name| !computed.contains(name)) {
+      assert!(
+        properties.contains_key(name),
+        "{}.{} has no property",
+        schema.collection,
+        name
+      );
+    }
+  }
+}
@@ -0,0 +1,217 @@
+mod cached;
+mod memory;
+mod mongo;
+mod sqlite;
+
+pub use cached::{invalidate_on_changes, CachedStore, QueryCache};
+pub use memory::MemoryStore;
+pub use mongo::MongoStore;
+pub use sqlite::SqliteStore;
+
+use crate::lib::{
+  cursor_connection::{CursorConnection, Edges, PaginationArguments},
+  fields::Fields,
+};
+use crate::model::{organization::Organization, repository::Repository, user::User};
+use futures::{
+  future::BoxFuture,
+  stream::{self, BoxStream, StreamExt, TryStreamExt},
+};
+use mongodb::{bson::oid::ObjectId, error::Error as ModelError};
+use serde::Serialize;
+
+/// The size of the pages read by the default `Store::stream_list`
+const STREAM_PAGE_SIZE: u32 = 100;
+
+/// The lists of the routes that can be streamed whole, identified by their owner
+#[derive(Debug, Clone, PartialEq)]
+pub enum List {
+  OrganizationsOfUser(String),
+  StarredRepositories(String),
+  Followers(String),
+  Following(String),
+  People(String),
+  RepositoriesByOwnerId(ObjectId),
+}
+
+/// An item of a streamed list
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Node {
+  User(User),
+  Organization(Organization),
+  Repository(Repository),
+}
+
+/// The queries of the routes, independent of where the data is stored. The lists are paginated with the same
+/// semantics on every backend: items are ordered by `_id`, the cursor is the hex `_id` of an item and the page info
+/// tells whether the list has items before the first and after the last item of the page.
+pub trait Store: Send + Sync {
+  fn find_user_by_login<'a>(
+    &'a self,
+    login: &'a str,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<Option<User>, ModelError>>;
+
+  fn find_organization_by_login<'a>(
+    &'a self,
+    login: &'a str,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<Option<Organization>, ModelError>>;
+
+  fn find_organizations_of_user<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<Organization>, ModelError>>;
+
+  fn find_starred_repositories<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<Repository>, ModelError>>;
+
+  fn find_followers<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<User>, ModelError>>;
+
+  fn find_following<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<User>, ModelError>>;
+
+  fn find_people<'a>(
+    &'a self,
+    organization_login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<User>, ModelError>>;
+
+  fn find_repositories_by_owner_id<'a>(
+    &'a self,
+    owner_id: &'a ObjectId,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<Repository>, ModelError>>;
+
+  /// Streams up to `limit` items of a list after the `after` cursor, in the order of its pages and with the same
+  /// cursors. The default reads the list one page at a time, a backend with database cursors can stream it instead.
+  fn stream_list<'a>(
+    &'a self,
+    list: &'a List,
+    after: Option<String>,
+    limit: u32,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxStream<'a, Result<Edges<Node>, ModelError>> {
+    stream::try_unfold((after, limit), move |(after, remaining)| async move {
+      if remaining == 0 {
+        return Ok::<_, ModelError>(None);
+      }
+
+      let arguments = PaginationArguments {
+        first: Some(remaining.min(STREAM_PAGE_SIZE)),
+        after,
+        last: None,
+        before: None,
+      };
+      let (edges, has_next_page) = find_page(self, list, arguments, viewer_id, fields).await?;
+      let end_cursor = edges.last().map(|edge| edge.cursor.clone());
+      let remaining = match (has_next_page, &end_cursor) {
+        (true, Some(_)) => remaining - edges.len() as u32,
+        _ => 0,
+      };
+
+      Ok(Some((stream::iter(edges.into_iter().map(Ok)), (end_cursor, remaining))))
+    })
+    .try_flatten()
+    .boxed()
+  }
+}
+
+async fn find_page<S: Store + ?Sized>(
+  store: &S,
+  list: &List,
+  arguments: PaginationArguments,
+  viewer_id: &Option<ObjectId>,
+  fields: &Fields,
+) -> Result<(Vec<Edges<Node>>, bool), ModelError> {
+  match list {
+    List::OrganizationsOfUser(login) => {
+      let page = store.find_organizations_of_user(login, arguments, fields).await?;
+      Ok(into_edges(page, Node::Organization))
+    }
+    List::StarredRepositories(login) => {
+      let page = store
+        .find_starred_repositories(login, arguments, viewer_id, fields)
+        .await?;
+      Ok(into_edges(page, Node::Repository))
+    }
+    List::Followers(login) => {
+      let page = store.find_followers(login, arguments, viewer_id, fields).await?;
+      Ok(into_edges(page, Node::User))
+    }
+    List::Following(login) => {
+      let page = store.find_following(login, arguments, viewer_id, fields).await?;
+      Ok(into_edges(page, Node::User))
+    }
+    List::People(login) => {
+      let page = store.find_people(login, arguments, viewer_id, fields).await?;
+      Ok(into_edges(page, Node::User))
+    }
+    List::RepositoriesByOwnerId(owner_id) => {
+      let page = store
+        .find_repositories_by_owner_id(owner_id, arguments, viewer_id, fields)
+        .await?;
+      Ok(into_edges(page, Node::Repository))
+    }
+  }
+}
+
+fn into_edges<T>(page: CursorConnection<T>, into_node: fn(T) -> Node) -> (Vec<Edges<Node>>, bool) {
+  let edges = page
+    .edges
+    .into_iter()
+    .map(|edge| Edges {
+      cursor: edge.cursor,
+      node: into_node(edge.node),
+    })
+    .collect();
+  (edges, page.page_info.has_next_page)
+}
+
+/// The models listed in a `CursorConnection`, their `_id` is the cursor
+trait HasId {
+  fn id(&self) -> ObjectId;
+}
+impl HasId for User {
+  fn id(&self) -> ObjectId {
+    self._id
+  }
+}
+impl HasId for Organization {
+  fn id(&self) -> ObjectId {
+    self._id
+  }
+}
+impl HasId for Repository {
+  fn id(&self) -> ObjectId {
+    self._id
+  }
+}
+
+#[cfg(test)]
+mod cached_spec;
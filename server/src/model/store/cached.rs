@@ -0,0 +1,555 @@
+use crate::lib::{
+  cursor_connection::{CursorConnection, Edges, PaginationArguments},
+  fields::Fields,
+};
+use crate::model::{
+  organization::Organization,
+  repository::Repository,
+  store::{List, Node, Store},
+  user::User,
+};
+use crate::setup::{
+  config::{CacheConfig, RetryConfig},
+  db::backoff_delays,
+  metrics::Metrics,
+};
+use futures::{
+  future::{BoxFuture, FutureExt},
+  stream::{BoxStream, StreamExt},
+};
+use lru::LruCache;
+use mongodb::{
+  bson::{doc, oid::ObjectId, Document},
+  change_stream::event::{OperationType, ResumeToken},
+  error::{Error as ModelError, ErrorKind},
+  options::{ChangeStreamOptions, FullDocumentType},
+  Database,
+};
+use std::{
+  collections::{HashMap, HashSet},
+  num::NonZeroUsize,
+  sync::{Arc, Mutex},
+  time::{Duration, Instant},
+};
+use tokio::time;
+use tracing;
+
+/// A result of a store query, the lists are cached page by page
+#[derive(Clone)]
+enum Cached {
+  User(Option<User>),
+  Organization(Option<Organization>),
+  Organizations(CursorConnection<Organization>),
+  Repositories(CursorConnection<Repository>),
+  Users(CursorConnection<User>),
+}
+
+#[derive(Hash, PartialEq, Eq, Clone, Debug)]
+struct Key {
+  query: &'static str,
+  /// The login, or the hex `_id` of the owner of the repositories
+  owner: String,
+  pagination_arguments: Option<PaginationArguments>,
+  viewer_id: Option<ObjectId>,
+  fields: Fields,
+}
+impl Key {
+  fn new(
+    query: &'static str,
+    owner: &str,
+    pagination_arguments: Option<&PaginationArguments>,
+    viewer_id: &Option<ObjectId>,
+    fields: &Fields,
+  ) -> Self {
+    Self {
+      query,
+      owner: owner.to_string(),
+      pagination_arguments: pagination_arguments.cloned(),
+      viewer_id: *viewer_id,
+      fields: fields.clone(),
+    }
+  }
+}
+
+struct Entry {
+  value: Cached,
+  /// The logins and hex `_id`s the result depends on, an invalidation of one of them removes the entry
+  tags: HashSet<String>,
+  expires_at: Instant,
+}
+
+/// The cached results, and the keys of the results of each tag so an invalidation only visits the removed results
+struct Entries {
+  lru: LruCache<Key, Entry>,
+  tagged: HashMap<String, HashSet<Key>>,
+}
+
+impl Entries {
+  /// Caches a result, returns the least recently used one evicted to make room for it
+  fn push(&mut self, key: Key, entry: Entry) -> Option<Entry> {
+    self.pop(&key);
+    for tag in &entry.tags {
+      self.tagged.entry(tag.clone()).or_default().insert(key.clone());
+    }
+    let (evicted_key, evicted) = self.lru.push(key, entry)?;
+    self.untag(&evicted_key, &evicted.tags);
+
+    Some(evicted)
+  }
+
+  fn pop(&mut self, key: &Key) -> Option<Entry> {
+    let entry = self.lru.pop(key)?;
+    self.untag(key, &entry.tags);
+
+    Some(entry)
+  }
+
+  fn untag(&mut self, key: &Key, tags: &HashSet<String>) {
+    for tag in tags {
+      if let Some(keys) = self.tagged.get_mut(tag) {
+        keys.remove(key);
+        if keys.is_empty() {
+          self.tagged.remove(tag);
+        }
+      }
+    }
+  }
+
+  fn tagged(&self, tag: &str) -> impl Iterator<Item = &Key> {
+    self.tagged.get(tag).into_iter().flatten()
+  }
+}
+
+/// The results of the store queries kept in memory, the least recently used one is evicted when the cache is full and
+/// a result older than the TTL is read again from the store.
+pub struct QueryCache {
+  entries: Mutex<Entries>,
+  ttl: Duration,
+  metrics: Arc<Metrics>,
+}
+
+impl QueryCache {
+  pub fn new(config: &CacheConfig, metrics: Arc<Metrics>) -> Self {
+    let capacity = NonZeroUsize::new(config.max_entries).unwrap_or(NonZeroUsize::new(1).unwrap());
+    Self {
+      entries: Mutex::new(Entries {
+        lru: LruCache::new(capacity),
+        tagged: HashMap::new(),
+      }),
+      ttl: Duration::from_secs(config.ttl_secs),
+      metrics,
+    }
+  }
+
+  fn get(&self, key: &Key) -> Option<Cached> {
+    let mut entries = self.entries.lock().unwrap();
+    let (value, outcome) = match entries.lru.get(key) {
+      Some(entry) if entry.expires_at > Instant::now() => (Some(entry.value.clone()), "hit"),
+      Some(_) => {
+        entries.pop(key);
+        self.evicted("expired", 1, entries.lru.len());
+        (None, "miss")
+      }
+      None => (None, "miss"),
+    };
+    self
+      .metrics
+      .cache_requests_total
+      .with_label_values(&[key.query, outcome])
+      .inc();
+
+    value
+  }
+
+  fn put(&self, key: Key, value: Cached, tags: HashSet<String>) {
+    let mut entries = self.entries.lock().unwrap();
+    let entry = Entry {
+      value,
+      tags,
+      expires_at: Instant::now() + self.ttl,
+    };
+    if entries.push(key, entry).is_some() {
+      self.evicted("capacity", 1, entries.lru.len());
+    }
+    self.metrics.cache_entries.set(entries.lru.len() as i64);
+  }
+
+  /// Removes the results that depend on a login or a hex `_id`, a user or an organization is known by both so
+  /// invalidating one also removes the results cached under the other. Returns how many results were removed.
+  pub fn invalidate(&self, login_or_id: &str) -> usize {
+    let mut entries = self.entries.lock().unwrap();
+    // an object is tagged by the login of its key and its `_id`, whatever fields were asked
+    let aliases: HashSet<String> = entries
+      .tagged(login_or_id)
+      .filter(|key| matches!(key.query, "user" | "organization"))
+      .filter_map(|key| entries.lru.peek(key))
+      .flat_map(|entry| entry.tags.iter().cloned())
+      .chain(std::iter::once(login_or_id.to_string()))
+      .collect();
+
+    let keys: HashSet<Key> = aliases
+      .iter()
+      .flat_map(|alias| entries.tagged(alias))
+      .cloned()
+      .collect();
+    for key in &keys {
+      entries.pop(key);
+    }
+    self.evicted("invalidated", keys.len(), entries.lru.len());
+
+    keys.len()
+  }
+
+  /// Removes every result, returns how many were removed
+  pub fn clear(&self) -> usize {
+    let mut entries = self.entries.lock().unwrap();
+    let count = entries.lru.len();
+    entries.lru.clear();
+    entries.tagged.clear();
+    self.evicted("invalidated", count, 0);
+
+    count
+  }
+
+  fn evicted(&self, reason: &str, count: usize, len: usize) {
+    self
+      .metrics
+      .cache_evictions_total
+      .with_label_values(&[reason])
+      .inc_by(count as u64);
+    self.metrics.cache_entries.set(len as i64);
+  }
+}
+
+/// The result types of the store queries that can be cached
+trait Cacheable: Clone + Sized {
+  fn into_cached(self) -> Cached;
+  fn from_cached(cached: Cached) -> Option<Self>;
+  /// The hex `_id`s of the documents in the result
+  fn tags(&self) -> Vec<String>;
+}
+
+macro_rules! cacheable {
+  ($type:ty, $variant:ident, $tags:expr) => {
+    impl Cacheable for $type {
+      fn into_cached(self) -> Cached {
+        Cached::$variant(self)
+      }
+
+      fn from_cached(cached: Cached) -> Option<Self> {
+        match cached {
+          Cached::$variant(value) => Some(value),
+          _ => None,
+        }
+      }
+
+      fn tags(&self) -> Vec<String> {
+        $tags(self)
+      }
+    }
+  };
+}
+
+cacheable!(Option<User>, User, |user: &Option<User>| user
+  .iter()
+  .flat_map(Tags::tags)
+  .collect());
+cacheable!(Option<Organization>, Organization, |organization: &Option<
+  Organization,
+>| {
+  organization.iter().flat_map(Tags::tags).collect()
+});
+cacheable!(CursorConnection<Organization>, Organizations, connection_tags);
+cacheable!(CursorConnection<Repository>, Repositories, connection_tags);
+cacheable!(CursorConnection<User>, Users, connection_tags);
+
+fn connection_tags<T: Tags>(connection: &CursorConnection<T>) -> Vec<String> {
+  connection.edges.iter().flat_map(|edge| edge.node.tags()).collect()
+}
+
+/// The identifier a document is invalidated by, its login can be missing from the asked fields so only the login of
+/// the query key is used
+trait Tags {
+  fn tags(&self) -> Vec<String>;
+}
+impl Tags for User {
+  fn tags(&self) -> Vec<String> {
+    vec![self._id.to_hex()]
+  }
+}
+impl Tags for Organization {
+  fn tags(&self) -> Vec<String> {
+    vec![self._id.to_hex()]
+  }
+}
+impl Tags for Repository {
+  fn tags(&self) -> Vec<String> {
+    vec![self._id.to_hex()]
+  }
+}
+
+/// A store that answers the queries from a `QueryCache` and reads the missing results from another store. The
+/// streamed lists always come from the other store.
+pub struct CachedStore {
+  inner: Arc<dyn Store>,
+  cache: Arc<QueryCache>,
+}
+
+impl CachedStore {
+  pub fn new(inner: Arc<dyn Store>, cache: Arc<QueryCache>) -> Self {
+    Self { inner, cache }
+  }
+
+  async fn cached<T: Cacheable>(&self, key: Key, load: BoxFuture<'_, Result<T, ModelError>>) -> Result<T, ModelError> {
+    if let Some(value) = self.cache.get(&key).and_then(T::from_cached) {
+      return Ok(value);
+    }
+
+    let value = load.await?;
+    let mut tags: HashSet<String> = value.tags().into_iter().collect();
+    tags.insert(key.owner.clone());
+    self.cache.put(key, value.clone().into_cached(), tags);
+
+    Ok(value)
+  }
+}
+
+impl Store for CachedStore {
+  fn find_user_by_login<'a>(
+    &'a self,
+    login: &'a str,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<Option<User>, ModelError>> {
+    let key = Key::new("user", login, None, viewer_id, fields);
+    self
+      .cached(key, self.inner.find_user_by_login(login, viewer_id, fields))
+      .boxed()
+  }
+
+  fn find_organization_by_login<'a>(
+    &'a self,
+    login: &'a str,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<Option<Organization>, ModelError>> {
+    let key = Key::new("organization", login, None, &None, fields);
+    self
+      .cached(key, self.inner.find_organization_by_login(login, fields))
+      .boxed()
+  }
+
+  fn find_organizations_of_user<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<Organization>, ModelError>> {
+    let key = Key::new(
+      "organizations_of_user",
+      login,
+      Some(&pagination_arguments),
+      &None,
+      fields,
+    );
+    self
+      .cached(
+        key,
+        self
+          .inner
+          .find_organizations_of_user(login, pagination_arguments, fields),
+      )
+      .boxed()
+  }
+
+  fn find_starred_repositories<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<Repository>, ModelError>> {
+    let key = Key::new(
+      "starred_repositories",
+      login,
+      Some(&pagination_arguments),
+      viewer_id,
+      fields,
+    );
+    let load = self
+      .inner
+      .find_starred_repositories(login, pagination_arguments, viewer_id, fields);
+    self.cached(key, load).boxed()
+  }
+
+  fn find_followers<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<User>, ModelError>> {
+    let key = Key::new("followers", login, Some(&pagination_arguments), viewer_id, fields);
+    let load = self
+      .inner
+      .find_followers(login, pagination_arguments, viewer_id, fields);
+    self.cached(key, load).boxed()
+  }
+
+  fn find_following<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<User>, ModelError>> {
+    let key = Key::new("following", login, Some(&pagination_arguments), viewer_id, fields);
+    let load = self
+      .inner
+      .find_following(login, pagination_arguments, viewer_id, fields);
+    self.cached(key, load).boxed()
+  }
+
+  fn find_people<'a>(
+    &'a self,
+    organization_login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<User>, ModelError>> {
+    let key = Key::new(
+      "people",
+      organization_login,
+      Some(&pagination_arguments),
+      viewer_id,
+      fields,
+    );
+    let load = self
+      .inner
+      .find_people(organization_login, pagination_arguments, viewer_id, fields);
+    self.cached(key, load).boxed()
+  }
+
+  fn find_repositories_by_owner_id<'a>(
+    &'a self,
+    owner_id: &'a ObjectId,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<Repository>, ModelError>> {
+    let key = Key::new(
+      "repositories_by_owner_id",
+      &owner_id.to_hex(),
+      Some(&pagination_arguments),
+      viewer_id,
+      fields,
+    );
+    let load = self
+      .inner
+      .find_repositories_by_owner_id(owner_id, pagination_arguments, viewer_id, fields);
+    self.cached(key, load).boxed()
+  }
+
+  fn stream_list<'a>(
+    &'a self,
+    list: &'a List,
+    after: Option<String>,
+    limit: u32,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxStream<'a, Result<Edges<Node>, ModelError>> {
+    self.inner.stream_list(list, after, limit, viewer_id, fields).boxed()
+  }
+}
+
+/// The error of a change stream resumed after a change no longer in the oplog
+const CHANGE_STREAM_HISTORY_LOST: i32 = 286;
+
+/// Invalidates the results of the users, organizations and repositories changed in the database, including by other
+/// processes like the imports. It needs a replica set, without it the results are only refreshed by their TTL. An
+/// interrupted stream is opened again after the last change seen, waiting longer after each failure.
+pub async fn invalidate_on_changes(db: Database, cache: Arc<QueryCache>, retry: RetryConfig) {
+  let pipeline = [doc! {
+    "$match": { "ns.coll": { "$in": [ "users", "organizations", "repositories" ] } }
+  }];
+  let mut resume_token: Option<ResumeToken> = None;
+  let mut delays = backoff_delays(&retry);
+  let mut opened_once = false;
+
+  loop {
+    let options = ChangeStreamOptions::builder()
+      .full_document(Some(FullDocumentType::UpdateLookup))
+      .resume_after(resume_token.clone())
+      .build();
+    let mut changes = match db.watch(pipeline.clone(), options).await {
+      Ok(changes) => changes,
+      Err(err) if !opened_once => {
+        tracing::warn!("Change stream unavailable, the cache relies on its TTL: {}", err);
+        return;
+      }
+      Err(err) => {
+        if matches!(err.kind.as_ref(), ErrorKind::Command(command) if command.code == CHANGE_STREAM_HISTORY_LOST) {
+          resume_token = None;
+        }
+        let delay = delays.next().unwrap_or_default();
+        tracing::warn!(
+          "Change stream not reopened, retrying in {}ms: {}",
+          delay.as_millis(),
+          err
+        );
+        time::sleep(delay).await;
+        continue;
+      }
+    };
+    if opened_once {
+      // the results cached while the stream was closed may miss the changes it did not resume from
+      cache.clear();
+    } else {
+      tracing::info!("Cache invalidated by the database change stream");
+    }
+    opened_once = true;
+
+    while let Some(change) = changes.next().await {
+      let event = match change {
+        Ok(event) => event,
+        Err(err) => {
+          // the changes missed until the stream is opened again may be gone from the oplog
+          tracing::warn!("Change stream interrupted, resuming after the last change: {}", err);
+          cache.clear();
+          break;
+        }
+      };
+      resume_token = changes.resume_token();
+      delays = backoff_delays(&retry);
+      if matches!(
+        event.operation_type,
+        OperationType::Drop | OperationType::DropDatabase | OperationType::Rename | OperationType::Invalidate
+      ) {
+        cache.clear();
+        if event.operation_type == OperationType::Invalidate {
+          // an invalidated stream can not be resumed
+          resume_token = None;
+          break;
+        }
+        continue;
+      }
+
+      let id = event
+        .document_key
+        .as_ref()
+        .and_then(|key| key.get_object_id("_id").ok());
+      let document = event.full_document.as_ref();
+      let login = document.and_then(|document| document.get_str("login").ok());
+      let owner_id = document
+        .and_then(|document| document.get_document("owner").ok())
+        .and_then(|owner: &Document| owner.get_object_id("_id").ok());
+      for key in id.iter().chain(owner_id.iter()).map(|id| id.to_hex()) {
+        cache.invalidate(&key);
+      }
+      if let Some(login) = login {
+        cache.invalidate(login);
+      }
+    }
+
+    time::sleep(delays.next().unwrap_or_default()).await;
+  }
+}
@@ -0,0 +1,142 @@
+use crate::lib::{cursor_connection::PaginationArguments, fields::Fields};
+use crate::model::store::{CachedStore, QueryCache, Store};
+use crate::setup::{config::CacheConfig, metrics::Metrics, mock};
+use mongodb::bson::oid::ObjectId;
+use pretty_assertions::assert_eq;
+use std::sync::Arc;
+
+fn cached_store(sufix: &str, max_entries: usize) -> (CachedStore, Arc<QueryCache>, Arc<Metrics>) {
+  let config = CacheConfig {
+    max_entries,
+    ..CacheConfig::default()
+  };
+  let metrics = Arc::new(Metrics::new());
+  let cache = Arc::new(QueryCache::new(&config, metrics.clone()));
+  let store = CachedStore::new(Arc::new(mock::memory_store(sufix)), cache.clone());
+
+  (store, cache, metrics)
+}
+
+fn first(count: u32) -> PaginationArguments {
+  PaginationArguments {
+    first: Some(count),
+    after: None,
+    last: None,
+    before: None,
+  }
+}
+
+fn requests(metrics: &Metrics, query: &str, outcome: &str) -> u64 {
+  metrics.cache_requests_total.with_label_values(&[query, outcome]).get()
+}
+
+#[actix_rt::test]
+async fn should_answer_a_repeated_query_from_the_cache() {
+  let sufix = mock::random_sufix();
+  let (store, _, metrics) = cached_store(&sufix, 100);
+  let login = format!("user_foo_{sufix}");
+
+  let page = store
+    .find_followers(&login, first(1), &None, &Fields::default())
+    .await
+    .unwrap();
+  let cached_page = store
+    .find_followers(&login, first(1), &None, &Fields::default())
+    .await
+    .unwrap();
+  store
+    .find_followers(&login, first(2), &None, &Fields::default())
+    .await
+    .unwrap();
+  store
+    .find_followers(&login, first(1), &Some(ObjectId::new()), &Fields::default())
+    .await
+    .unwrap();
+
+  assert_eq!(cached_page, page);
+  assert_eq!(requests(&metrics, "followers", "hit"), 1);
+  assert_eq!(requests(&metrics, "followers", "miss"), 3);
+  assert_eq!(metrics.cache_entries.get(), 3);
+}
+
+#[actix_rt::test]
+async fn should_invalidate_the_results_containing_a_changed_document() {
+  let sufix = mock::random_sufix();
+  let (store, cache, metrics) = cached_store(&sufix, 100);
+  let (foo, bar) = (format!("user_foo_{sufix}"), format!("user_bar_{sufix}"));
+  let bar_id = store
+    .find_user_by_login(&bar, &None, &Fields::default())
+    .await
+    .unwrap()
+    .unwrap()
+    ._id;
+  store.find_user_by_login(&foo, &None, &Fields::default()).await.unwrap();
+  store
+    .find_followers(&foo, first(10), &None, &Fields::default())
+    .await
+    .unwrap();
+
+  let removed = cache.invalidate(&bar_id.to_hex());
+  store.find_user_by_login(&foo, &None, &Fields::default()).await.unwrap();
+  store
+    .find_followers(&foo, first(10), &None, &Fields::default())
+    .await
+    .unwrap();
+
+  assert_eq!(removed, 2);
+  assert_eq!(requests(&metrics, "user", "hit"), 1);
+  assert_eq!(requests(&metrics, "followers", "miss"), 2);
+}
+
+#[actix_rt::test]
+async fn should_invalidate_by_login_the_results_asked_without_the_login() {
+  let sufix = mock::random_sufix();
+  let (store, cache, _) = cached_store(&sufix, 100);
+  let (foo, bar) = (format!("user_foo_{sufix}"), format!("user_bar_{sufix}"));
+  let fields = Fields::parse("name").unwrap();
+  store.find_user_by_login(&bar, &None, &fields).await.unwrap();
+  store.find_followers(&foo, first(10), &None, &fields).await.unwrap();
+  store.find_following(&foo, first(10), &None, &fields).await.unwrap();
+
+  let removed = cache.invalidate(&bar);
+
+  assert_eq!(removed, 2);
+}
+
+#[actix_rt::test]
+async fn should_evict_the_least_recently_used_result_when_full() {
+  let sufix = mock::random_sufix();
+  let (store, _, metrics) = cached_store(&sufix, 2);
+  let (foo, bar, dee) = (
+    format!("user_foo_{sufix}"),
+    format!("user_bar_{sufix}"),
+    format!("user_dee_{sufix}"),
+  );
+
+  store.find_user_by_login(&foo, &None, &Fields::default()).await.unwrap();
+  store.find_user_by_login(&bar, &None, &Fields::default()).await.unwrap();
+  store.find_user_by_login(&foo, &None, &Fields::default()).await.unwrap();
+  store.find_user_by_login(&dee, &None, &Fields::default()).await.unwrap();
+  store.find_user_by_login(&foo, &None, &Fields::default()).await.unwrap();
+  store.find_user_by_login(&bar, &None, &Fields::default()).await.unwrap();
+
+  assert_eq!(requests(&metrics, "user", "hit"), 2);
+  assert_eq!(requests(&metrics, "user", "miss"), 4);
+  assert_eq!(metrics.cache_evictions_total.with_label_values(&["capacity"]).get(), 2);
+}
+
+#[actix_rt::test]
+async fn should_forget_the_tags_of_the_evicted_results() {
+  let sufix = mock::random_sufix();
+  let (store, cache, metrics) = cached_store(&sufix, 1);
+  let (foo, bar) = (format!("user_foo_{sufix}"), format!("user_bar_{sufix}"));
+  store.find_user_by_login(&bar, &None, &Fields::default()).await.unwrap();
+  store.find_user_by_login(&foo, &None, &Fields::default()).await.unwrap();
+
+  let removed_bar = cache.invalidate(&bar);
+  let removed_foo = cache.invalidate(&foo);
+
+  assert_eq!(removed_bar, 0);
+  assert_eq!(removed_foo, 1);
+  assert_eq!(metrics.cache_entries.get(), 0);
+}
@@ -0,0 +1,287 @@
+use crate::lib::{
+  cursor_connection::{CursorConnection, Direction, PaginationArguments},
+  fields::Fields,
+};
+use crate::model::{
+  organization::Organization,
+  repository::Repository,
+  store::{HasId, Store},
+  user::User,
+  utils::{referenced_ids, to_object_id},
+};
+use futures::future::{self, BoxFuture, FutureExt};
+use mongodb::{
+  bson::{self, oid::ObjectId, Document},
+  error::Error as ModelError,
+};
+use serde::de::DeserializeOwned;
+
+/// The fields of an organization read in the lists, the same projection the Mongo pipeline applies
+const ORGANIZATION_LIST_FIELDS: [&str; 6] = ["_id", "login", "name", "avatarUrl", "url", "__typename"];
+
+/// Keeps the documents in memory, in the same shape they are stored in Mongo, and answers the queries like the
+/// aggregation pipelines do. Meant for tests and for running the server without a database.
+#[derive(Default)]
+pub struct MemoryStore {
+  users: Vec<Document>,
+  organizations: Vec<Document>,
+  repositories: Vec<Document>,
+}
+impl MemoryStore {
+  pub fn new(users: Vec<Document>, organizations: Vec<Document>, repositories: Vec<Document>) -> Self {
+    Self {
+      users,
+      organizations,
+      repositories,
+    }
+  }
+
+  fn user(&self, login: &str) -> Option<&Document> {
+    find_by_login(&self.users, login)
+  }
+
+  fn organization(&self, login: &str) -> Option<&Document> {
+    find_by_login(&self.organizations, login)
+  }
+
+  fn has_starred(&self, viewer_id: &Option<ObjectId>, repository: &Document) -> Option<bool> {
+    let viewer = find_by_id(&self.users, &(*viewer_id)?);
+    let starred = viewer.map(|viewer| referenced_ids(viewer, "starredRepositories"));
+    Some(starred.unwrap_or_default().contains(&id_of(repository)))
+  }
+
+  fn to_repositories(
+    &self,
+    page: Page,
+    viewer_id: &Option<ObjectId>,
+  ) -> Result<CursorConnection<Repository>, ModelError> {
+    page.into_connection(|repository| {
+      let mut repository = repository.clone();
+      if let Some(has_starred) = self.has_starred(viewer_id, &repository) {
+        repository.insert("viewerHasStarred", has_starred);
+      }
+      repository
+    })
+  }
+}
+
+impl Store for MemoryStore {
+  fn find_user_by_login<'a>(
+    &'a self,
+    login: &'a str,
+    viewer_id: &'a Option<ObjectId>,
+    _fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<Option<User>, ModelError>> {
+    let user = self
+      .user(login)
+      .map(|user| to_model(user_view(user, viewer_id)))
+      .transpose();
+    future::ready(user).boxed()
+  }
+
+  fn find_organization_by_login<'a>(
+    &'a self,
+    login: &'a str,
+    _fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<Option<Organization>, ModelError>> {
+    let organization = self.organization(login).map(|organization| {
+      let mut organization = organization.clone();
+      organization.remove("people");
+      to_model(organization)
+    });
+    future::ready(organization.transpose()).boxed()
+  }
+
+  fn find_organizations_of_user<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    _fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<Organization>, ModelError>> {
+    let organizations = referenced(&self.organizations, self.user(login), "organizations");
+    let connection = Page::new(organizations, pagination_arguments).into_connection(|organization| {
+      organization
+        .iter()
+        .filter(|(field, _)| ORGANIZATION_LIST_FIELDS.contains(&field.as_str()))
+        .map(|(field, value)| (field.clone(), value.clone()))
+        .collect()
+    });
+    future::ready(connection).boxed()
+  }
+
+  fn find_starred_repositories<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    _fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<Repository>, ModelError>> {
+    let repositories = referenced(&self.repositories, self.user(login), "starredRepositories");
+    let connection = self.to_repositories(Page::new(repositories, pagination_arguments), viewer_id);
+    future::ready(connection).boxed()
+  }
+
+  fn find_followers<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    _fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<User>, ModelError>> {
+    let users = referenced(&self.users, self.user(login), "followers");
+    let connection = Page::new(users, pagination_arguments).into_connection(|user| user_view(user, viewer_id));
+    future::ready(connection).boxed()
+  }
+
+  fn find_following<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    _fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<User>, ModelError>> {
+    let users = referenced(&self.users, self.user(login), "following");
+    let connection = Page::new(users, pagination_arguments).into_connection(|user| user_view(user, viewer_id));
+    future::ready(connection).boxed()
+  }
+
+  fn find_people<'a>(
+    &'a self,
+    organization_login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    _fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<User>, ModelError>> {
+    let users = referenced(&self.users, self.organization(organization_login), "people");
+    let connection = Page::new(users, pagination_arguments).into_connection(|user| user_view(user, viewer_id));
+    future::ready(connection).boxed()
+  }
+
+  fn find_repositories_by_owner_id<'a>(
+    &'a self,
+    owner_id: &'a ObjectId,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    _fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<Repository>, ModelError>> {
+    let repositories = self
+      .repositories
+      .iter()
+      .filter(|repository| {
+        let owner = repository.get_document("owner").ok();
+        owner.and_then(|owner| owner.get_object_id("_id").ok()) == Some(*owner_id)
+      })
+      .collect();
+    let connection = self.to_repositories(Page::new(repositories, pagination_arguments), viewer_id);
+    future::ready(connection).boxed()
+  }
+}
+
+/// A page of a list ordered by `_id`, with whether the list has items before and after it
+struct Page<'a> {
+  items: Vec<&'a Document>,
+  has_previous_page: bool,
+  has_next_page: bool,
+}
+impl<'a> Page<'a> {
+  fn new(mut list: Vec<&'a Document>, pagination_arguments: PaginationArguments) -> Self {
+    let (direction, limit, cursor) = pagination_arguments.parse_args().unwrap();
+    let cursor_id = to_object_id(cursor);
+    list.sort_by_key(|document| id_of(document));
+
+    let after_cursor = list.iter().copied().filter(|document| match (&direction, cursor_id) {
+      (_, None) => true,
+      (Direction::Forward, Some(cursor_id)) => id_of(document) > cursor_id,
+      (Direction::Backward, Some(cursor_id)) => id_of(document) < cursor_id,
+    });
+    let items: Vec<&Document> = match direction {
+      Direction::Forward => after_cursor.take(limit as usize).collect(),
+      Direction::Backward => {
+        let items: Vec<&Document> = after_cursor.collect();
+        let skip = items.len().saturating_sub(limit as usize);
+        items.into_iter().skip(skip).collect()
+      }
+    };
+
+    let has_previous_page = match items.first() {
+      Some(first) => list.iter().any(|document| id_of(document) < id_of(first)),
+      None => false,
+    };
+    let has_next_page = match items.last() {
+      Some(last) => list.iter().any(|document| id_of(document) > id_of(last)),
+      None => false,
+    };
+
+    Self {
+      items,
+      has_previous_page,
+      has_next_page,
+    }
+  }
+
+  fn into_connection<T, F>(self, view: F) -> Result<CursorConnection<T>, ModelError>
+  where
+    T: DeserializeOwned + HasId,
+    F: Fn(&Document) -> Document,
+  {
+    let items = self
+      .items
+      .into_iter()
+      .map(|document| to_model(view(document)))
+      .collect::<Result<Vec<T>, _>>()?;
+    let reference_from = |item: &T| item.id().to_hex();
+
+    Ok(CursorConnection::new(
+      items,
+      self.has_previous_page,
+      self.has_next_page,
+      reference_from,
+    ))
+  }
+}
+
+/// The user as seen by the viewer, the relations with the viewer are added and the email is only kept when it is
+/// public or the viewer is the user
+fn user_view(user: &Document, viewer_id: &Option<ObjectId>) -> Document {
+  let mut user = user.clone();
+  if let Some(viewer_id) = viewer_id {
+    let viewer_is_following = referenced_ids(&user, "followers").contains(viewer_id);
+    let is_following_viewer = referenced_ids(&user, "following").contains(viewer_id);
+    user.insert("viewerIsFollowing", viewer_is_following);
+    user.insert("isFollowingViewer", is_following_viewer);
+  }
+
+  let is_public = user.get_str("emailVisibility") == Ok("public");
+  let is_viewer = *viewer_id == Some(id_of(&user));
+  if !is_public && !is_viewer {
+    user.remove("email");
+  }
+
+  user
+}
+
+fn to_model<T: DeserializeOwned>(document: Document) -> Result<T, ModelError> {
+  Ok(bson::from_document(document)?)
+}
+
+/// The documents referenced by `field` of `owner`, the missing ones are left out like `$lookup` does
+fn referenced<'a>(documents: &'a [Document], owner: Option<&Document>, field: &str) -> Vec<&'a Document> {
+  owner
+    .map(|owner| referenced_ids(owner, field))
+    .unwrap_or_default()
+    .iter()
+    .filter_map(|id| find_by_id(documents, id))
+    .collect()
+}
+
+fn find_by_login<'a>(documents: &'a [Document], login: &str) -> Option<&'a Document> {
+  documents.iter().find(|document| document.get_str("login") == Ok(login))
+}
+
+fn find_by_id<'a>(documents: &'a [Document], id: &ObjectId) -> Option<&'a Document> {
+  documents.iter().find(|document| id_of(document) == *id)
+}
+
+fn id_of(document: &Document) -> ObjectId {
+  document.get_object_id("_id").unwrap()
+}
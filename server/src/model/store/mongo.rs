@@ -0,0 +1,207 @@
+use crate::lib::{
+  cursor_connection::{reference_to_cursor, CursorConnection, Edges, PaginationArguments},
+  fields::Fields,
+};
+use crate::model::{
+  organization::{self, Organization},
+  repository::{self, Repository},
+  store::{List, Node, Store},
+  user::{self, User},
+};
+use futures::{
+  future::{BoxFuture, FutureExt, TryFutureExt},
+  stream::{BoxStream, StreamExt},
+};
+use mongodb::{
+  bson::{self, oid::ObjectId, Document},
+  error::Error as ModelError,
+  Database,
+};
+
+pub struct MongoStore {
+  db: Database,
+}
+impl MongoStore {
+  pub fn new(db: Database) -> Self {
+    Self { db }
+  }
+}
+
+impl Store for MongoStore {
+  fn find_user_by_login<'a>(
+    &'a self,
+    login: &'a str,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<Option<User>, ModelError>> {
+    async move { user::find_user_by_login(&self.db, &login.to_string(), viewer_id, fields).await }.boxed()
+  }
+
+  fn find_organization_by_login<'a>(
+    &'a self,
+    login: &'a str,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<Option<Organization>, ModelError>> {
+    async move { organization::find_organization_by_login(&self.db, &login.to_string(), fields).await }.boxed()
+  }
+
+  fn find_organizations_of_user<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<Organization>, ModelError>> {
+    async move {
+      let login = login.to_string();
+      let result = user::find_organizations_by_login(&self.db, &login, pagination_arguments, fields).await;
+      organization::organizations_to_cursor_connection(&self.db, &login, result).await
+    }
+    .boxed()
+  }
+
+  fn find_starred_repositories<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<Repository>, ModelError>> {
+    async move {
+      let login = login.to_string();
+      let result =
+        user::find_starred_repositories_by_login(&self.db, &login, pagination_arguments, viewer_id, fields).await;
+      user::starred_repositories_to_cursor_connection(&self.db, &login, result).await
+    }
+    .boxed()
+  }
+
+  fn find_followers<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<User>, ModelError>> {
+    async move {
+      let login = login.to_string();
+      let result = user::find_followers_by_login(&self.db, &login, pagination_arguments, viewer_id, fields).await;
+      user::users_to_cursor_connection(&self.db, &login, "users", "followers", result).await
+    }
+    .boxed()
+  }
+
+  fn find_following<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<User>, ModelError>> {
+    async move {
+      let login = login.to_string();
+      let result = user::find_following_by_login(&self.db, &login, pagination_arguments, viewer_id, fields).await;
+      user::users_to_cursor_connection(&self.db, &login, "users", "following", result).await
+    }
+    .boxed()
+  }
+
+  fn find_people<'a>(
+    &'a self,
+    organization_login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<User>, ModelError>> {
+    async move {
+      let login = organization_login.to_string();
+      let result = organization::find_people_by_login(&self.db, &login, pagination_arguments, viewer_id, fields).await;
+      user::users_to_cursor_connection(&self.db, &login, "organizations", "people", result).await
+    }
+    .boxed()
+  }
+
+  fn find_repositories_by_owner_id<'a>(
+    &'a self,
+    owner_id: &'a ObjectId,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<Repository>, ModelError>> {
+    async move {
+      let result =
+        repository::find_repositories_by_owner_id(&self.db, owner_id, pagination_arguments, viewer_id, fields).await;
+      repository::repositories_to_cursor_connection(&self.db, owner_id, result).await
+    }
+    .boxed()
+  }
+
+  /// Runs the pipeline of the list with the limit as page size, the documents are read from the database cursor
+  /// as the stream is polled
+  fn stream_list<'a>(
+    &'a self,
+    list: &'a List,
+    after: Option<String>,
+    limit: u32,
+    viewer_id: &'a Option<ObjectId>,
+    fields: &'a Fields,
+  ) -> BoxStream<'a, Result<Edges<Node>, ModelError>> {
+    let arguments = PaginationArguments {
+      first: Some(limit),
+      after,
+      last: None,
+      before: None,
+    };
+    let (collection, pipeline) = match list {
+      List::OrganizationsOfUser(login) => ("users", user::pipeline_paginated_organization(login, arguments, fields)),
+      List::StarredRepositories(login) => (
+        "users",
+        user::pipeline_paginated_starred_repositories(login, arguments, viewer_id, fields),
+      ),
+      List::Followers(login) => (
+        "users",
+        user::pipeline_paginated_followers(login, arguments, viewer_id, fields),
+      ),
+      List::Following(login) => (
+        "users",
+        user::pipeline_paginated_following(login, arguments, viewer_id, fields),
+      ),
+      List::People(login) => (
+        "organizations",
+        organization::pipeline_paginated_people(login, arguments, viewer_id, fields),
+      ),
+      List::RepositoriesByOwnerId(owner_id) => (
+        "repositories",
+        repository::pipeline_paginated_repositories(arguments, owner_id, viewer_id, fields),
+      ),
+    };
+
+    async move {
+      let cursor = self
+        .db
+        .collection::<Document>(collection)
+        .aggregate(pipeline, None)
+        .await?;
+      Ok(cursor.map(move |document| to_edge(list, document?)))
+    }
+    .try_flatten_stream()
+    .boxed()
+  }
+}
+
+fn to_edge(list: &List, document: Document) -> Result<Edges<Node>, ModelError> {
+  let node = match list {
+    List::OrganizationsOfUser(_) => Node::Organization(bson::from_document(document)?),
+    List::StarredRepositories(_) | List::RepositoriesByOwnerId(_) => Node::Repository(bson::from_document(document)?),
+    List::Followers(_) | List::Following(_) | List::People(_) => Node::User(bson::from_document(document)?),
+  };
+  let id = match &node {
+    Node::User(user) => user._id,
+    Node::Organization(organization) => organization._id,
+    Node::Repository(repository) => repository._id,
+  };
+
+  Ok(Edges {
+    cursor: reference_to_cursor(id.to_hex()),
+    node,
+  })
+}
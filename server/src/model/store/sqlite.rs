@@ -0,0 +1,357 @@
+use crate::lib::{
+  cursor_connection::{CursorConnection, Direction, PaginationArguments},
+  fields::Fields,
+};
+use crate::model::{
+  organization::Organization,
+  repository::{Language, License, Owner, Repository},
+  store::{HasId, Store},
+  user::{Email, EmailVisibility, User},
+  utils::to_object_id,
+};
+use futures::future::{BoxFuture, FutureExt};
+use mongodb::{bson::oid::ObjectId, error::Error as ModelError};
+use rusqlite::{named_params, types::Type, Connection, OptionalExtension, Row};
+use std::{
+  io,
+  sync::{Arc, Mutex},
+};
+use tokio::task;
+
+const USER_COLUMNS: &str = "id, login, avatar_url, bio, email, email_visibility, name, url, website_url,
+  EXISTS (SELECT 1 FROM follows WHERE followed_id = users.id AND follower_id = :viewer_id),
+  EXISTS (SELECT 1 FROM follows WHERE follower_id = users.id AND followed_id = :viewer_id)";
+const ORGANIZATION_COLUMNS: &str = "id, login, avatar_url, description, location, name, url, website_url";
+const REPOSITORY_COLUMNS: &str = "id, name, description, fork_count, license_name, language_name, language_color,
+  owner_id, EXISTS (SELECT 1 FROM stars WHERE repository_id = repositories.id AND user_id = :viewer_id)";
+
+/// Answers the queries from a SQLite database migrated by `setup::sqlite`. The connection is shared behind a lock and
+/// the queries run on the blocking thread pool.
+pub struct SqliteStore {
+  connection: Arc<Mutex<Connection>>,
+}
+impl SqliteStore {
+  pub fn new(connection: Connection) -> Self {
+    Self {
+      connection: Arc::new(Mutex::new(connection)),
+    }
+  }
+
+  fn query<T, F>(&self, query: F) -> BoxFuture<'static, Result<T, ModelError>>
+  where
+    T: Send + 'static,
+    F: FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+  {
+    let connection = self.connection.clone();
+    async move {
+      let result = task::spawn_blocking(move || query(&connection.lock().unwrap()))
+        .await
+        .unwrap();
+      result.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err).into())
+    }
+    .boxed()
+  }
+}
+
+impl Store for SqliteStore {
+  fn find_user_by_login<'a>(
+    &'a self,
+    login: &'a str,
+    viewer_id: &'a Option<ObjectId>,
+    _fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<Option<User>, ModelError>> {
+    let login = login.to_string();
+    let viewer_id = *viewer_id;
+    self.query(move |connection| {
+      let sql = format!("SELECT {USER_COLUMNS} FROM users WHERE login = :login");
+      let viewer = viewer_id.map(|id| id.to_hex());
+      connection
+        .prepare_cached(&sql)?
+        .query_row(named_params! { ":login": login, ":viewer_id": viewer }, |row| {
+          to_user(row, &viewer_id)
+        })
+        .optional()
+    })
+  }
+
+  fn find_organization_by_login<'a>(
+    &'a self,
+    login: &'a str,
+    _fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<Option<Organization>, ModelError>> {
+    let login = login.to_string();
+    self.query(move |connection| {
+      let sql = format!("SELECT {ORGANIZATION_COLUMNS} FROM organizations WHERE login = :login");
+      connection
+        .prepare_cached(&sql)?
+        .query_row(named_params! { ":login": login }, to_organization)
+        .optional()
+    })
+  }
+
+  fn find_organizations_of_user<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    _fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<Organization>, ModelError>> {
+    let login = login.to_string();
+    self.query(move |connection| {
+      let list = "SELECT organization_id AS id FROM memberships
+        WHERE user_id = (SELECT id FROM users WHERE login = :key)";
+      let page = Page::new(connection, list, &login, pagination_arguments)?;
+      page.into_connection(|id| {
+        let sql = format!("SELECT {ORGANIZATION_COLUMNS} FROM organizations WHERE id = :id");
+        let organization = connection
+          .prepare_cached(&sql)?
+          .query_row(named_params! { ":id": id }, to_organization)?;
+        // the lists carry the same fields as the Mongo projection
+        Ok(Organization {
+          description: None,
+          location: None,
+          website_url: None,
+          ..organization
+        })
+      })
+    })
+  }
+
+  fn find_starred_repositories<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    _fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<Repository>, ModelError>> {
+    let login = login.to_string();
+    let viewer_id = *viewer_id;
+    self.query(move |connection| {
+      let list = "SELECT repository_id AS id FROM stars WHERE user_id = (SELECT id FROM users WHERE login = :key)";
+      let page = Page::new(connection, list, &login, pagination_arguments)?;
+      page.into_connection(|id| find_repository(connection, id, &viewer_id))
+    })
+  }
+
+  fn find_followers<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    _fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<User>, ModelError>> {
+    let login = login.to_string();
+    let viewer_id = *viewer_id;
+    self.query(move |connection| {
+      let list = "SELECT follower_id AS id FROM follows WHERE followed_id = (SELECT id FROM users WHERE login = :key)";
+      let page = Page::new(connection, list, &login, pagination_arguments)?;
+      page.into_connection(|id| find_user(connection, id, &viewer_id))
+    })
+  }
+
+  fn find_following<'a>(
+    &'a self,
+    login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    _fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<User>, ModelError>> {
+    let login = login.to_string();
+    let viewer_id = *viewer_id;
+    self.query(move |connection| {
+      let list = "SELECT followed_id AS id FROM follows WHERE follower_id = (SELECT id FROM users WHERE login = :key)";
+      let page = Page::new(connection, list, &login, pagination_arguments)?;
+      page.into_connection(|id| find_user(connection, id, &viewer_id))
+    })
+  }
+
+  fn find_people<'a>(
+    &'a self,
+    organization_login: &'a str,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    _fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<User>, ModelError>> {
+    let login = organization_login.to_string();
+    let viewer_id = *viewer_id;
+    self.query(move |connection| {
+      let list = "SELECT user_id AS id FROM memberships
+        WHERE organization_id = (SELECT id FROM organizations WHERE login = :key)";
+      let page = Page::new(connection, list, &login, pagination_arguments)?;
+      page.into_connection(|id| find_user(connection, id, &viewer_id))
+    })
+  }
+
+  fn find_repositories_by_owner_id<'a>(
+    &'a self,
+    owner_id: &'a ObjectId,
+    pagination_arguments: PaginationArguments,
+    viewer_id: &'a Option<ObjectId>,
+    _fields: &'a Fields,
+  ) -> BoxFuture<'a, Result<CursorConnection<Repository>, ModelError>> {
+    let owner_id = owner_id.to_hex();
+    let viewer_id = *viewer_id;
+    self.query(move |connection| {
+      let list = "SELECT id FROM repositories WHERE owner_id = :key";
+      let page = Page::new(connection, list, &owner_id, pagination_arguments)?;
+      page.into_connection(|id| find_repository(connection, id, &viewer_id))
+    })
+  }
+}
+
+/// The ids of a page of a list ordered by `id`, with whether the list has items before and after it. The list is a
+/// query selecting the `id` column filtered by the `:key` parameter.
+struct Page {
+  ids: Vec<String>,
+  has_previous_page: bool,
+  has_next_page: bool,
+}
+impl Page {
+  fn new(
+    connection: &Connection,
+    list: &str,
+    key: &str,
+    pagination_arguments: PaginationArguments,
+  ) -> rusqlite::Result<Self> {
+    let (direction, limit, cursor) = pagination_arguments.parse_args().unwrap();
+    let cursor = to_object_id(cursor).map(|id| id.to_hex());
+    let sql = match direction {
+      Direction::Forward => {
+        format!("SELECT id FROM ({list}) WHERE (:cursor IS NULL OR id > :cursor) ORDER BY id LIMIT :limit")
+      }
+      Direction::Backward => format!(
+        "SELECT id FROM (
+          SELECT id FROM ({list}) WHERE (:cursor IS NULL OR id < :cursor) ORDER BY id DESC LIMIT :limit
+        ) ORDER BY id"
+      ),
+    };
+    let ids = connection
+      .prepare_cached(&sql)?
+      .query_map(
+        named_params! { ":key": key, ":cursor": cursor, ":limit": limit },
+        |row| row.get(0),
+      )?
+      .collect::<rusqlite::Result<Vec<String>>>()?;
+
+    let exists = |condition: &str, id: Option<&String>| match id {
+      Some(id) => connection
+        .prepare_cached(&format!(
+          "SELECT EXISTS (SELECT 1 FROM ({list}) WHERE id {condition} :id)"
+        ))?
+        .query_row(named_params! { ":key": key, ":id": id }, |row| row.get(0)),
+      None => Ok(false),
+    };
+
+    Ok(Self {
+      has_previous_page: exists("<", ids.first())?,
+      has_next_page: exists(">", ids.last())?,
+      ids,
+    })
+  }
+
+  fn into_connection<T, F>(self, find: F) -> rusqlite::Result<CursorConnection<T>>
+  where
+    T: HasId,
+    F: Fn(&str) -> rusqlite::Result<T>,
+  {
+    let items = self
+      .ids
+      .iter()
+      .map(|id| find(id))
+      .collect::<rusqlite::Result<Vec<T>>>()?;
+    let reference_from = |item: &T| item.id().to_hex();
+
+    Ok(CursorConnection::new(
+      items,
+      self.has_previous_page,
+      self.has_next_page,
+      reference_from,
+    ))
+  }
+}
+
+fn find_user(connection: &Connection, id: &str, viewer_id: &Option<ObjectId>) -> rusqlite::Result<User> {
+  let sql = format!("SELECT {USER_COLUMNS} FROM users WHERE id = :id");
+  let viewer = viewer_id.map(|id| id.to_hex());
+  connection
+    .prepare_cached(&sql)?
+    .query_row(named_params! { ":id": id, ":viewer_id": viewer }, |row| {
+      to_user(row, viewer_id)
+    })
+}
+
+fn find_repository(connection: &Connection, id: &str, viewer_id: &Option<ObjectId>) -> rusqlite::Result<Repository> {
+  let sql = format!("SELECT {REPOSITORY_COLUMNS} FROM repositories WHERE id = :id");
+  let viewer = viewer_id.map(|id| id.to_hex());
+  connection
+    .prepare_cached(&sql)?
+    .query_row(named_params! { ":id": id, ":viewer_id": viewer }, |row| {
+      to_repository(row, viewer_id)
+    })
+}
+
+/// The user as seen by the viewer, the email is only kept when it is public or the viewer is the user
+fn to_user(row: &Row, viewer_id: &Option<ObjectId>) -> rusqlite::Result<User> {
+  let id = object_id(row, 0)?;
+  let email_visibility = match row.get::<_, Option<String>>(5)?.as_deref() {
+    Some("public") => Some(EmailVisibility::Public),
+    Some("private") => Some(EmailVisibility::Private),
+    _ => None,
+  };
+  let shows_email = email_visibility == Some(EmailVisibility::Public) || *viewer_id == Some(id);
+
+  Ok(User {
+    _id: id,
+    login: row.get(1)?,
+    avatar_url: row.get(2)?,
+    bio: row.get(3)?,
+    email: row.get::<_, Option<String>>(4)?.filter(|_| shows_email).map(Email),
+    email_visibility,
+    name: row.get(6)?,
+    organizations: None,
+    url: row.get(7)?,
+    website_url: row.get(8)?,
+    viewer_is_following: viewer_id.map(|_| row.get(9)).transpose()?,
+    is_following_viewer: viewer_id.map(|_| row.get(10)).transpose()?,
+    typename: "User".to_string(),
+  })
+}
+
+fn to_organization(row: &Row) -> rusqlite::Result<Organization> {
+  Ok(Organization {
+    _id: object_id(row, 0)?,
+    login: row.get(1)?,
+    avatar_url: row.get(2)?,
+    description: row.get(3)?,
+    location: row.get(4)?,
+    name: row.get(5)?,
+    people: None,
+    url: row.get(6)?,
+    website_url: row.get(7)?,
+    typename: "Organization".to_string(),
+  })
+}
+
+fn to_repository(row: &Row, viewer_id: &Option<ObjectId>) -> rusqlite::Result<Repository> {
+  let language = match (row.get(5)?, row.get(6)?) {
+    (Some(name), Some(color)) => Some(Language { name, color }),
+    _ => None,
+  };
+
+  Ok(Repository {
+    _id: object_id(row, 0)?,
+    name: row.get(1)?,
+    description: row.get(2)?,
+    fork_count: row.get(3)?,
+    license_info: row.get::<_, Option<String>>(4)?.map(|name| License { name }),
+    primary_language: language,
+    owner: Owner {
+      _id: object_id(row, 7)?,
+    },
+    viewer_has_starred: viewer_id.map(|_| row.get(8)).transpose()?,
+  })
+}
+
+fn object_id(row: &Row, index: usize) -> rusqlite::Result<ObjectId> {
+  let hex: String = row.get(index)?;
+  ObjectId::parse_str(hex).map_err(|err| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(err)))
+}
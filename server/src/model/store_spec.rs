@@ -0,0 +1,367 @@
+use crate::lib::{
+  cursor_connection::{CursorConnection, Edges, PaginationArguments},
+  fields::Fields,
+};
+use crate::model::{
+  store::{List, Node, Store},
+  user::Email,
+};
+use crate::setup::mock;
+use futures::TryStreamExt;
+use pretty_assertions::assert_eq;
+
+fn first(first: u32, after: Option<String>) -> PaginationArguments {
+  PaginationArguments {
+    first: Some(first),
+    after,
+    last: None,
+    before: None,
+  }
+}
+
+fn last(last: u32, before: Option<String>) -> PaginationArguments {
+  PaginationArguments {
+    first: None,
+    after: None,
+    last: Some(last),
+    before,
+  }
+}
+
+/// Every store backend, filled with the same mocked data
+async fn stores(sufix: &str) -> Vec<(&'static str, Box<dyn Store>)> {
+  vec![
+    ("mongo", Box::new(mock::mongo_store(sufix).await)),
+    ("memory", Box::new(mock::memory_store(sufix))),
+    ("sqlite", Box::new(mock::sqlite_store(sufix))),
+  ]
+}
+
+fn logins<T>(connection: &CursorConnection<T>, login: fn(&T) -> String) -> Vec<String> {
+  connection.edges.iter().map(|edge| login(&edge.node)).collect()
+}
+
+fn node_logins(edges: &[Edges<Node>]) -> Vec<String> {
+  edges
+    .iter()
+    .map(|edge| match &edge.node {
+      Node::User(user) => user.login.clone(),
+      Node::Organization(organization) => organization.login.clone(),
+      Node::Repository(repository) => repository.name.clone(),
+    })
+    .collect()
+}
+
+#[actix_rt::test]
+async fn should_find_a_user_by_login() {
+  let sufix = mock::random_sufix();
+  for (backend, store) in stores(&sufix).await {
+    let user = store
+      .find_user_by_login(&format!("user_foo_{sufix}"), &None, &Fields::default())
+      .await
+      .unwrap();
+    let missing = store
+      .find_user_by_login(&format!("user_xxx_{sufix}"), &None, &Fields::default())
+      .await
+      .unwrap();
+
+    assert_eq!(user.unwrap().login, format!("user_foo_{sufix}"), "{}", backend);
+    assert_eq!(missing, None, "{}", backend);
+  }
+}
+
+#[actix_rt::test]
+async fn should_show_a_private_email_only_to_the_user_themself() {
+  let sufix = mock::random_sufix();
+  let login = format!("user_foo_{sufix}");
+  for (backend, store) in stores(&sufix).await {
+    let user = store
+      .find_user_by_login(&login, &None, &Fields::default())
+      .await
+      .unwrap()
+      .unwrap();
+    let viewer = store
+      .find_user_by_login(&format!("user_bar_{sufix}"), &None, &Fields::default())
+      .await
+      .unwrap()
+      .unwrap();
+
+    let other_user = store
+      .find_user_by_login(&login, &Some(viewer._id), &Fields::default())
+      .await
+      .unwrap()
+      .unwrap();
+    let themself = store
+      .find_user_by_login(&login, &Some(user._id), &Fields::default())
+      .await
+      .unwrap()
+      .unwrap();
+
+    assert_eq!(user.email, None, "{}", backend);
+    assert_eq!(other_user.email, None, "{}", backend);
+    assert_eq!(themself.email, Some(Email("foo@email.com".to_string())), "{}", backend);
+    assert_eq!(viewer.email, Some(Email("bar@email.com".to_string())), "{}", backend);
+  }
+}
+
+#[actix_rt::test]
+async fn should_compute_viewer_relations() {
+  let sufix = mock::random_sufix();
+  for (backend, store) in stores(&sufix).await {
+    let viewer = store
+      .find_user_by_login(&format!("user_bar_{sufix}"), &None, &Fields::default())
+      .await
+      .unwrap()
+      .unwrap();
+
+    let user = store
+      .find_user_by_login(&format!("user_foo_{sufix}"), &Some(viewer._id), &Fields::default())
+      .await
+      .unwrap()
+      .unwrap();
+
+    assert_eq!(user.viewer_is_following, Some(true), "{}", backend);
+    assert_eq!(user.is_following_viewer, Some(false), "{}", backend);
+  }
+}
+
+#[actix_rt::test]
+async fn should_find_an_organization_without_its_people() {
+  let sufix = mock::random_sufix();
+  for (backend, store) in stores(&sufix).await {
+    let organization = store
+      .find_organization_by_login(&format!("organization_acme_{sufix}"), &Fields::default())
+      .await
+      .unwrap()
+      .unwrap();
+
+    assert_eq!(organization.login, format!("organization_acme_{sufix}"), "{}", backend);
+    assert_eq!(organization.people, None, "{}", backend);
+  }
+}
+
+#[actix_rt::test]
+async fn should_paginate_forward_with_cursors() {
+  let sufix = mock::random_sufix();
+  let login = format!("user_foo_{sufix}");
+  for (backend, store) in stores(&sufix).await {
+    let page = store
+      .find_followers(&login, first(1, None), &None, &Fields::default())
+      .await
+      .unwrap();
+    let next_page = store
+      .find_followers(
+        &login,
+        first(1, page.page_info.end_cursor.clone()),
+        &None,
+        &Fields::default(),
+      )
+      .await
+      .unwrap();
+
+    assert_eq!(
+      logins(&page, |user| user.login.clone()),
+      vec![format!("user_bar_{sufix}")],
+      "{}",
+      backend
+    );
+    assert_eq!(
+      (page.page_info.has_previous_page, page.page_info.has_next_page),
+      (false, true),
+      "{}",
+      backend
+    );
+    assert_eq!(
+      logins(&next_page, |user| user.login.clone()),
+      vec![format!("user_dee_{sufix}")],
+      "{}",
+      backend
+    );
+    assert_eq!(
+      (next_page.page_info.has_previous_page, next_page.page_info.has_next_page),
+      (true, false),
+      "{}",
+      backend
+    );
+  }
+}
+
+#[actix_rt::test]
+async fn should_paginate_backward_with_cursors() {
+  let sufix = mock::random_sufix();
+  let login = format!("user_foo_{sufix}");
+  for (backend, store) in stores(&sufix).await {
+    let page = store
+      .find_followers(&login, last(1, None), &None, &Fields::default())
+      .await
+      .unwrap();
+    let previous_page = store
+      .find_followers(
+        &login,
+        last(1, page.page_info.start_cursor.clone()),
+        &None,
+        &Fields::default(),
+      )
+      .await
+      .unwrap();
+
+    assert_eq!(
+      logins(&page, |user| user.login.clone()),
+      vec![format!("user_dee_{sufix}")],
+      "{}",
+      backend
+    );
+    assert_eq!(
+      (page.page_info.has_previous_page, page.page_info.has_next_page),
+      (true, false),
+      "{}",
+      backend
+    );
+    assert_eq!(
+      logins(&previous_page, |user| user.login.clone()),
+      vec![format!("user_bar_{sufix}")],
+      "{}",
+      backend
+    );
+    assert_eq!(
+      (
+        previous_page.page_info.has_previous_page,
+        previous_page.page_info.has_next_page
+      ),
+      (false, true),
+      "{}",
+      backend
+    );
+  }
+}
+
+#[actix_rt::test]
+async fn should_find_the_organizations_and_people() {
+  let sufix = mock::random_sufix();
+  for (backend, store) in stores(&sufix).await {
+    let organizations = store
+      .find_organizations_of_user(&format!("user_dee_{sufix}"), first(10, None), &Fields::default())
+      .await
+      .unwrap();
+    let people = store
+      .find_people(
+        &format!("organization_acme_{sufix}"),
+        first(10, None),
+        &None,
+        &Fields::default(),
+      )
+      .await
+      .unwrap();
+
+    assert_eq!(
+      logins(&organizations, |organization| organization.login.clone()),
+      vec![format!("organization_acme_{sufix}")],
+      "{}",
+      backend
+    );
+    assert_eq!(organizations.edges[0].node.people, None, "{}", backend);
+    assert_eq!(
+      logins(&people, |user| user.login.clone()),
+      vec![format!("user_foo_{sufix}"), format!("user_dee_{sufix}")],
+      "{}",
+      backend
+    );
+  }
+}
+
+#[actix_rt::test]
+async fn should_find_the_repositories_with_the_viewer_stars() {
+  let sufix = mock::random_sufix();
+  for (backend, store) in stores(&sufix).await {
+    let owner = store
+      .find_organization_by_login(&format!("organization_acme_{sufix}"), &Fields::default())
+      .await
+      .unwrap()
+      .unwrap();
+    let viewer = store
+      .find_user_by_login(&format!("user_bar_{sufix}"), &None, &Fields::default())
+      .await
+      .unwrap()
+      .unwrap();
+
+    let repositories = store
+      .find_repositories_by_owner_id(&owner._id, first(10, None), &Some(viewer._id), &Fields::default())
+      .await
+      .unwrap();
+    let starred = store
+      .find_starred_repositories(&viewer.login, first(10, None), &None, &Fields::default())
+      .await
+      .unwrap();
+
+    assert_eq!(
+      logins(&repositories, |repository| repository.name.clone()),
+      vec![format!("repository_tux_{sufix}"), format!("repository_mar_{sufix}")],
+      "{}",
+      backend
+    );
+    assert_eq!(repositories.edges[0].node.viewer_has_starred, Some(true), "{}", backend);
+    assert_eq!(
+      repositories.edges[1].node.viewer_has_starred,
+      Some(false),
+      "{}",
+      backend
+    );
+    assert_eq!(
+      logins(&starred, |repository| repository.name.clone()),
+      vec![format!("repository_tux_{sufix}"), format!("repository_dee_{sufix}")],
+      "{}",
+      backend
+    );
+  }
+}
+
+#[actix_rt::test]
+async fn should_return_an_empty_list_for_an_unknown_login() {
+  let sufix = mock::random_sufix();
+  for (backend, store) in stores(&sufix).await {
+    let users = store
+      .find_following(&format!("user_xxx_{sufix}"), first(10, None), &None, &Fields::default())
+      .await
+      .unwrap();
+
+    assert_eq!(users.edges.len(), 0, "{}", backend);
+    assert_eq!(
+      (users.page_info.has_previous_page, users.page_info.has_next_page),
+      (false, false),
+      "{}",
+      backend
+    );
+  }
+}
+
+#[actix_rt::test]
+async fn should_stream_a_list_across_pages_up_to_the_limit() {
+  let sufix = mock::random_sufix();
+  let list = List::People(format!("organization_acme_{sufix}"));
+  for (backend, store) in stores(&sufix).await {
+    let all: Vec<_> = store
+      .stream_list(&list, None, 100, &None, &Fields::default())
+      .try_collect()
+      .await
+      .unwrap();
+    let first: Vec<_> = store
+      .stream_list(&list, None, 1, &None, &Fields::default())
+      .try_collect()
+      .await
+      .unwrap();
+    let rest: Vec<_> = store
+      .stream_list(&list, Some(first[0].cursor.clone()), 100, &None, &Fields::default())
+      .try_collect()
+      .await
+      .unwrap();
+
+    assert_eq!(
+      node_logins(&all),
+      vec![format!("user_foo_{sufix}"), format!("user_dee_{sufix}")],
+      "{}",
+      backend
+    );
+    assert_eq!(node_logins(&first), vec![format!("user_foo_{sufix}")], "{}", backend);
+    assert_eq!(node_logins(&rest), vec![format!("user_dee_{sufix}")], "{}", backend);
+  }
+}
@@ -1,13 +1,41 @@
-use crate::lib::cursor_connection::{CursorConnection, PaginationArguments};
-use crate::model::{self, organization::Organization, repository::Repository, Pipeline};
+use crate::lib::{
+  cursor_connection::{CursorConnection, PaginationArguments},
+  fields::Fields,
+  redact::REDACTED_EMAIL,
+};
+use crate::model::{
+  self,
+  organization::Organization,
+  repository::Repository,
+  schema::{one_of, optional, references, required, required_fields, CollectionSchema},
+  Pipeline,
+};
 use mongodb::{
   bson::{doc, oid::ObjectId},
   error::Error as ModelError,
-  options::FindOneOptions,
+  options::IndexOptions,
+  IndexModel,
 };
 use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
+#[serde(transparent)]
+pub struct Email(pub String);
+impl fmt::Debug for Email {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(REDACTED_EMAIL)
+  }
+}
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailVisibility {
+  Public,
+  Private,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct User {
   // TODO find a way to serialize _id into id with hex version
@@ -16,7 +44,10 @@ pub struct User {
   pub avatar_url: String,
   #[serde(skip_serializing_if = "Option::is_none")]
   pub bio: Option<String>,
-  pub email: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub email: Option<Email>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub email_visibility: Option<EmailVisibility>,
   pub login: String,
   #[serde(skip_serializing_if = "Option::is_none")]
   pub name: Option<String>,
@@ -25,18 +56,68 @@ pub struct User {
   pub url: String,
   #[serde(skip_serializing_if = "Option::is_none")]
   pub website_url: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub viewer_is_following: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub is_following_viewer: Option<bool>,
   #[serde(rename = "__typename")]
   pub typename: String,
 }
 
-pub async fn find_user_by_login(db: &mongodb::Database, login: &String) -> Result<Option<User>, ModelError> {
-  let user_collection = db.collection::<User>("users");
-  let filter = doc! { "login": login };
-  let options = FindOneOptions::builder()
-    .projection(doc! { "organizations": 0 })
-    .build();
+/// The non optional fields of `User`
+pub const REQUIRED_FIELDS: &[&str] = &["avatarUrl", "login", "url", "__typename"];
+
+pub fn collection_schema() -> CollectionSchema {
+  CollectionSchema {
+    collection: "users",
+    indexes: vec![
+      IndexModel::builder()
+        .keys(doc! { "login": 1 })
+        .options(
+          IndexOptions::builder()
+            .name("login_unique".to_string())
+            .unique(true)
+            .build(),
+        )
+        .build(),
+      IndexModel::builder()
+        .keys(doc! { "login": "text", "name": "text", "bio": "text" })
+        .options(IndexOptions::builder().name("search_text".to_string()).build())
+        .build(),
+    ],
+    json_schema: doc! {
+      "bsonType": "object",
+      "required": required_fields(REQUIRED_FIELDS),
+      "properties": {
+        "_id": required("objectId"),
+        "avatarUrl": required("string"),
+        "bio": optional("string"),
+        "email": optional("string"),
+        "emailVisibility": one_of(&["public", "private"]),
+        "login": required("string"),
+        "name": optional("string"),
+        "url": required("string"),
+        "websiteUrl": optional("string"),
+        "followers": references(),
+        "following": references(),
+        "organizations": references(),
+        "starredRepositories": references(),
+        "__typename": one_of(&["User"]),
+      },
+    },
+  }
+}
 
-  let user = user_collection.find_one(filter, options).await?;
+pub async fn find_user_by_login(
+  db: &mongodb::Database,
+  login: &String,
+  viewer_id: &Option<ObjectId>,
+  fields: &Fields,
+) -> Result<Option<User>, ModelError> {
+  let user_collection = db.collection::<User>("users");
+  let pipeline = pipeline_user(login, viewer_id, fields);
+  let cursor = user_collection.aggregate(pipeline, None).await?;
+  let user = model::utils::collect_into_model(cursor).await.into_iter().next();
 
   Ok(user)
 }
@@ -45,9 +126,10 @@ pub async fn find_organizations_by_login(
   db: &mongodb::Database,
   login: &String,
   pagination_arguments: PaginationArguments,
+  fields: &Fields,
 ) -> Result<Vec<Organization>, ModelError> {
   let user_collection = db.collection::<User>("users");
-  let pipeline = pipeline_paginated_organization(&login, pagination_arguments);
+  let pipeline = pipeline_paginated_organization(&login, pagination_arguments, fields);
   let cursor = user_collection.aggregate(pipeline, None).await?;
   let items = model::utils::collect_into_model(cursor).await;
 
@@ -58,9 +140,11 @@ pub async fn find_starred_repositories_by_login(
   db: &mongodb::Database,
   login: &String,
   pagination_arguments: PaginationArguments,
+  viewer_id: &Option<ObjectId>,
+  fields: &Fields,
 ) -> Result<Vec<Repository>, ModelError> {
   let user_collection = db.collection::<User>("users");
-  let pipeline = pipeline_paginated_starred_repositories(login, pagination_arguments);
+  let pipeline = pipeline_paginated_starred_repositories(login, pagination_arguments, viewer_id, fields);
   let cursor = user_collection.aggregate(pipeline, None).await?;
   let items = model::utils::collect_into_model(cursor).await;
 
@@ -71,9 +155,11 @@ pub async fn find_followers_by_login(
   db: &mongodb::Database,
   login: &String,
   pagination_arguments: PaginationArguments,
+  viewer_id: &Option<ObjectId>,
+  fields: &Fields,
 ) -> Result<Vec<User>, ModelError> {
   let user_collection = db.collection::<User>("users");
-  let pipeline = pipeline_paginated_followers(login, pagination_arguments);
+  let pipeline = pipeline_paginated_followers(login, pagination_arguments, viewer_id, fields);
   let cursor = user_collection.aggregate(pipeline, None).await?;
   let items = model::utils::collect_into_model(cursor).await;
 
@@ -84,28 +170,32 @@ pub async fn find_following_by_login(
   db: &mongodb::Database,
   login: &String,
   pagination_arguments: PaginationArguments,
+  viewer_id: &Option<ObjectId>,
+  fields: &Fields,
 ) -> Result<Vec<User>, ModelError> {
   let user_collection = db.collection::<User>("users");
-  let pipeline = pipeline_paginated_following(login, pagination_arguments);
+  let pipeline = pipeline_paginated_following(login, pagination_arguments, viewer_id, fields);
   let cursor = user_collection.aggregate(pipeline, None).await?;
   let items = model::utils::collect_into_model(cursor).await;
 
   Ok(items)
 }
 
+/// `coll_name` and `field_name` tell where the list of users comes from, like the `people` of an `organizations` or
+/// the `followers` of a `users`
 pub async fn users_to_cursor_connection(
   db: &mongodb::Database,
-  org_login: &String,
+  owner_login: &String,
+  coll_name: &str,
+  field_name: &str,
   result: Result<Vec<User>, ModelError>,
 ) -> Result<CursorConnection<User>, ModelError> {
   let result = result?;
   let (has_previous_page, has_next_page) = if result.len() > 0 {
-    let coll_name = "organizations";
-    let field_name = "people";
     let first_item_id = result.first().unwrap()._id;
-    let last_item_id = result.first().unwrap()._id;
+    let last_item_id = result.last().unwrap()._id;
 
-    model::utils::pages_previous_and_next(db, org_login, &first_item_id, &last_item_id, coll_name, field_name).await
+    model::utils::pages_previous_and_next(db, owner_login, &first_item_id, &last_item_id, coll_name, field_name).await
   } else {
     (false, false)
   };
@@ -116,7 +206,77 @@ pub async fn users_to_cursor_connection(
   Ok(items)
 }
 
-fn pipeline_paginated_organization(login: &String, pagination_arguments: PaginationArguments) -> Pipeline {
+pub async fn starred_repositories_to_cursor_connection(
+  db: &mongodb::Database,
+  user_login: &String,
+  result: Result<Vec<Repository>, ModelError>,
+) -> Result<CursorConnection<Repository>, ModelError> {
+  let result = result?;
+  let (has_previous_page, has_next_page) = if !result.is_empty() {
+    let coll_name = "users";
+    let field_name = "starredRepositories";
+    let first_item_id = result.first().unwrap()._id;
+    let last_item_id = result.last().unwrap()._id;
+
+    model::utils::pages_previous_and_next(db, user_login, &first_item_id, &last_item_id, coll_name, field_name).await
+  } else {
+    (false, false)
+  };
+
+  let reference_from = |item: &Repository| item._id.to_hex();
+  let items = CursorConnection::new(result, has_previous_page, has_next_page, reference_from);
+
+  Ok(items)
+}
+
+fn pipeline_user(login: &String, viewer_id: &Option<ObjectId>, fields: &Fields) -> Pipeline {
+  let filter_by_login = vec![doc! { "$match": { "login": login } }, doc! { "$limit": 1 }];
+
+  let remove_organizations = vec![doc! { "$project": { "organizations": 0 } }];
+
+  vec![]
+    .into_iter()
+    .chain(filter_by_login)
+    .chain(pipeline_viewer_relations(viewer_id))
+    .chain(pipeline_email_visibility(viewer_id))
+    .chain(remove_organizations)
+    .chain(model::utils::pipeline_fields(fields, REQUIRED_FIELDS))
+    .collect()
+}
+
+pub fn pipeline_viewer_relations(viewer_id: &Option<ObjectId>) -> Pipeline {
+  match viewer_id {
+    None => vec![],
+    Some(viewer_id) => vec![doc! { "$addFields": {
+      "viewerIsFollowing": {
+        "$in": [ viewer_id, { "$ifNull": [ "$followers._id", [] ] } ]
+      },
+      "isFollowingViewer": {
+        "$in": [ viewer_id, { "$ifNull": [ "$following._id", [] ] } ]
+      },
+    } }],
+  }
+}
+
+pub fn pipeline_email_visibility(viewer_id: &Option<ObjectId>) -> Pipeline {
+  let is_public = doc! { "$eq": [ "$emailVisibility", "public" ] };
+  let is_allowed = match viewer_id {
+    None => is_public,
+    Some(viewer_id) => doc! { "$or": [ is_public, { "$eq": [ "$_id", viewer_id ] } ] },
+  };
+
+  vec![doc! { "$addFields": {
+    "email": {
+      "$cond": { "if": is_allowed, "then": "$email", "else": "$$REMOVE" }
+    },
+  } }]
+}
+
+pub fn pipeline_paginated_organization(
+  login: &String,
+  pagination_arguments: PaginationArguments,
+  fields: &Fields,
+) -> Pipeline {
   let (direction, limit, cursor) = pagination_arguments.parse_args().unwrap();
   let organization_id = model::utils::to_object_id(cursor);
   let order = model::utils::to_order(&direction);
@@ -170,10 +330,19 @@ fn pipeline_paginated_organization(login: &String, pagination_arguments: Paginat
     .chain(filter_by_organization_id)
     .chain(paginate_items)
     .chain(project_organizations)
+    .chain(model::utils::pipeline_fields(
+      fields,
+      model::organization::REQUIRED_FIELDS,
+    ))
     .collect()
 }
 
-fn pipeline_paginated_starred_repositories(login: &String, pagination_arguments: PaginationArguments) -> Pipeline {
+pub fn pipeline_paginated_starred_repositories(
+  login: &String,
+  pagination_arguments: PaginationArguments,
+  viewer_id: &Option<ObjectId>,
+  fields: &Fields,
+) -> Pipeline {
   let (direction, limit, cursor) = pagination_arguments.parse_args().unwrap();
   let repository_id = model::utils::to_object_id(cursor);
   let order = model::utils::to_order(&direction);
@@ -218,10 +387,20 @@ fn pipeline_paginated_starred_repositories(login: &String, pagination_arguments:
     .chain(lookup_with_repositories)
     .chain(filter_by_repository_id)
     .chain(paginate_items)
+    .chain(model::repository::pipeline_viewer_has_starred(viewer_id))
+    .chain(model::utils::pipeline_fields(
+      fields,
+      model::repository::REQUIRED_FIELDS,
+    ))
     .collect()
 }
 
-fn pipeline_paginated_followers(login: &String, pagination_arguments: PaginationArguments) -> Pipeline {
+pub fn pipeline_paginated_followers(
+  login: &String,
+  pagination_arguments: PaginationArguments,
+  viewer_id: &Option<ObjectId>,
+  fields: &Fields,
+) -> Pipeline {
   let (direction, limit, cursor) = pagination_arguments.parse_args().unwrap();
   let user_id = model::utils::to_object_id(cursor);
   let order = model::utils::to_order(&direction);
@@ -266,10 +445,18 @@ fn pipeline_paginated_followers(login: &String, pagination_arguments: Pagination
     .chain(lookup_with_users)
     .chain(filter_by_user_id)
     .chain(paginate_items)
+    .chain(pipeline_viewer_relations(viewer_id))
+    .chain(pipeline_email_visibility(viewer_id))
+    .chain(model::utils::pipeline_fields(fields, REQUIRED_FIELDS))
     .collect()
 }
 
-fn pipeline_paginated_following(login: &String, pagination_arguments: PaginationArguments) -> Pipeline {
+pub fn pipeline_paginated_following(
+  login: &String,
+  pagination_arguments: PaginationArguments,
+  viewer_id: &Option<ObjectId>,
+  fields: &Fields,
+) -> Pipeline {
   let (direction, limit, cursor) = pagination_arguments.parse_args().unwrap();
   let user_id = model::utils::to_object_id(cursor);
   let order = model::utils::to_order(&direction);
@@ -314,5 +501,8 @@ fn pipeline_paginated_following(login: &String, pagination_arguments: Pagination
     .chain(lookup_with_users)
     .chain(filter_by_user_id)
     .chain(paginate_items)
+    .chain(pipeline_viewer_relations(viewer_id))
+    .chain(pipeline_email_visibility(viewer_id))
+    .chain(model::utils::pipeline_fields(fields, REQUIRED_FIELDS))
     .collect()
 }
@@ -1,4 +1,4 @@
-use crate::lib::cursor_connection::PaginationArguments;
+use crate::lib::{cursor_connection::PaginationArguments, fields::Fields};
 use crate::model::{organization, user::*};
 use crate::setup::mock;
 use base64;
@@ -10,9 +10,91 @@ async fn should_find_an_existing_user() {
   let login = format!("user_foo_{sufix}");
   let db = mock::setup(&sufix).await;
 
-  let user = find_user_by_login(&db, &login).await.unwrap().unwrap();
+  let user = find_user_by_login(&db, &login, &None, &Fields::default())
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(user.login, format!("user_foo_{sufix}"));
+}
+
+#[actix_rt::test]
+async fn should_project_a_user_on_the_asked_and_required_fields() {
+  let sufix = mock::random_sufix();
+  let login = format!("user_bar_{sufix}");
+  let db = mock::setup(&sufix).await;
+  let fields = Fields::parse("login").unwrap();
+
+  let user = find_user_by_login(&db, &login, &None, &fields).await.unwrap().unwrap();
+
+  assert_eq!(user.login, login);
+  assert_eq!(user.avatar_url, "https://bar.com/avatar.jpg");
+  assert_eq!(user.typename, "User");
+  assert_eq!(user.email, None);
+}
+
+#[actix_rt::test]
+async fn should_hide_a_private_email() {
+  let sufix = mock::random_sufix();
+  let login = format!("user_foo_{sufix}");
+  let viewer_login = format!("user_bar_{sufix}");
+  let db = mock::setup(&sufix).await;
+  let viewer = find_user_by_login(&db, &viewer_login, &None, &Fields::default())
+    .await
+    .unwrap()
+    .unwrap();
 
-  assert_eq!(user.email, "foo@email.com".to_owned());
+  let anonymous = find_user_by_login(&db, &login, &None, &Fields::default())
+    .await
+    .unwrap()
+    .unwrap();
+  let other_user = find_user_by_login(&db, &login, &Some(viewer._id), &Fields::default())
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(anonymous.email, None);
+  assert_eq!(other_user.email, None);
+}
+
+#[actix_rt::test]
+async fn should_show_a_private_email_to_the_user_themself() {
+  let sufix = mock::random_sufix();
+  let login = format!("user_foo_{sufix}");
+  let db = mock::setup(&sufix).await;
+  let user = find_user_by_login(&db, &login, &None, &Fields::default())
+    .await
+    .unwrap()
+    .unwrap();
+
+  let user = find_user_by_login(&db, &login, &Some(user._id), &Fields::default())
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(user.email, Some(Email("foo@email.com".to_string())));
+}
+
+#[actix_rt::test]
+async fn should_show_a_public_email() {
+  let sufix = mock::random_sufix();
+  let login = format!("user_bar_{sufix}");
+  let db = mock::setup(&sufix).await;
+
+  let user = find_user_by_login(&db, &login, &None, &Fields::default())
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(user.email, Some(Email("bar@email.com".to_string())));
+  assert_eq!(user.email_visibility, Some(EmailVisibility::Public));
+}
+
+#[test]
+fn should_redact_the_email_when_debugging_a_user() {
+  let email = Email("foo@email.com".to_string());
+
+  assert_eq!(format!("{:?}", email), "[redacted email]");
 }
 
 #[actix_rt::test]
@@ -21,11 +103,74 @@ async fn should_dont_panic_when_user_is_not_found() {
   let login = format!("user_xxx_{sufix}");
   let db = mock::setup(&sufix).await;
 
-  let user = find_user_by_login(&db, &login).await.unwrap();
+  let user = find_user_by_login(&db, &login, &None, &Fields::default())
+    .await
+    .unwrap();
 
   assert_eq!(user, None);
 }
 
+#[actix_rt::test]
+async fn should_not_compute_viewer_relations_when_there_is_no_viewer() {
+  let sufix = mock::random_sufix();
+  let login = format!("user_foo_{sufix}");
+  let db = mock::setup(&sufix).await;
+
+  let user = find_user_by_login(&db, &login, &None, &Fields::default())
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(user.viewer_is_following, None);
+  assert_eq!(user.is_following_viewer, None);
+}
+
+#[actix_rt::test]
+async fn should_compute_viewer_relations_of_the_user() {
+  let sufix = mock::random_sufix();
+  let login = format!("user_foo_{sufix}");
+  let viewer_login = format!("user_bar_{sufix}");
+  let db = mock::setup(&sufix).await;
+  let viewer = find_user_by_login(&db, &viewer_login, &None, &Fields::default())
+    .await
+    .unwrap()
+    .unwrap();
+
+  let user = find_user_by_login(&db, &login, &Some(viewer._id), &Fields::default())
+    .await
+    .unwrap()
+    .unwrap();
+
+  assert_eq!(user.viewer_is_following, Some(true));
+  assert_eq!(user.is_following_viewer, Some(false));
+}
+
+#[actix_rt::test]
+async fn should_compute_viewer_relations_of_the_followers() {
+  let sufix = mock::random_sufix();
+  let login = format!("user_bar_{sufix}");
+  let viewer_login = format!("user_foo_{sufix}");
+  let db = mock::setup(&sufix).await;
+  let viewer = find_user_by_login(&db, &viewer_login, &None, &Fields::default())
+    .await
+    .unwrap()
+    .unwrap();
+  let pagination_argument = PaginationArguments {
+    first: Some(1),
+    after: None,
+    last: None,
+    before: None,
+  };
+
+  let users = find_followers_by_login(&db, &login, pagination_argument, &Some(viewer._id), &Fields::default())
+    .await
+    .unwrap();
+
+  assert_eq!(users[0].login, format!("user_dee_{sufix}"));
+  assert_eq!(users[0].viewer_is_following, Some(false));
+  assert_eq!(users[0].is_following_viewer, Some(true));
+}
+
 #[actix_rt::test]
 async fn should_find_users_organizations() {
   let sufix = mock::random_sufix();
@@ -38,7 +183,7 @@ async fn should_find_users_organizations() {
     before: None,
   };
 
-  let organizations = find_organizations_by_login(&db, &login, pagination_argument)
+  let organizations = find_organizations_by_login(&db, &login, pagination_argument, &Fields::default())
     .await
     .unwrap();
 
@@ -58,7 +203,7 @@ async fn should_dont_panic_when_organization_is_not_found() {
     before: None,
   };
 
-  let organizations = find_organizations_by_login(&db, &login, pagination_argument)
+  let organizations = find_organizations_by_login(&db, &login, pagination_argument, &Fields::default())
     .await
     .unwrap();
 
@@ -77,7 +222,7 @@ async fn should_find_users_starred_repositories() {
     before: None,
   };
 
-  let repositories = find_starred_repositories_by_login(&db, &login, pagination_argument)
+  let repositories = find_starred_repositories_by_login(&db, &login, pagination_argument, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -97,7 +242,7 @@ async fn should_dont_panic_when_starred_reposiotry_is_not_found() {
     before: None,
   };
 
-  let repositories = find_starred_repositories_by_login(&db, &login, pagination_argument)
+  let repositories = find_starred_repositories_by_login(&db, &login, pagination_argument, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -116,7 +261,9 @@ async fn should_find_users_followers() {
     before: None,
   };
 
-  let users = find_followers_by_login(&db, &login, pagination_argument).await.unwrap();
+  let users = find_followers_by_login(&db, &login, pagination_argument, &None, &Fields::default())
+    .await
+    .unwrap();
 
   assert_eq!(users.len(), 2);
   assert_eq!(users[0].login, format!("user_bar_{sufix}"));
@@ -135,7 +282,9 @@ async fn should_dont_panic_when_follower_is_not_found() {
     before: None,
   };
 
-  let repositories = find_followers_by_login(&db, &login, pagination_argument).await.unwrap();
+  let repositories = find_followers_by_login(&db, &login, pagination_argument, &None, &Fields::default())
+    .await
+    .unwrap();
 
   assert_eq!(repositories.len(), 0);
 }
@@ -152,7 +301,9 @@ async fn should_find_users_following() {
     before: None,
   };
 
-  let users = find_following_by_login(&db, &login, pagination_argument).await.unwrap();
+  let users = find_following_by_login(&db, &login, pagination_argument, &None, &Fields::default())
+    .await
+    .unwrap();
 
   assert_eq!(users.len(), 2);
   assert_eq!(users[0].login, format!("user_foo_{sufix}"));
@@ -171,7 +322,9 @@ async fn should_dont_panic_when_following_is_not_found() {
     before: None,
   };
 
-  let repositories = find_following_by_login(&db, &login, pagination_argument).await.unwrap();
+  let repositories = find_following_by_login(&db, &login, pagination_argument, &None, &Fields::default())
+    .await
+    .unwrap();
 
   assert_eq!(repositories.len(), 0);
 }
@@ -187,11 +340,12 @@ async fn should_convert_a_user_list_into_cursor_connection_of_users() {
     last: None,
     before: None,
   };
-  let users = organization::find_people_by_login(&db, &organization_login, pagination_argument)
-    .await
-    .unwrap();
+  let users =
+    organization::find_people_by_login(&db, &organization_login, pagination_argument, &None, &Fields::default())
+      .await
+      .unwrap();
 
-  let cursor_connection = users_to_cursor_connection(&db, &organization_login, Ok(users))
+  let cursor_connection = users_to_cursor_connection(&db, &organization_login, "organizations", "people", Ok(users))
     .await
     .unwrap();
 
@@ -218,7 +372,7 @@ async fn should_paginating_organizations_from_start_to_end() {
     before: None,
   };
 
-  let organizations = find_organizations_by_login(&db, &login, pagination_arguments)
+  let organizations = find_organizations_by_login(&db, &login, pagination_arguments, &Fields::default())
     .await
     .unwrap();
 
@@ -236,7 +390,7 @@ async fn should_paginating_organizations_from_start_to_end() {
     before: None,
   };
 
-  let organizations = find_organizations_by_login(&db, &login, pagination_arguments)
+  let organizations = find_organizations_by_login(&db, &login, pagination_arguments, &Fields::default())
     .await
     .unwrap();
 
@@ -254,7 +408,7 @@ async fn should_paginating_organizations_from_start_to_end() {
     before: None,
   };
 
-  let organizations = find_organizations_by_login(&db, &login, pagination_arguments)
+  let organizations = find_organizations_by_login(&db, &login, pagination_arguments, &Fields::default())
     .await
     .unwrap();
 
@@ -276,7 +430,7 @@ async fn should_paginating_organizations_from_end_to_start() {
     before: None,
   };
 
-  let organizations = find_organizations_by_login(&db, &login, pagination_arguments)
+  let organizations = find_organizations_by_login(&db, &login, pagination_arguments, &Fields::default())
     .await
     .unwrap();
 
@@ -294,7 +448,7 @@ async fn should_paginating_organizations_from_end_to_start() {
     before: start_cursor,
   };
 
-  let organizations = find_organizations_by_login(&db, &login, pagination_arguments)
+  let organizations = find_organizations_by_login(&db, &login, pagination_arguments, &Fields::default())
     .await
     .unwrap();
 
@@ -312,7 +466,7 @@ async fn should_paginating_organizations_from_end_to_start() {
     before: start_cursor,
   };
 
-  let organizations = find_organizations_by_login(&db, &login, pagination_arguments)
+  let organizations = find_organizations_by_login(&db, &login, pagination_arguments, &Fields::default())
     .await
     .unwrap();
 
@@ -336,7 +490,7 @@ async fn should_paginating_starred_repositories_from_start_to_end() {
     before: None,
   };
 
-  let repositories = find_starred_repositories_by_login(&db, &login, pagination_arguments)
+  let repositories = find_starred_repositories_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -354,7 +508,7 @@ async fn should_paginating_starred_repositories_from_start_to_end() {
     before: None,
   };
 
-  let repositories = find_starred_repositories_by_login(&db, &login, pagination_arguments)
+  let repositories = find_starred_repositories_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -372,7 +526,7 @@ async fn should_paginating_starred_repositories_from_start_to_end() {
     before: None,
   };
 
-  let repositories = find_starred_repositories_by_login(&db, &login, pagination_arguments)
+  let repositories = find_starred_repositories_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -394,7 +548,7 @@ async fn should_paginating_starred_repositories_from_end_to_start() {
     before: None,
   };
 
-  let repositories = find_starred_repositories_by_login(&db, &login, pagination_arguments)
+  let repositories = find_starred_repositories_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -412,7 +566,7 @@ async fn should_paginating_starred_repositories_from_end_to_start() {
     before: start_cursor,
   };
 
-  let repositories = find_starred_repositories_by_login(&db, &login, pagination_arguments)
+  let repositories = find_starred_repositories_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -430,7 +584,7 @@ async fn should_paginating_starred_repositories_from_end_to_start() {
     before: start_cursor,
   };
 
-  let repositories = find_starred_repositories_by_login(&db, &login, pagination_arguments)
+  let repositories = find_starred_repositories_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -454,7 +608,7 @@ async fn should_paginating_followers_from_start_to_end() {
     before: None,
   };
 
-  let users = find_followers_by_login(&db, &login, pagination_arguments)
+  let users = find_followers_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -472,7 +626,7 @@ async fn should_paginating_followers_from_start_to_end() {
     before: None,
   };
 
-  let users = find_followers_by_login(&db, &login, pagination_arguments)
+  let users = find_followers_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -490,7 +644,7 @@ async fn should_paginating_followers_from_start_to_end() {
     before: None,
   };
 
-  let users = find_followers_by_login(&db, &login, pagination_arguments)
+  let users = find_followers_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -512,7 +666,7 @@ async fn should_paginating_followers_from_end_to_start() {
     before: None,
   };
 
-  let users = find_followers_by_login(&db, &login, pagination_arguments)
+  let users = find_followers_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -530,7 +684,7 @@ async fn should_paginating_followers_from_end_to_start() {
     before: start_cursor,
   };
 
-  let users = find_followers_by_login(&db, &login, pagination_arguments)
+  let users = find_followers_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -548,7 +702,7 @@ async fn should_paginating_followers_from_end_to_start() {
     before: start_cursor,
   };
 
-  let users = find_followers_by_login(&db, &login, pagination_arguments)
+  let users = find_followers_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -572,7 +726,7 @@ async fn should_paginating_following_from_start_to_end() {
     before: None,
   };
 
-  let users = find_following_by_login(&db, &login, pagination_arguments)
+  let users = find_following_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -590,7 +744,7 @@ async fn should_paginating_following_from_start_to_end() {
     before: None,
   };
 
-  let users = find_following_by_login(&db, &login, pagination_arguments)
+  let users = find_following_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -608,7 +762,7 @@ async fn should_paginating_following_from_start_to_end() {
     before: None,
   };
 
-  let users = find_following_by_login(&db, &login, pagination_arguments)
+  let users = find_following_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -630,7 +784,7 @@ async fn should_paginating_following_from_end_to_start() {
     before: None,
   };
 
-  let users = find_following_by_login(&db, &login, pagination_arguments)
+  let users = find_following_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -648,7 +802,7 @@ async fn should_paginating_following_from_end_to_start() {
     before: start_cursor,
   };
 
-  let users = find_following_by_login(&db, &login, pagination_arguments)
+  let users = find_following_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -666,7 +820,7 @@ async fn should_paginating_following_from_end_to_start() {
     before: start_cursor,
   };
 
-  let users = find_following_by_login(&db, &login, pagination_arguments)
+  let users = find_following_by_login(&db, &login, pagination_arguments, &None, &Fields::default())
     .await
     .unwrap();
 
@@ -1,4 +1,7 @@
-use crate::lib::cursor_connection::{cursor_to_reference, Direction};
+use crate::lib::{
+  cursor_connection::{cursor_to_reference, Direction},
+  fields::Fields,
+};
 use crate::model;
 use mongodb::{
   bson::{self, doc, oid::ObjectId, Document},
@@ -16,6 +19,29 @@ pub async fn collect_into_model<T: DeserializeOwned>(cursor: Cursor<Document>) -
     .collect::<Vec<T>>()
 }
 
+/// The ids of a list of references like `[{ "_id": ObjectId, "ref": "users" }]`
+pub fn referenced_ids(document: &Document, field: &str) -> Vec<ObjectId> {
+  document
+    .get_array(field)
+    .map(|references| {
+      references
+        .iter()
+        .filter_map(|reference| reference.as_document())
+        .filter_map(|reference| reference.get_object_id("_id").ok())
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Keeps the asked fields of the documents at the end of a pipeline, along with the `required` fields of their model
+/// which are removed from the response instead
+pub fn pipeline_fields(fields: &Fields, required: &[&str]) -> model::Pipeline {
+  match fields.projection(required) {
+    None => vec![],
+    Some(projection) => vec![doc! { "$project": projection }],
+  }
+}
+
 pub fn to_object_id(cursor: Option<String>) -> Option<ObjectId> {
   if cursor.is_none() {
     return None;
@@ -0,0 +1,384 @@
+use crate::setup::{
+  integrity::find_dangling_references,
+  seed::{parse_document, validate_document},
+};
+use mongodb::{
+  bson::{doc, oid::ObjectId, Bson, DateTime, Document},
+  error::Error as ModelError,
+  options::FindOptions,
+  Client, Database,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+  collections::{BTreeMap, HashMap, HashSet},
+  fmt,
+  fs::{self, File},
+  io::{self, BufRead, BufReader, BufWriter, Write},
+  path::{Path, PathBuf},
+};
+use tokio_stream::StreamExt;
+use tracing;
+
+pub const COLLECTIONS: [&str; 3] = ["users", "organizations", "repositories"];
+const MANIFEST_FILE: &str = "manifest.json";
+const INSERT_BATCH_SIZE: usize = 1000;
+/// `import --drop` fills these collections first and renames them over the imported ones once they are complete
+const STAGING_PREFIX: &str = "_import_";
+/// A broken backup can have millions of issues, only the first ones are listed
+const MAX_LISTED_ISSUES: usize = 50;
+
+/// Written next to the collection files, tells how many documents each file must have
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+  pub created_at: String,
+  pub collections: BTreeMap<String, usize>,
+}
+
+#[derive(Debug)]
+pub enum BackupError {
+  Io(String, io::Error),
+  Parse { file: String, line: usize, message: String },
+  Inconsistent { issues: Vec<String>, total: usize },
+  NotEmpty(&'static str),
+  Database(ModelError),
+}
+impl fmt::Display for BackupError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      BackupError::Io(path, err) => write!(f, "{}: {}", path, err),
+      BackupError::Parse { file, line, message } => write!(f, "{}:{}: {}", file, line, message),
+      BackupError::Inconsistent { issues, total } => {
+        write!(f, "the backup has {} issue(s)", total)?;
+        for issue in issues {
+          write!(f, "\n  {}", issue)?;
+        }
+        match total - issues.len() {
+          0 => Ok(()),
+          more => write!(f, "\n  and {} more", more),
+        }
+      }
+      BackupError::NotEmpty(collection) => write!(
+        f,
+        "the collection {} is not empty, use --drop to replace its documents",
+        collection
+      ),
+      BackupError::Database(err) => write!(f, "{}", err),
+    }
+  }
+}
+impl From<ModelError> for BackupError {
+  fn from(err: ModelError) -> Self {
+    BackupError::Database(err)
+  }
+}
+
+/// Writes one document per line in canonical extended JSON, which keeps the BSON types of the values
+pub struct NdjsonWriter {
+  path: String,
+  writer: BufWriter<File>,
+  count: usize,
+}
+impl NdjsonWriter {
+  pub fn create(path: &Path) -> Result<Self, BackupError> {
+    let file = File::create(path).map_err(|err| BackupError::Io(path.display().to_string(), err))?;
+    Ok(Self {
+      path: path.display().to_string(),
+      writer: BufWriter::new(file),
+      count: 0,
+    })
+  }
+
+  pub fn write(&mut self, document: Document) -> Result<(), BackupError> {
+    let value = Bson::Document(document).into_canonical_extjson();
+    serde_json::to_writer(&mut self.writer, &value)
+      .map_err(io::Error::from)
+      .and_then(|_| self.writer.write_all(b"\n"))
+      .map_err(|err| BackupError::Io(self.path.clone(), err))?;
+    self.count += 1;
+    Ok(())
+  }
+
+  /// Flushes the file and returns the number of documents written
+  pub fn finish(mut self) -> Result<usize, BackupError> {
+    self
+      .writer
+      .flush()
+      .map_err(|err| BackupError::Io(self.path.clone(), err))?;
+    Ok(self.count)
+  }
+}
+
+/// Reads the documents of a file one line at a time, the blank lines are skipped
+pub struct NdjsonReader {
+  path: String,
+  lines: io::Lines<BufReader<File>>,
+  line: usize,
+}
+impl NdjsonReader {
+  pub fn open(path: &Path) -> Result<Self, BackupError> {
+    let file = File::open(path).map_err(|err| BackupError::Io(path.display().to_string(), err))?;
+    Ok(Self {
+      path: path.display().to_string(),
+      lines: BufReader::new(file).lines(),
+      line: 0,
+    })
+  }
+}
+impl Iterator for NdjsonReader {
+  type Item = Result<Document, BackupError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      let line = match self.lines.next()? {
+        Ok(line) => line,
+        Err(err) => return Some(Err(BackupError::Io(self.path.clone(), err))),
+      };
+      self.line += 1;
+      if line.trim().is_empty() {
+        continue;
+      }
+
+      return Some(parse_document(&line).map_err(|message| BackupError::Parse {
+        file: self.path.clone(),
+        line: self.line,
+        message,
+      }));
+    }
+  }
+}
+
+pub fn collection_path(dir: &Path, collection: &str) -> PathBuf {
+  dir.join(format!("{}.ndjson", collection))
+}
+
+pub fn write_manifest(dir: &Path, manifest: &Manifest) -> Result<(), BackupError> {
+  let path = dir.join(MANIFEST_FILE);
+  let content =
+    serde_json::to_string_pretty(manifest).map_err(|err| BackupError::Io(path.display().to_string(), err.into()))?;
+  fs::write(&path, content).map_err(|err| BackupError::Io(path.display().to_string(), err))
+}
+
+pub fn read_manifest(dir: &Path) -> Result<Manifest, BackupError> {
+  let path = dir.join(MANIFEST_FILE);
+  let file = path.display().to_string();
+  let content = fs::read_to_string(&path).map_err(|err| BackupError::Io(file.clone(), err))?;
+  serde_json::from_str(&content).map_err(|err| BackupError::Parse {
+    file,
+    line: err.line(),
+    message: err.to_string(),
+  })
+}
+
+/// Streams every collection into `DIR/<collection>.ndjson` ordered by `_id`, then writes the manifest. A file is only
+/// renamed to its final name once complete. The collections are read one after the other, the writes made meanwhile
+/// may be partially included.
+pub async fn export(db: &Database, dir: &Path) -> Result<Manifest, BackupError> {
+  fs::create_dir_all(dir).map_err(|err| BackupError::Io(dir.display().to_string(), err))?;
+  let mut collections = BTreeMap::new();
+
+  for collection in COLLECTIONS {
+    let path = collection_path(dir, collection);
+    let partial_path = path.with_extension("ndjson.partial");
+    let mut writer = NdjsonWriter::create(&partial_path)?;
+    let options = FindOptions::builder().sort(doc! { "_id": 1 }).build();
+    let mut cursor = db.collection::<Document>(collection).find(None, options).await?;
+    while let Some(document) = cursor.next().await {
+      writer.write(document?)?;
+    }
+    let count = writer.finish()?;
+    fs::rename(&partial_path, &path).map_err(|err| BackupError::Io(path.display().to_string(), err))?;
+
+    tracing::info!(collection, count, "Collection exported");
+    collections.insert(collection.to_string(), count);
+  }
+
+  let manifest = Manifest {
+    created_at: DateTime::now().try_to_rfc3339_string().unwrap_or_default(),
+    collections,
+  };
+  write_manifest(dir, &manifest)?;
+
+  Ok(manifest)
+}
+
+/// Checks a backup without keeping its documents in memory. A first pass checks the models, the duplicated ids and
+/// logins and the counts of the manifest while collecting the ids, a second one checks the references against them.
+pub fn check(dir: &Path) -> Result<Manifest, BackupError> {
+  let manifest = read_manifest(dir)?;
+  let mut issues = Issues::default();
+  let mut ids: HashMap<&str, HashSet<ObjectId>> = HashMap::new();
+
+  for collection in COLLECTIONS {
+    let collection_ids = ids.entry(collection).or_default();
+    let mut logins = HashSet::new();
+    let mut count = 0;
+    for document in NdjsonReader::open(&collection_path(dir, collection))? {
+      let document = document?;
+      count += 1;
+      if let Some(issue) = validate_document(collection, &document) {
+        issues.push(issue);
+      }
+      if let Ok(id) = document.get_object_id("_id") {
+        if !collection_ids.insert(id) {
+          issues.push(format!("{}: duplicated _id {}", collection, id));
+        }
+      }
+      if let Ok(login) = document.get_str("login") {
+        if !logins.insert(login.to_string()) {
+          issues.push(format!("{}: duplicated login {:?}", collection, login));
+        }
+      }
+    }
+
+    let expected = manifest.collections.get(collection).copied().unwrap_or_default();
+    if count != expected {
+      issues.push(format!(
+        "{}: {} documents instead of the {} of the manifest",
+        collection, count, expected
+      ));
+    }
+  }
+
+  let exists = |collection: &str, id: &ObjectId| ids[collection].contains(id);
+  for collection in COLLECTIONS {
+    for document in NdjsonReader::open(&collection_path(dir, collection))? {
+      for issue in find_dangling_references(collection, &document?, exists) {
+        issues.push(issue.to_string());
+      }
+    }
+  }
+
+  match issues.total {
+    0 => Ok(manifest),
+    total => Err(BackupError::Inconsistent {
+      issues: issues.listed,
+      total,
+    }),
+  }
+}
+
+/// Checks the backup then inserts its documents in batches, keeping their ids. The collections must be empty unless
+/// `drop` is set, the documents are inserted into staging collections with the options and indexes of the replaced
+/// ones then, and each collection is replaced by its staging one when they are all filled. A failed import leaves the
+/// collections untouched, but the replacement is atomic per collection only.
+pub async fn import(client: &Client, db: &Database, dir: &Path, drop: bool) -> Result<Manifest, BackupError> {
+  let manifest = check(dir)?;
+
+  if !drop {
+    for collection in COLLECTIONS {
+      if db
+        .collection::<Document>(collection)
+        .find_one(None, None)
+        .await?
+        .is_some()
+      {
+        return Err(BackupError::NotEmpty(collection));
+      }
+    }
+    for collection in COLLECTIONS {
+      insert_documents(db, dir, collection, collection).await?;
+    }
+    return Ok(manifest);
+  }
+
+  if let Err(err) = stage(db, dir).await {
+    for collection in COLLECTIONS {
+      if let Err(err) = db.collection::<Document>(&staging_name(collection)).drop(None).await {
+        tracing::warn!(collection, error = %err, "Staging collection not dropped");
+      }
+    }
+    return Err(err);
+  }
+  for collection in COLLECTIONS {
+    let command = doc! {
+      "renameCollection": format!("{}.{}", db.name(), staging_name(collection)),
+      "to": format!("{}.{}", db.name(), collection),
+      "dropTarget": true,
+    };
+    client.database("admin").run_command(command, None).await?;
+    tracing::info!(collection, "Collection replaced");
+  }
+
+  Ok(manifest)
+}
+
+fn staging_name(collection: &str) -> String {
+  format!("{}{}", STAGING_PREFIX, collection)
+}
+
+/// Fills a staging collection per collection, created like the collection it replaces so that the rename keeps its
+/// validator and indexes
+async fn stage(db: &Database, dir: &Path) -> Result<(), BackupError> {
+  for collection in COLLECTIONS {
+    let staging = staging_name(collection);
+    db.collection::<Document>(&staging).drop(None).await?;
+
+    let specification = db
+      .list_collections(doc! { "name": collection }, None)
+      .await?
+      .next()
+      .await
+      .transpose()?;
+    match specification {
+      Some(specification) => {
+        db.create_collection(&staging, specification.options).await?;
+        let indexes: Vec<_> = db
+          .collection::<Document>(collection)
+          .list_indexes(None)
+          .await?
+          .collect::<Result<Vec<_>, _>>()
+          .await?
+          .into_iter()
+          .filter(|index| index.keys != doc! { "_id": 1 })
+          .collect();
+        if !indexes.is_empty() {
+          db.collection::<Document>(&staging)
+            .create_indexes(indexes, None)
+            .await?;
+        }
+      }
+      None => db.create_collection(&staging, None).await?,
+    }
+
+    insert_documents(db, dir, collection, &staging).await?;
+  }
+
+  Ok(())
+}
+
+/// Inserts the documents of the backup file of `collection` into `target`
+async fn insert_documents(db: &Database, dir: &Path, collection: &str, target: &str) -> Result<(), BackupError> {
+  let target = db.collection::<Document>(target);
+  let mut batch = Vec::with_capacity(INSERT_BATCH_SIZE);
+  let mut count = 0;
+  for document in NdjsonReader::open(&collection_path(dir, collection))? {
+    batch.push(document?);
+    if batch.len() == INSERT_BATCH_SIZE {
+      target.insert_many(batch.drain(..), None).await?;
+      count += INSERT_BATCH_SIZE;
+    }
+  }
+  if !batch.is_empty() {
+    count += batch.len();
+    target.insert_many(batch, None).await?;
+  }
+
+  tracing::info!(collection, count, "Collection imported");
+  Ok(())
+}
+
+/// Counts every issue and keeps the first ones
+#[derive(Default)]
+struct Issues {
+  listed: Vec<String>,
+  total: usize,
+}
+impl Issues {
+  fn push(&mut self, issue: String) {
+    if self.listed.len() < MAX_LISTED_ISSUES {
+      self.listed.push(issue);
+    }
+    self.total += 1;
+  }
+}
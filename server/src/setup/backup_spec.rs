@@ -0,0 +1,180 @@
+use crate::setup::{
+  backup::{
+    check, collection_path, read_manifest, write_manifest, BackupError, Manifest, NdjsonReader, NdjsonWriter,
+    COLLECTIONS,
+  },
+  seed::{generate, GenerateOptions, SeedData},
+};
+use mongodb::bson::{doc, oid::ObjectId, DateTime};
+use pretty_assertions::assert_eq;
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+fn temp_dir() -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("backup_spec_{}", ObjectId::new()));
+  fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+fn data() -> SeedData {
+  generate(&GenerateOptions {
+    users: 30,
+    organizations: 4,
+    repositories: 40,
+    seed: 5,
+  })
+}
+
+/// Writes the collections and a manifest with their counts, like `export` does
+fn write_backup(dir: &Path, data: &SeedData) {
+  let mut collections = std::collections::BTreeMap::new();
+  for (collection, documents) in data.collections() {
+    let mut writer = NdjsonWriter::create(&collection_path(dir, collection)).unwrap();
+    for document in documents {
+      writer.write(document.clone()).unwrap();
+    }
+    collections.insert(collection.to_string(), writer.finish().unwrap());
+  }
+  let manifest = Manifest {
+    created_at: "2022-10-01T00:00:00Z".to_string(),
+    collections,
+  };
+  write_manifest(dir, &manifest).unwrap();
+}
+
+fn issues_of(dir: &Path) -> (Vec<String>, usize) {
+  match check(dir) {
+    Err(BackupError::Inconsistent { issues, total }) => (issues, total),
+    result => panic!("expected issues, got {:?}", result),
+  }
+}
+
+#[test]
+fn should_accept_a_consistent_backup() {
+  let dir = temp_dir();
+  write_backup(&dir, &data());
+
+  let manifest = check(&dir).unwrap();
+  fs::remove_dir_all(&dir).unwrap();
+
+  assert_eq!(manifest.collections["users"], 30);
+  assert_eq!(manifest.collections["organizations"], 4);
+  assert_eq!(manifest.collections["repositories"], 40);
+}
+
+#[test]
+fn should_read_back_the_documents_with_their_types() {
+  let dir = temp_dir();
+  let path = dir.join("values.ndjson");
+  let document = doc! {
+    "_id": ObjectId::new(),
+    "int32": 1_i32,
+    "int64": 1_i64,
+    "double": 1.0,
+    "createdAt": DateTime::from_millis(1_577_836_800_000),
+  };
+  let mut writer = NdjsonWriter::create(&path).unwrap();
+  writer.write(document.clone()).unwrap();
+  writer.finish().unwrap();
+
+  let documents: Vec<_> = NdjsonReader::open(&path).unwrap().map(Result::unwrap).collect();
+  fs::remove_dir_all(&dir).unwrap();
+
+  assert_eq!(documents, vec![document]);
+}
+
+#[test]
+fn should_report_the_line_of_an_invalid_document() {
+  let dir = temp_dir();
+  write_backup(&dir, &data());
+  let path = collection_path(&dir, "organizations");
+  let content = fs::read_to_string(&path).unwrap();
+  fs::write(&path, format!("{}\n{{ \"_id\": \n", content)).unwrap();
+
+  let err = check(&dir).unwrap_err().to_string();
+  fs::remove_dir_all(&dir).unwrap();
+
+  assert!(
+    err.ends_with("organizations.ndjson:6: EOF while parsing a value at line 1 column 9"),
+    "{}",
+    err
+  );
+}
+
+#[test]
+fn should_report_the_references_to_missing_documents() {
+  let dir = temp_dir();
+  let mut data = data();
+  let missing_id = ObjectId::new();
+  data.users[0].insert("following", vec![doc! { "_id": missing_id }]);
+  write_backup(&dir, &data);
+
+  let (issues, total) = issues_of(&dir);
+  fs::remove_dir_all(&dir).unwrap();
+
+  assert_eq!(total, 1);
+  assert_eq!(
+    issues,
+    vec![format!(
+      "users {}: following references the missing users {}",
+      data.users[0].get_object_id("_id").unwrap(),
+      missing_id
+    )]
+  );
+}
+
+#[test]
+fn should_report_duplicated_documents_and_counts_that_dont_match_the_manifest() {
+  let dir = temp_dir();
+  let mut data = data();
+  data.repositories.push(data.repositories[0].clone());
+  write_backup(&dir, &data);
+  let mut manifest = read_manifest(&dir).unwrap();
+  manifest.collections.insert("users".to_string(), 31);
+  write_manifest(&dir, &manifest).unwrap();
+
+  let (issues, _) = issues_of(&dir);
+  fs::remove_dir_all(&dir).unwrap();
+
+  assert_eq!(
+    issues,
+    vec![
+      "users: 30 documents instead of the 31 of the manifest".to_string(),
+      format!(
+        "repositories: duplicated _id {}",
+        data.repositories[0].get_object_id("_id").unwrap()
+      ),
+    ]
+  );
+}
+
+#[test]
+fn should_list_only_the_first_issues() {
+  let dir = temp_dir();
+  let mut data = data();
+  for user in data.users.iter_mut() {
+    let missing: Vec<_> = (0..3).map(|_| doc! { "_id": ObjectId::new() }).collect();
+    user.insert("starredRepositories", missing);
+  }
+  write_backup(&dir, &data);
+
+  let (issues, total) = issues_of(&dir);
+  fs::remove_dir_all(&dir).unwrap();
+
+  assert_eq!(issues.len(), 50);
+  assert_eq!(total, 90);
+}
+
+#[test]
+fn should_require_every_collection_file() {
+  let dir = temp_dir();
+  write_backup(&dir, &data());
+  fs::remove_file(collection_path(&dir, COLLECTIONS[2])).unwrap();
+
+  let err = check(&dir).unwrap_err().to_string();
+  fs::remove_dir_all(&dir).unwrap();
+
+  assert!(err.contains("repositories.ndjson: "), "{}", err);
+}
@@ -0,0 +1,756 @@
+use serde::Deserialize;
+use std::{env, fmt, fs, path::Path, str::FromStr};
+use tracing;
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+const MIN_ADMIN_TOKEN_LENGTH: usize = 16;
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ServerConfig {
+  pub host: String,
+  pub port: u16,
+}
+impl Default for ServerConfig {
+  fn default() -> Self {
+    Self {
+      host: "0.0.0.0".to_string(),
+      port: 8080,
+    }
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct DatabaseConfig {
+  pub uri: String,
+  pub name: String,
+  /// Commands slower than this are logged as warnings, `None` disables the log
+  pub slow_query_threshold_ms: Option<u64>,
+  pub max_pool_size: Option<u32>,
+  pub min_pool_size: Option<u32>,
+  pub connect_timeout_ms: u64,
+  pub server_selection_timeout_ms: u64,
+  /// Starts the server even when the database is unreachable after the retries, the readiness probe reports it as
+  /// failing until the database answers
+  pub start_degraded: bool,
+  pub retry: RetryConfig,
+}
+impl Default for DatabaseConfig {
+  fn default() -> Self {
+    Self {
+      uri: "mongodb://0.0.0.0:27017".to_string(),
+      name: "database".to_string(),
+      slow_query_threshold_ms: Some(500),
+      max_pool_size: None,
+      min_pool_size: None,
+      connect_timeout_ms: 5000,
+      server_selection_timeout_ms: 5000,
+      start_degraded: false,
+      retry: RetryConfig::default(),
+    }
+  }
+}
+
+/// Exponential backoff used while waiting for the database at startup, the delay doubles after each failed attempt
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetryConfig {
+  pub max_attempts: u32,
+  pub initial_delay_ms: u64,
+  pub max_delay_ms: u64,
+}
+impl Default for RetryConfig {
+  fn default() -> Self {
+    Self {
+      max_attempts: 5,
+      initial_delay_ms: 500,
+      max_delay_ms: 10000,
+    }
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaValidation {
+  Off,
+  Warn,
+  Error,
+}
+impl FromStr for SchemaValidation {
+  type Err = ();
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value.to_lowercase().as_str() {
+      "off" => Ok(Self::Off),
+      "warn" => Ok(Self::Warn),
+      "error" => Ok(Self::Error),
+      _ => Err(()),
+    }
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SchemaConfig {
+  /// Creates the indexes the queries rely on at startup
+  pub ensure_indexes: bool,
+  /// Applies the `$jsonSchema` validators of the models, `warn` only logs invalid writes on the database side
+  pub validation: SchemaValidation,
+  /// Reports the changes without applying them
+  pub dry_run: bool,
+}
+impl Default for SchemaConfig {
+  fn default() -> Self {
+    Self {
+      ensure_indexes: true,
+      validation: SchemaValidation::Off,
+      dry_run: false,
+    }
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct MigrationsConfig {
+  /// Applies the pending migrations before the server starts
+  pub run_at_startup: bool,
+  /// A lock older than this is considered abandoned by a crashed process
+  pub lock_timeout_secs: u64,
+}
+impl Default for MigrationsConfig {
+  fn default() -> Self {
+    Self {
+      run_at_startup: false,
+      lock_timeout_secs: 600,
+    }
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreBackend {
+  Mongo,
+  Memory,
+  Sqlite,
+}
+impl FromStr for StoreBackend {
+  type Err = ();
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value.to_lowercase().as_str() {
+      "mongo" => Ok(Self::Mongo),
+      "memory" => Ok(Self::Memory),
+      "sqlite" => Ok(Self::Sqlite),
+      _ => Err(()),
+    }
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct StoreConfig {
+  /// Where the routes read the data from, `memory` and `sqlite` run the server without MongoDB
+  pub backend: StoreBackend,
+  /// Seed files loaded into the `memory` backend at startup, it starts empty when absent. The `sqlite` backend
+  /// imports them when its database is empty.
+  pub seed_dir: Option<String>,
+  /// The database file of the `sqlite` backend, created and migrated at startup
+  pub sqlite_path: String,
+}
+impl Default for StoreConfig {
+  fn default() -> Self {
+    Self {
+      backend: StoreBackend::Mongo,
+      seed_dir: None,
+      sqlite_path: "database.sqlite".to_string(),
+    }
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CorsConfig {
+  /// Exact origins like `https://example.com` or subdomain patterns like `https://*.example.com`
+  pub allowed_origins: Vec<String>,
+  pub allowed_methods: Vec<String>,
+  pub allowed_headers: Vec<String>,
+  pub exposed_headers: Vec<String>,
+  pub supports_credentials: bool,
+  pub max_age: Option<usize>,
+}
+impl Default for CorsConfig {
+  fn default() -> Self {
+    Self {
+      allowed_origins: to_strings(&["http://localhost:3000"]),
+      allowed_methods: to_strings(&["GET", "POST", "PUT", "DELETE"]),
+      allowed_headers: to_strings(&["Authorization", "Accept", "Content-Type"]),
+      exposed_headers: to_strings(&["ETag", "Link", "X-Request-Id"]),
+      supports_credentials: false,
+      max_age: Some(3600),
+    }
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct HealthConfig {
+  /// How long the readiness probe waits for the database ping before reporting it as failing
+  pub database_timeout_ms: u64,
+}
+impl Default for HealthConfig {
+  fn default() -> Self {
+    Self {
+      database_timeout_ms: 2000,
+    }
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct StreamConfig {
+  /// Most items of a list sent as NDJSON in one response, the cursor of the last line continues the list
+  pub max_items: u32,
+  /// How many serialized items wait for a slow client before the list stops being read from the database
+  pub buffer_size: usize,
+}
+impl Default for StreamConfig {
+  fn default() -> Self {
+    Self {
+      max_items: 10000,
+      buffer_size: 64,
+    }
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CacheConfig {
+  /// Keeps the results of the store queries in memory, keyed by login, pagination arguments and viewer
+  pub enabled: bool,
+  /// Most results kept, the least recently used one is evicted first
+  pub max_entries: usize,
+  /// How long a result is served before it is read again from the store
+  pub ttl_secs: u64,
+  /// Invalidates the results of the changed documents with a MongoDB change stream, which needs a replica set
+  pub change_streams: bool,
+}
+impl Default for CacheConfig {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      max_entries: 10000,
+      ttl_secs: 30,
+      change_streams: false,
+    }
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CacheControlConfig {
+  /// `Cache-Control` of the successful GET responses of the scopes without their own policy
+  pub default: String,
+  pub profile: Option<String>,
+  pub user: Option<String>,
+  pub organization: Option<String>,
+}
+impl Default for CacheControlConfig {
+  fn default() -> Self {
+    Self {
+      default: "no-cache".to_string(),
+      profile: None,
+      user: None,
+      organization: None,
+    }
+  }
+}
+impl CacheControlConfig {
+  /// The policy of a route scope, `profile`, `user` or `organization`
+  pub fn policy(&self, scope: &str) -> &str {
+    let policy = match scope {
+      "profile" => &self.profile,
+      "user" => &self.user,
+      "organization" => &self.organization,
+      _ => &None,
+    };
+    policy.as_deref().unwrap_or(&self.default)
+  }
+
+  fn policies(&self) -> impl Iterator<Item = (&'static str, &String)> {
+    let scopes = [
+      ("cache_control.profile", &self.profile),
+      ("cache_control.user", &self.user),
+      ("cache_control.organization", &self.organization),
+    ];
+    std::iter::once(("cache_control.default", &self.default)).chain(
+      scopes
+        .into_iter()
+        .filter_map(|(key, policy)| Some((key, policy.as_ref()?))),
+    )
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TracingExporter {
+  Otlp,
+  Jaeger,
+  Stdout,
+  None,
+}
+impl FromStr for TracingExporter {
+  type Err = ();
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value.to_lowercase().as_str() {
+      "otlp" => Ok(Self::Otlp),
+      "jaeger" => Ok(Self::Jaeger),
+      "stdout" => Ok(Self::Stdout),
+      "none" => Ok(Self::None),
+      _ => Err(()),
+    }
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OtlpProtocol {
+  Grpc,
+  Http,
+}
+impl FromStr for OtlpProtocol {
+  type Err = ();
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value.to_lowercase().as_str() {
+      "grpc" => Ok(Self::Grpc),
+      "http" => Ok(Self::Http),
+      _ => Err(()),
+    }
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+  Bunyan,
+  Pretty,
+}
+impl FromStr for LogFormat {
+  type Err = ();
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value.to_lowercase().as_str() {
+      "bunyan" => Ok(Self::Bunyan),
+      "pretty" => Ok(Self::Pretty),
+      _ => Err(()),
+    }
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct TracingConfig {
+  pub ui: Option<String>,
+  pub exporter: TracingExporter,
+  /// Collector address, when absent each exporter falls back to its own default and `OTEL_*` variables
+  pub endpoint: Option<String>,
+  pub otlp_protocol: OtlpProtocol,
+  /// Fraction of the root traces that are sampled, child spans follow the decision of their parent
+  pub sampling_ratio: f64,
+  pub log_format: LogFormat,
+}
+impl Default for TracingConfig {
+  fn default() -> Self {
+    Self {
+      ui: None,
+      exporter: TracingExporter::Jaeger,
+      endpoint: None,
+      otlp_protocol: OtlpProtocol::Grpc,
+      sampling_ratio: 1.0,
+      log_format: LogFormat::Bunyan,
+    }
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ShutdownConfig {
+  /// How long the readiness probe reports not ready before the server stops accepting connections
+  pub readiness_delay_ms: u64,
+  /// How long the in-flight requests have to complete once the server stops accepting connections
+  pub timeout_secs: u64,
+}
+impl Default for ShutdownConfig {
+  fn default() -> Self {
+    Self {
+      readiness_delay_ms: 5000,
+      timeout_secs: 30,
+    }
+  }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct AdminConfig {
+  /// Bearer token required by the `/admin` endpoints, they are disabled when absent
+  pub token: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Config {
+  pub environment: String,
+  pub server: ServerConfig,
+  pub database: DatabaseConfig,
+  pub schema: SchemaConfig,
+  pub migrations: MigrationsConfig,
+  pub store: StoreConfig,
+  pub cors: CorsConfig,
+  pub health: HealthConfig,
+  pub stream: StreamConfig,
+  pub cache: CacheConfig,
+  pub cache_control: CacheControlConfig,
+  pub shutdown: ShutdownConfig,
+  pub tracing: TracingConfig,
+  pub admin: AdminConfig,
+}
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      environment: "development".to_string(),
+      server: ServerConfig::default(),
+      database: DatabaseConfig::default(),
+      schema: SchemaConfig::default(),
+      migrations: MigrationsConfig::default(),
+      store: StoreConfig::default(),
+      cors: CorsConfig::default(),
+      health: HealthConfig::default(),
+      stream: StreamConfig::default(),
+      cache: CacheConfig::default(),
+      cache_control: CacheControlConfig::default(),
+      shutdown: ShutdownConfig::default(),
+      tracing: TracingConfig::default(),
+      admin: AdminConfig::default(),
+    }
+  }
+}
+
+impl Config {
+  /// Loads the configuration from the defaults, the optional TOML file and the process environment, in this order of
+  /// precedence. The `.env` file is expected to be already loaded into the environment.
+  pub fn load() -> Result<Self, Error> {
+    Self::from_source(|key| match env::var(key).ok() {
+      None if key == "CONFIG_FILE" && Path::new(DEFAULT_CONFIG_FILE).exists() => Some(DEFAULT_CONFIG_FILE.to_string()),
+      value => value,
+    })
+  }
+
+  /// Builds the configuration from `lookup` only, the TOML file is read when `lookup` has a `CONFIG_FILE`
+  pub fn from_source<F>(lookup: F) -> Result<Self, Error>
+  where
+    F: Fn(&str) -> Option<String>,
+  {
+    let mut config = match lookup("CONFIG_FILE") {
+      Some(path) => Self::from_file(&path)?,
+      None => Self::default(),
+    };
+    config.override_from(&lookup)?;
+    config.validate()?;
+
+    Ok(config)
+  }
+
+  pub fn from_file(path: &str) -> Result<Self, Error> {
+    let content = fs::read_to_string(path).map_err(|err| Error::ReadFile(path.to_string(), err.to_string()))?;
+    Self::from_toml(&content).map_err(|err| match err {
+      Error::ParseFile(_, message) => Error::ParseFile(path.to_string(), message),
+      err => err,
+    })
+  }
+
+  pub fn from_toml(content: &str) -> Result<Self, Error> {
+    toml::from_str(content).map_err(|err| Error::ParseFile("".to_string(), err.to_string()))
+  }
+
+  fn override_from<F>(&mut self, lookup: &F) -> Result<(), Error>
+  where
+    F: Fn(&str) -> Option<String>,
+  {
+    override_value(lookup, "RUST_ENV", &mut self.environment)?;
+    override_value(lookup, "SERVER_HOST", &mut self.server.host)?;
+    override_value(lookup, "SERVER_PORT", &mut self.server.port)?;
+    override_value(lookup, "DATABASE_URI", &mut self.database.uri)?;
+    override_value(lookup, "DATABASE_NAME", &mut self.database.name)?;
+    override_optional_value(
+      lookup,
+      "DATABASE_SLOW_QUERY_THRESHOLD_MS",
+      &mut self.database.slow_query_threshold_ms,
+    )?;
+    override_optional_value(lookup, "DATABASE_MAX_POOL_SIZE", &mut self.database.max_pool_size)?;
+    override_optional_value(lookup, "DATABASE_MIN_POOL_SIZE", &mut self.database.min_pool_size)?;
+    override_value(
+      lookup,
+      "DATABASE_CONNECT_TIMEOUT_MS",
+      &mut self.database.connect_timeout_ms,
+    )?;
+    override_value(
+      lookup,
+      "DATABASE_SERVER_SELECTION_TIMEOUT_MS",
+      &mut self.database.server_selection_timeout_ms,
+    )?;
+    override_value(lookup, "DATABASE_START_DEGRADED", &mut self.database.start_degraded)?;
+    override_value(
+      lookup,
+      "DATABASE_RETRY_MAX_ATTEMPTS",
+      &mut self.database.retry.max_attempts,
+    )?;
+    override_value(
+      lookup,
+      "DATABASE_RETRY_INITIAL_DELAY_MS",
+      &mut self.database.retry.initial_delay_ms,
+    )?;
+    override_value(
+      lookup,
+      "DATABASE_RETRY_MAX_DELAY_MS",
+      &mut self.database.retry.max_delay_ms,
+    )?;
+    override_value(lookup, "SCHEMA_ENSURE_INDEXES", &mut self.schema.ensure_indexes)?;
+    override_value(lookup, "SCHEMA_VALIDATION", &mut self.schema.validation)?;
+    override_value(lookup, "SCHEMA_DRY_RUN", &mut self.schema.dry_run)?;
+    override_value(lookup, "MIGRATIONS_RUN_AT_STARTUP", &mut self.migrations.run_at_startup)?;
+    override_value(
+      lookup,
+      "MIGRATIONS_LOCK_TIMEOUT_SECS",
+      &mut self.migrations.lock_timeout_secs,
+    )?;
+    override_value(lookup, "STORE_BACKEND", &mut self.store.backend)?;
+    override_optional_value(lookup, "STORE_SEED_DIR", &mut self.store.seed_dir)?;
+    override_value(lookup, "STORE_SQLITE_PATH", &mut self.store.sqlite_path)?;
+    override_list(lookup, "CORS_ALLOWED_ORIGINS", &mut self.cors.allowed_origins);
+    override_list(lookup, "CORS_ALLOWED_METHODS", &mut self.cors.allowed_methods);
+    override_list(lookup, "CORS_ALLOWED_HEADERS", &mut self.cors.allowed_headers);
+    override_list(lookup, "CORS_EXPOSED_HEADERS", &mut self.cors.exposed_headers);
+    override_value(lookup, "CORS_SUPPORTS_CREDENTIALS", &mut self.cors.supports_credentials)?;
+    override_optional_value(lookup, "CORS_MAX_AGE", &mut self.cors.max_age)?;
+    override_value(
+      lookup,
+      "HEALTH_DATABASE_TIMEOUT_MS",
+      &mut self.health.database_timeout_ms,
+    )?;
+    override_value(lookup, "STREAM_MAX_ITEMS", &mut self.stream.max_items)?;
+    override_value(lookup, "STREAM_BUFFER_SIZE", &mut self.stream.buffer_size)?;
+    override_value(lookup, "CACHE_ENABLED", &mut self.cache.enabled)?;
+    override_value(lookup, "CACHE_MAX_ENTRIES", &mut self.cache.max_entries)?;
+    override_value(lookup, "CACHE_TTL_SECS", &mut self.cache.ttl_secs)?;
+    override_value(lookup, "CACHE_CHANGE_STREAMS", &mut self.cache.change_streams)?;
+    override_value(lookup, "CACHE_CONTROL_DEFAULT", &mut self.cache_control.default)?;
+    override_optional_value(lookup, "CACHE_CONTROL_PROFILE", &mut self.cache_control.profile)?;
+    override_optional_value(lookup, "CACHE_CONTROL_USER", &mut self.cache_control.user)?;
+    override_optional_value(
+      lookup,
+      "CACHE_CONTROL_ORGANIZATION",
+      &mut self.cache_control.organization,
+    )?;
+    override_value(
+      lookup,
+      "SHUTDOWN_READINESS_DELAY_MS",
+      &mut self.shutdown.readiness_delay_ms,
+    )?;
+    override_value(lookup, "SHUTDOWN_TIMEOUT_SECS", &mut self.shutdown.timeout_secs)?;
+    override_optional_value(lookup, "TRACING_UI", &mut self.tracing.ui)?;
+    override_value(lookup, "TRACING_EXPORTER", &mut self.tracing.exporter)?;
+    override_optional_value(lookup, "TRACING_ENDPOINT", &mut self.tracing.endpoint)?;
+    override_value(lookup, "TRACING_OTLP_PROTOCOL", &mut self.tracing.otlp_protocol)?;
+    override_value(lookup, "TRACING_SAMPLING_RATIO", &mut self.tracing.sampling_ratio)?;
+    override_value(lookup, "LOG_FORMAT", &mut self.tracing.log_format)?;
+    override_optional_value(lookup, "ADMIN_TOKEN", &mut self.admin.token)?;
+
+    Ok(())
+  }
+
+  pub fn validate(&self) -> Result<(), Error> {
+    if self.server.host.is_empty() {
+      return Err(Error::Invalid("server.host", "must not be empty".to_string()));
+    }
+    if self.server.port == 0 {
+      return Err(Error::Invalid("server.port", "must be greater than 0".to_string()));
+    }
+    if !self.database.uri.starts_with("mongodb://") && !self.database.uri.starts_with("mongodb+srv://") {
+      let reason = "must start with mongodb:// or mongodb+srv://".to_string();
+      return Err(Error::Invalid("database.uri", reason));
+    }
+    if self.database.name.is_empty() {
+      return Err(Error::Invalid("database.name", "must not be empty".to_string()));
+    }
+    if self.database.retry.max_attempts == 0 {
+      let reason = "must be greater than 0".to_string();
+      return Err(Error::Invalid("database.retry.max_attempts", reason));
+    }
+    if self.database.retry.initial_delay_ms > self.database.retry.max_delay_ms {
+      let reason = "must not be greater than database.retry.max_delay_ms".to_string();
+      return Err(Error::Invalid("database.retry.initial_delay_ms", reason));
+    }
+    if matches!((self.database.min_pool_size, self.database.max_pool_size), (Some(min), Some(max)) if min > max) {
+      let reason = "must not be greater than database.max_pool_size".to_string();
+      return Err(Error::Invalid("database.min_pool_size", reason));
+    }
+    if self.cors.allowed_origins.is_empty() {
+      return Err(Error::Invalid("cors.allowed_origins", "must not be empty".to_string()));
+    }
+    if let Some(origin) = self.cors.allowed_origins.iter().find(|origin| !is_valid_origin(origin)) {
+      let reason = format!(
+        "has the invalid origin {:?}, expected scheme://host or scheme://*.host",
+        origin
+      );
+      return Err(Error::Invalid("cors.allowed_origins", reason));
+    }
+    if self.stream.max_items == 0 {
+      return Err(Error::Invalid("stream.max_items", "must be greater than 0".to_string()));
+    }
+    if self.stream.buffer_size == 0 {
+      return Err(Error::Invalid(
+        "stream.buffer_size",
+        "must be greater than 0".to_string(),
+      ));
+    }
+    if self.cache.max_entries == 0 {
+      return Err(Error::Invalid(
+        "cache.max_entries",
+        "must be greater than 0".to_string(),
+      ));
+    }
+    if self.cache.ttl_secs == 0 {
+      return Err(Error::Invalid("cache.ttl_secs", "must be greater than 0".to_string()));
+    }
+    if self.cache.change_streams && self.store.backend != StoreBackend::Mongo {
+      let reason = "needs the mongo store backend".to_string();
+      return Err(Error::Invalid("cache.change_streams", reason));
+    }
+    if let Some((key, _)) = self
+      .cache_control
+      .policies()
+      .find(|(_, policy)| policy.is_empty() || !policy.chars().all(|c| c == ' ' || c.is_ascii_graphic()))
+    {
+      let reason = "must be a non empty header value of visible ASCII characters".to_string();
+      return Err(Error::Invalid(key, reason));
+    }
+    if !(0.0..=1.0).contains(&self.tracing.sampling_ratio) {
+      let reason = "must be between 0.0 and 1.0".to_string();
+      return Err(Error::Invalid("tracing.sampling_ratio", reason));
+    }
+    if matches!(&self.admin.token, Some(token) if token.len() < MIN_ADMIN_TOKEN_LENGTH) {
+      let reason = format!("must have at least {} characters", MIN_ADMIN_TOKEN_LENGTH);
+      return Err(Error::Invalid("admin.token", reason));
+    }
+
+    Ok(())
+  }
+
+  pub fn server_uri(&self) -> String {
+    format!("{}:{}", self.server.host, self.server.port)
+  }
+
+  pub fn report(&self) {
+    tracing::info!(
+      environment = %self.environment,
+      server.host = %self.server.host,
+      server.port = self.server.port,
+      database.uri = %hide_credentials(&self.database.uri),
+      database.name = %self.database.name,
+      database.start_degraded = self.database.start_degraded,
+      store.backend = ?self.store.backend,
+      cache.enabled = self.cache.enabled,
+      cors.allowed_origins = ?self.cors.allowed_origins,
+      cors.supports_credentials = self.cors.supports_credentials,
+      tracing.ui = ?self.tracing.ui,
+      tracing.exporter = ?self.tracing.exporter,
+      tracing.endpoint = ?self.tracing.endpoint,
+      tracing.sampling_ratio = self.tracing.sampling_ratio,
+      admin.enabled = self.admin.token.is_some(),
+      "Configuration loaded"
+    );
+  }
+}
+
+fn override_value<F, T>(lookup: &F, key: &'static str, target: &mut T) -> Result<(), Error>
+where
+  F: Fn(&str) -> Option<String>,
+  T: FromStr,
+{
+  if let Some(value) = lookup(key) {
+    *target = value.parse().map_err(|_| Error::InvalidValue(key, value.clone()))?;
+  }
+
+  Ok(())
+}
+
+fn override_optional_value<F, T>(lookup: &F, key: &'static str, target: &mut Option<T>) -> Result<(), Error>
+where
+  F: Fn(&str) -> Option<String>,
+  T: FromStr,
+{
+  if let Some(value) = lookup(key) {
+    let value = value.parse().map_err(|_| Error::InvalidValue(key, value.clone()))?;
+    *target = Some(value);
+  }
+
+  Ok(())
+}
+
+fn override_list<F>(lookup: &F, key: &'static str, target: &mut Vec<String>)
+where
+  F: Fn(&str) -> Option<String>,
+{
+  if let Some(value) = lookup(key) {
+    *target = value
+      .split(',')
+      .map(str::trim)
+      .filter(|item| !item.is_empty())
+      .map(str::to_string)
+      .collect();
+  }
+}
+
+fn to_strings(values: &[&str]) -> Vec<String> {
+  values.iter().map(|value| value.to_string()).collect()
+}
+
+fn is_valid_origin(origin: &str) -> bool {
+  match origin.split_once("://") {
+    Some((scheme, host)) => {
+      let host = host.strip_prefix("*.").unwrap_or(host);
+      !scheme.is_empty() && !host.is_empty() && !host.contains(['/', '*'])
+    }
+    None => false,
+  }
+}
+
+pub fn hide_credentials(uri: &str) -> String {
+  match (uri.find("://"), uri.rfind('@')) {
+    (Some(scheme_end), Some(credentials_end)) if scheme_end < credentials_end => {
+      format!("{}://***{}", &uri[..scheme_end], &uri[credentials_end..])
+    }
+    _ => uri.to_string(),
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+  ReadFile(String, String),
+  ParseFile(String, String),
+  InvalidValue(&'static str, String),
+  Invalid(&'static str, String),
+}
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::ReadFile(path, message) => write!(f, "could not read the config file {}: {}", path, message),
+      Error::ParseFile(path, message) => write!(f, "could not parse the config file {}: {}", path, message),
+      Error::InvalidValue(key, value) => write!(f, "the environment variable {} has an invalid value {:?}", key, value),
+      Error::Invalid(field, reason) => write!(f, "the setting {} {}", field, reason),
+    }
+  }
+}
+impl std::error::Error for Error {}
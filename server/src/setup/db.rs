@@ -1,17 +1,40 @@
-use mongodb::{Client, Database};
-use std::env;
+use crate::model::store::{MemoryStore, MongoStore, SqliteStore, Store};
+use crate::setup::{
+  config::{DatabaseConfig, RetryConfig, StoreBackend, StoreConfig},
+  db_events::DbEventHandler,
+  metrics::Metrics,
+  seed, sqlite,
+};
+use mongodb::{bson::doc, options::ClientOptions, Client, Database};
+use std::{
+  iter,
+  path::Path,
+  sync::Arc,
+  time::{Duration, Instant},
+};
+use tokio::time;
 use tracing;
 
-pub async fn db_client_connection() -> Result<Database, mongodb::error::Error> {
-  let database_uri = env::var("DATABASE_URI").unwrap();
-  let database_name = env::var("DATABASE_NAME").unwrap();
-  let client = Client::with_uri_str(&database_uri).await;
+/// The client of the database, kept by the server to shut it down once the requests are drained
+pub async fn db_client(config: &DatabaseConfig, metrics: Arc<Metrics>) -> Result<Client, mongodb::error::Error> {
+  let client = ClientOptions::parse(&config.uri).await.and_then(|mut options| {
+    let slow_query_threshold = config.slow_query_threshold_ms.map(Duration::from_millis);
+    let event_handler = Arc::new(DbEventHandler::new(metrics, slow_query_threshold));
+    options.command_event_handler = Some(event_handler.clone());
+    options.cmap_event_handler = Some(event_handler);
+    options.app_name = Some("rust_web_server".to_string());
+    options.max_pool_size = config.max_pool_size;
+    options.min_pool_size = config.min_pool_size;
+    options.connect_timeout = Some(Duration::from_millis(config.connect_timeout_ms));
+    options.server_selection_timeout = Some(Duration::from_millis(config.server_selection_timeout_ms));
+    Client::with_options(options)
+  });
 
   match client {
     Ok(client) => {
-      tracing::info!("DB connection stablished with success");
-      let db = client.database(database_name.as_str());
-      Ok(db)
+      // the driver connects lazily, `wait_for_database` tells whether the database is reachable
+      tracing::info!("DB client created with success");
+      Ok(client)
     }
     Err(err) => {
       tracing::error!("DB connection fails with the error\n{}", err);
@@ -19,3 +42,105 @@ pub async fn db_client_connection() -> Result<Database, mongodb::error::Error> {
     }
   }
 }
+
+pub async fn ping(db: &Database, timeout: Duration) -> Result<Duration, String> {
+  let started_at = Instant::now();
+  let result = time::timeout(timeout, db.run_command(doc! { "ping": 1 }, None)).await;
+
+  match result {
+    Ok(Ok(_)) => Ok(started_at.elapsed()),
+    Ok(Err(err)) => Err(err.to_string()),
+    Err(_) => Err(format!("no response after {}ms", timeout.as_millis())),
+  }
+}
+
+/// Pings the database until it answers, waiting longer after each failed attempt. Gives up after `max_attempts` when
+/// it is set, otherwise keeps trying.
+pub async fn wait_for_database(
+  db: &Database,
+  config: &DatabaseConfig,
+  max_attempts: Option<u32>,
+) -> Result<(), String> {
+  let timeout = Duration::from_millis(config.server_selection_timeout_ms);
+  let mut delays = backoff_delays(&config.retry);
+  let mut attempt = 1;
+
+  loop {
+    match ping(db, timeout).await {
+      Ok(latency) => {
+        tracing::info!(attempt, latency_ms = latency.as_millis() as u64, "Database reachable");
+        return Ok(());
+      }
+      Err(err) if matches!(max_attempts, Some(max_attempts) if attempt >= max_attempts) => {
+        tracing::error!(attempt, error = %err, "Database unreachable, giving up");
+        return Err(err);
+      }
+      Err(err) => {
+        let delay = delays.next().unwrap_or_default();
+        tracing::warn!(
+          attempt,
+          retry_in_ms = delay.as_millis() as u64,
+          error = %err,
+          "Database unreachable, retrying"
+        );
+        time::sleep(delay).await;
+        attempt += 1;
+      }
+    }
+  }
+}
+
+pub fn backoff_delays(config: &RetryConfig) -> impl Iterator<Item = Duration> {
+  let max_delay = Duration::from_millis(config.max_delay_ms);
+  iter::successors(Some(Duration::from_millis(config.initial_delay_ms)), move |delay| {
+    Some((*delay * 2).min(max_delay))
+  })
+}
+
+/// The store the routes read from, the memory backend and an empty sqlite database are filled with the seed files
+/// when they are configured
+pub fn open_store(config: &StoreConfig, db: &Database) -> Result<Arc<dyn Store>, String> {
+  match config.backend {
+    StoreBackend::Mongo => Ok(Arc::new(MongoStore::new(db.clone()))),
+    StoreBackend::Memory => {
+      let data = match &config.seed_dir {
+        Some(dir) => load_seed(dir)?,
+        None => seed::SeedData::default(),
+      };
+      tracing::info!(
+        users = data.users.len(),
+        organizations = data.organizations.len(),
+        repositories = data.repositories.len(),
+        "In-memory store loaded"
+      );
+      Ok(Arc::new(MemoryStore::new(
+        data.users,
+        data.organizations,
+        data.repositories,
+      )))
+    }
+    StoreBackend::Sqlite => {
+      let mut connection = sqlite::open(Path::new(&config.sqlite_path)).map_err(|err| err.to_string())?;
+      if let Some(dir) = &config.seed_dir {
+        if sqlite::is_empty(&connection).map_err(|err| err.to_string())? {
+          let data = load_seed(dir)?;
+          sqlite::import(&mut connection, &data, false).map_err(|err| err.to_string())?;
+        }
+      }
+      tracing::info!(path = %config.sqlite_path, "SQLite store opened");
+      Ok(Arc::new(SqliteStore::new(connection)))
+    }
+  }
+}
+
+/// The seed files of a store, refused when a document doesn't match its model or references a missing one as the
+/// stores expect valid documents
+fn load_seed(dir: &str) -> Result<seed::SeedData, String> {
+  let data = seed::load_dir(Path::new(dir)).map_err(|err| err.to_string())?;
+  let issues = seed::validate(&data);
+  if !issues.is_empty() {
+    return Err(format!("Invalid seed files in {}\n{}", dir, issues.join("\n")));
+  }
+
+  Ok(data)
+}
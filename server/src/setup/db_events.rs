@@ -0,0 +1,172 @@
+use crate::setup::metrics::Metrics;
+use mongodb::{
+  bson::{Bson, Document},
+  event::{
+    cmap::{
+      CmapEventHandler, ConnectionCheckedInEvent, ConnectionCheckedOutEvent, ConnectionClosedEvent,
+      ConnectionCreatedEvent,
+    },
+    command::{CommandEventHandler, CommandFailedEvent, CommandStartedEvent, CommandSucceededEvent},
+  },
+};
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+use tracing::{field, Span};
+
+/// Fields added by the driver to every command, they carry session and cluster data instead of the query shape
+const DRIVER_FIELDS: &[&str] = &[
+  "lsid",
+  "$clusterTime",
+  "$db",
+  "$readPreference",
+  "txnNumber",
+  "signature",
+];
+
+struct RunningCommand {
+  collection: String,
+  span: Span,
+}
+
+/// Feeds the MongoDB driver command and connection pool events into the metrics registry and turns each command
+/// into a child span of the request being handled
+pub struct DbEventHandler {
+  metrics: Arc<Metrics>,
+  slow_query_threshold: Option<Duration>,
+  commands: Mutex<HashMap<i32, RunningCommand>>,
+}
+
+impl DbEventHandler {
+  pub fn new(metrics: Arc<Metrics>, slow_query_threshold: Option<Duration>) -> Self {
+    Self {
+      metrics,
+      slow_query_threshold,
+      commands: Mutex::new(HashMap::new()),
+    }
+  }
+
+  fn finish(&self, request_id: i32, command_name: &str, duration: Duration, outcome: &str) -> Option<Span> {
+    let (collection, span) = match self.commands.lock().unwrap().remove(&request_id) {
+      Some(RunningCommand { collection, span }) => (collection, Some(span)),
+      None => ("none".to_string(), None),
+    };
+
+    self
+      .metrics
+      .db_command_duration_seconds
+      .with_label_values(&[&collection, command_name, outcome])
+      .observe(duration.as_secs_f64());
+
+    let span = span?;
+    span.record("db.duration_ms", duration.as_secs_f64() * 1000.0);
+    if matches!(self.slow_query_threshold, Some(threshold) if duration >= threshold) {
+      tracing::warn!(
+        parent: &span,
+        db.mongodb.collection = %collection,
+        db.operation = command_name,
+        db.duration_ms = duration.as_secs_f64() * 1000.0,
+        "slow database command"
+      );
+    }
+
+    Some(span)
+  }
+}
+
+impl CommandEventHandler for DbEventHandler {
+  // the driver emits the events from the task running the operation, so the current span is the request span
+  fn handle_command_started_event(&self, event: CommandStartedEvent) {
+    let collection = command_collection(&event.command_name, &event.command).to_string();
+    let span = tracing::info_span!(
+      parent: Span::current(),
+      "db.command",
+      otel.name = %format!("{} {}", event.command_name, collection),
+      otel.kind = "client",
+      otel.status_code = field::Empty,
+      db.system = "mongodb",
+      db.name = %event.db,
+      db.mongodb.collection = %collection,
+      db.operation = %event.command_name,
+      db.statement = %sanitize_command(&event.command),
+      db.duration_ms = field::Empty,
+      error.message = field::Empty,
+    );
+
+    self
+      .commands
+      .lock()
+      .unwrap()
+      .insert(event.request_id, RunningCommand { collection, span });
+  }
+
+  fn handle_command_succeeded_event(&self, event: CommandSucceededEvent) {
+    self.finish(event.request_id, &event.command_name, event.duration, "succeeded");
+  }
+
+  fn handle_command_failed_event(&self, event: CommandFailedEvent) {
+    if let Some(span) = self.finish(event.request_id, &event.command_name, event.duration, "failed") {
+      span.record("otel.status_code", "ERROR");
+      span.record("error.message", field::display(&event.failure));
+    }
+  }
+}
+
+impl CmapEventHandler for DbEventHandler {
+  fn handle_connection_created_event(&self, _event: ConnectionCreatedEvent) {
+    self.metrics.db_pool_connections_open.inc();
+  }
+
+  fn handle_connection_closed_event(&self, _event: ConnectionClosedEvent) {
+    self.metrics.db_pool_connections_open.dec();
+  }
+
+  fn handle_connection_checked_out_event(&self, _event: ConnectionCheckedOutEvent) {
+    self.metrics.db_pool_connections_in_use.inc();
+  }
+
+  fn handle_connection_checked_in_event(&self, _event: ConnectionCheckedInEvent) {
+    self.metrics.db_pool_connections_in_use.dec();
+  }
+}
+
+/// The collection is the value of the command key, e.g. `{ "aggregate": "users", ... }`, except for `getMore` whose
+/// key holds the cursor id
+fn command_collection<'a>(command_name: &str, command: &'a Document) -> &'a str {
+  let key = match command_name {
+    "getMore" => "collection",
+    _ => command_name,
+  };
+  command.get_str(key).unwrap_or("none")
+}
+
+/// Keeps the shape of a command, its keys, operators and pipeline stages, and replaces every literal with `"?"` so
+/// that logins, emails and ids never reach the tracing backend. Field paths like `"$followers._id"` and the value of
+/// the first key, the collection name, are kept as is.
+pub fn sanitize_command(command: &Document) -> Document {
+  command
+    .iter()
+    .enumerate()
+    .filter(|(_, (key, _))| !DRIVER_FIELDS.contains(&key.as_str()))
+    .map(|(index, (key, value))| match index {
+      0 => (key.clone(), value.clone()),
+      _ => (key.clone(), sanitize_value(value)),
+    })
+    .collect()
+}
+
+fn sanitize_value(value: &Bson) -> Bson {
+  match value {
+    Bson::Document(document) => Bson::Document(
+      document
+        .iter()
+        .map(|(key, value)| (key.clone(), sanitize_value(value)))
+        .collect(),
+    ),
+    Bson::Array(values) => Bson::Array(values.iter().map(sanitize_value).collect()),
+    Bson::String(path) if path.starts_with('$') => Bson::String(path.clone()),
+    _ => Bson::String("?".to_string()),
+  }
+}
@@ -0,0 +1,43 @@
+use crate::setup::db_events::sanitize_command;
+use mongodb::bson::doc;
+use pretty_assertions::assert_eq;
+
+#[test]
+fn should_replace_the_literals_of_a_pipeline() {
+  let command = doc! {
+    "aggregate": "users",
+    "pipeline": [
+      { "$match": { "login": "foo", "email": "foo@email.com" } },
+      { "$limit": 1 },
+      { "$addFields": { "viewerIsFollowing": { "$in": ["5f7b", "$followers._id"] } } },
+    ],
+    "cursor": {},
+  };
+
+  let expected = doc! {
+    "aggregate": "users",
+    "pipeline": [
+      { "$match": { "login": "?", "email": "?" } },
+      { "$limit": "?" },
+      { "$addFields": { "viewerIsFollowing": { "$in": ["?", "$followers._id"] } } },
+    ],
+    "cursor": {},
+  };
+  assert_eq!(sanitize_command(&command), expected);
+}
+
+#[test]
+fn should_remove_the_fields_added_by_the_driver() {
+  let command = doc! {
+    "find": "organizations",
+    "filter": { "login": "acme" },
+    "lsid": { "id": "session" },
+    "$db": "database",
+  };
+
+  let expected = doc! {
+    "find": "organizations",
+    "filter": { "login": "?" },
+  };
+  assert_eq!(sanitize_command(&command), expected);
+}
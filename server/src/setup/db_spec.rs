@@ -1,9 +1,83 @@
-use crate::setup::db::db_client_connection;
+use crate::setup::{
+  config::{Config, DatabaseConfig, RetryConfig, StoreBackend, StoreConfig},
+  db::{backoff_delays, db_client, open_store, wait_for_database},
+  metrics::Metrics,
+};
+use mongodb::bson::oid::ObjectId;
 use pretty_assertions::assert_eq;
+use std::{sync::Arc, time::Duration};
 
 #[actix_rt::test]
 async fn should_connect_to_database_test_learning() {
-  let db = db_client_connection().await.unwrap();
+  let config = Config::load().unwrap();
+  let db = db_client(&config.database, Arc::new(Metrics::new()))
+    .await
+    .unwrap()
+    .database(config.database.name.as_str());
 
   assert_eq!(db.name(), "test_database");
 }
+
+#[actix_rt::test]
+async fn should_double_the_retry_delay_up_to_the_maximum() {
+  let retry = RetryConfig {
+    max_attempts: 5,
+    initial_delay_ms: 500,
+    max_delay_ms: 3000,
+  };
+  let delays: Vec<_> = backoff_delays(&retry).take(5).collect();
+
+  assert_eq!(
+    delays,
+    vec![
+      Duration::from_millis(500),
+      Duration::from_millis(1000),
+      Duration::from_millis(2000),
+      Duration::from_millis(3000),
+      Duration::from_millis(3000),
+    ]
+  );
+}
+
+#[actix_rt::test]
+async fn should_give_up_waiting_for_an_unreachable_database_after_the_max_attempts() {
+  let config = DatabaseConfig {
+    uri: "mongodb://127.0.0.1:1".to_string(),
+    server_selection_timeout_ms: 100,
+    retry: RetryConfig {
+      max_attempts: 2,
+      initial_delay_ms: 10,
+      max_delay_ms: 10,
+    },
+    ..DatabaseConfig::default()
+  };
+  let db = db_client(&config, Arc::new(Metrics::new()))
+    .await
+    .unwrap()
+    .database(config.name.as_str());
+  let result = wait_for_database(&db, &config, Some(config.retry.max_attempts)).await;
+
+  assert!(result.is_err());
+}
+
+#[actix_rt::test]
+async fn should_refuse_to_open_a_store_with_invalid_seed_files() {
+  let dir = std::env::temp_dir().join(format!("db_spec_{}", ObjectId::new()));
+  std::fs::create_dir_all(&dir).unwrap();
+  std::fs::write(dir.join("users.json"), "{ \"login\": \"foo\" }\n").unwrap();
+  let database = DatabaseConfig::default();
+  let db = db_client(&database, Arc::new(Metrics::new()))
+    .await
+    .unwrap()
+    .database(database.name.as_str());
+  let config = StoreConfig {
+    backend: StoreBackend::Memory,
+    seed_dir: Some(dir.display().to_string()),
+    ..StoreConfig::default()
+  };
+
+  let result = open_store(&config, &db);
+  std::fs::remove_dir_all(&dir).unwrap();
+
+  assert!(matches!(result, Err(message) if message.starts_with("Invalid seed files")));
+}
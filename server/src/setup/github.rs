@@ -0,0 +1,584 @@
+use crate::setup::seed::validate_document;
+use mongodb::{
+  bson::{doc, oid::ObjectId, Bson, DateTime, Document},
+  error::Error as ModelError,
+  options::{FindOptions, UpdateOptions},
+  Database,
+};
+use serde_json::{Map, Value};
+use std::{
+  collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+  fmt, fs, io,
+  path::Path,
+};
+use tokio_stream::StreamExt;
+use tracing;
+
+const LOOKUP_BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NodeType {
+  User,
+  Organization,
+  Repository,
+}
+impl NodeType {
+  fn from_typename(typename: &str) -> Option<Self> {
+    match typename {
+      "User" => Some(Self::User),
+      "Organization" => Some(Self::Organization),
+      "Repository" => Some(Self::Repository),
+      _ => None,
+    }
+  }
+
+  /// The type of a node read from its GitHub id, `U_kgDO...` or the legacy base64 of `04:User1`
+  fn from_node_id(id: &str) -> Option<Self> {
+    match id.split_once('_') {
+      Some(("U", _)) => return Some(Self::User),
+      Some(("O", _)) => return Some(Self::Organization),
+      Some(("R", _)) => return Some(Self::Repository),
+      _ => {}
+    }
+    let legacy = base64::decode(id)
+      .ok()
+      .and_then(|legacy| String::from_utf8(legacy).ok())?;
+    let (_, typed_id) = legacy.split_once(':')?;
+    Self::from_typename(typed_id.trim_end_matches(|c: char| c.is_ascii_digit()))
+  }
+
+  /// The type of the nodes of a root field, like `data.user`, or of a connection of a node
+  fn of_field(node_type: Option<Self>, field: &str) -> Option<Self> {
+    match (node_type, field) {
+      (None, "user" | "viewer") => Some(Self::User),
+      (None, "organization") => Some(Self::Organization),
+      (None, "repository") => Some(Self::Repository),
+      (Some(_), "followers" | "following" | "membersWithRole" | "stargazers") => Some(Self::User),
+      (Some(_), "organizations") => Some(Self::Organization),
+      (Some(_), "starredRepositories" | "repositories") => Some(Self::Repository),
+      _ => None,
+    }
+  }
+
+  pub fn collection(&self) -> &'static str {
+    match self {
+      Self::User => "users",
+      Self::Organization => "organizations",
+      Self::Repository => "repositories",
+    }
+  }
+}
+
+/// A node of the GitHub graph with its fields renamed to the ones stored, the same node found in several responses
+/// has the fields of all of them
+#[derive(Debug, PartialEq)]
+pub struct Node {
+  pub node_type: NodeType,
+  pub fields: Document,
+}
+
+/// The nodes and relations found in GitHub GraphQL responses, identified by their GitHub node ids
+#[derive(Debug, Default, PartialEq)]
+pub struct GithubDump {
+  pub nodes: BTreeMap<String, Node>,
+  /// `(follower, followed)`
+  pub follows: BTreeSet<(String, String)>,
+  /// `(organization, user)`
+  pub memberships: BTreeSet<(String, String)>,
+  /// `(user, repository)`
+  pub stars: BTreeSet<(String, String)>,
+  /// repository to its owner
+  pub owners: BTreeMap<String, String>,
+}
+
+#[derive(Debug)]
+pub enum GithubError {
+  Io(String, io::Error),
+  Parse(String, String),
+  Database(ModelError),
+}
+impl fmt::Display for GithubError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      GithubError::Io(path, err) => write!(f, "{}: {}", path, err),
+      GithubError::Parse(path, message) => write!(f, "{}: {}", path, message),
+      GithubError::Database(err) => write!(f, "{}", err),
+    }
+  }
+}
+impl From<ModelError> for GithubError {
+  fn from(err: ModelError) -> Self {
+    GithubError::Database(err)
+  }
+}
+
+/// Reads every `.json` file of `dir`, each one a saved response like `{ "data": { "user": { ... } } }`
+pub fn load_dir(dir: &Path) -> Result<GithubDump, GithubError> {
+  let entries = fs::read_dir(dir).map_err(|err| GithubError::Io(dir.display().to_string(), err))?;
+  let mut paths = entries
+    .map(|entry| entry.map(|entry| entry.path()))
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|err| GithubError::Io(dir.display().to_string(), err))?;
+  paths.retain(|path| path.extension().and_then(|extension| extension.to_str()) == Some("json"));
+  paths.sort();
+
+  let mut dump = GithubDump::default();
+  for path in paths {
+    let file = path.display().to_string();
+    let content = fs::read_to_string(&path).map_err(|err| GithubError::Io(file.clone(), err))?;
+    let response: Value =
+      serde_json::from_str(&content).map_err(|err| GithubError::Parse(file.clone(), err.to_string()))?;
+    if let Some(errors) = response.get("errors") {
+      tracing::warn!(file = %file, errors = %errors, "The response has errors, reading its data anyway");
+    }
+    if dump.add_response(&response) == 0 {
+      tracing::warn!(file = %file, "The response has no user, organization or repository with an id");
+    }
+  }
+
+  Ok(dump)
+}
+
+impl GithubDump {
+  /// Collects the nodes of a response and returns how many it has. A node is an object with `id` of a `User`, an
+  /// `Organization` or a `Repository`, told by its `__typename`, the field or connection it is read from, or its id.
+  /// The relations are read from the connections of a node, as `nodes` or `edges { node }`.
+  pub fn add_response(&mut self, response: &Value) -> usize {
+    let mut found = BTreeSet::new();
+    self.visit(response, None, &mut found);
+    found.len()
+  }
+
+  fn visit(&mut self, value: &Value, expected: Option<NodeType>, found: &mut BTreeSet<String>) -> Option<String> {
+    let object = match value {
+      Value::Array(items) => {
+        for item in items {
+          self.visit(item, expected, found);
+        }
+        return None;
+      }
+      Value::Object(object) => object,
+      _ => return None,
+    };
+
+    let node = node_id_and_type(object, expected);
+    for (field, child) in object {
+      let (id, node_type) = match &node {
+        Some((id, node_type)) => (id.clone(), *node_type),
+        None => {
+          self.visit(child, NodeType::of_field(None, field), found);
+          continue;
+        }
+      };
+
+      match (node_type, field.as_str()) {
+        (NodeType::Repository, "owner") => {
+          if let Some(owner) = self.visit(child, None, found) {
+            self.owners.insert(id, owner);
+          }
+        }
+        (_, field) if is_relation(node_type, field) => {
+          for item in connection_nodes(child) {
+            if let Some(other) = self.visit(item, NodeType::of_field(Some(node_type), field), found) {
+              self.add_relation(node_type, field, &id, other);
+            }
+          }
+        }
+        _ => {
+          self.visit(child, None, found);
+        }
+      }
+    }
+
+    let (id, node_type) = node?;
+    found.insert(id.clone());
+    let fields = node_fields(node_type, object);
+    let node = self.nodes.entry(id.clone()).or_insert(Node {
+      node_type,
+      fields: Document::new(),
+    });
+    node.fields.extend(fields);
+
+    Some(id)
+  }
+
+  fn add_relation(&mut self, node_type: NodeType, field: &str, id: &str, other: String) {
+    let id = id.to_string();
+    match (node_type, field) {
+      (NodeType::User, "followers") => self.follows.insert((other, id)),
+      (NodeType::User, "following") => self.follows.insert((id, other)),
+      (NodeType::User, "organizations") => self.memberships.insert((other, id)),
+      (NodeType::User, "starredRepositories") => self.stars.insert((id, other)),
+      (NodeType::Organization, "membersWithRole") => self.memberships.insert((id, other)),
+      (NodeType::Repository, "stargazers") => self.stars.insert((other, id)),
+      (_, "repositories") => self.owners.insert(other, id).is_none(),
+      _ => false,
+    };
+  }
+
+  /// The nodes of a type with their GitHub node ids
+  fn ids_of(&self, node_type: NodeType) -> impl Iterator<Item = (&String, &Node)> {
+    self.nodes.iter().filter(move |(_, node)| node.node_type == node_type)
+  }
+
+  /// The other side of the relations of `id` whose node is in the dump, `pairs` are read from left to right when
+  /// `forward` is set
+  fn related<'a>(
+    &'a self,
+    pairs: &'a BTreeSet<(String, String)>,
+    id: &str,
+    forward: bool,
+    node_type: NodeType,
+  ) -> Vec<&'a String> {
+    pairs
+      .iter()
+      .filter_map(|(from, to)| match forward {
+        true if from == id => Some(to),
+        false if to == id => Some(from),
+        _ => None,
+      })
+      .filter(|other| self.has(other, node_type))
+      .collect()
+  }
+
+  fn has(&self, id: &str, node_type: NodeType) -> bool {
+    matches!(self.nodes.get(id), Some(node) if node.node_type == node_type)
+  }
+}
+
+/// The `__typename` of a node is used when the query asks for it, the type of the field it is read from otherwise
+fn node_id_and_type(object: &Map<String, Value>, expected: Option<NodeType>) -> Option<(String, NodeType)> {
+  let id = object.get("id").and_then(Value::as_str)?;
+  let node_type = match object.get("__typename").and_then(Value::as_str) {
+    Some(typename) => NodeType::from_typename(typename)?,
+    None => expected.or_else(|| NodeType::from_node_id(id))?,
+  };
+  Some((id.to_string(), node_type))
+}
+
+fn is_relation(node_type: NodeType, field: &str) -> bool {
+  matches!(
+    (node_type, field),
+    (
+      NodeType::User,
+      "followers" | "following" | "organizations" | "starredRepositories" | "repositories"
+    ) | (NodeType::Organization, "membersWithRole" | "repositories")
+      | (NodeType::Repository, "stargazers")
+  )
+}
+
+/// The items of a connection, read from `nodes` or from `edges { node }`
+fn connection_nodes(connection: &Value) -> Vec<&Value> {
+  if let Some(nodes) = connection.get("nodes").and_then(Value::as_array) {
+    return nodes.iter().collect();
+  }
+  match connection.get("edges").and_then(Value::as_array) {
+    Some(edges) => edges.iter().filter_map(|edge| edge.get("node")).collect(),
+    None => vec![],
+  }
+}
+
+/// The fields of a node that are stored, the null and empty values are left out so that they don't override what
+/// another response had
+fn node_fields(node_type: NodeType, object: &Map<String, Value>) -> Document {
+  let names: &[&str] = match node_type {
+    NodeType::User => &[
+      "login",
+      "name",
+      "email",
+      "bio",
+      "avatarUrl",
+      "url",
+      "websiteUrl",
+      "location",
+    ],
+    NodeType::Organization => &[
+      "login",
+      "name",
+      "description",
+      "avatarUrl",
+      "url",
+      "websiteUrl",
+      "location",
+    ],
+    NodeType::Repository => &["name", "description", "url"],
+  };
+  let mut fields: Document = names
+    .iter()
+    .filter_map(|name| match object.get(*name) {
+      Some(Value::String(value)) if !value.is_empty() => Some((name.to_string(), Bson::String(value.clone()))),
+      _ => None,
+    })
+    .collect();
+
+  if fields.contains_key("email") {
+    // the API only returns the public email of a user
+    fields.insert("emailVisibility", "public");
+  }
+  if let Some(created_at) = object.get("createdAt").and_then(Value::as_str) {
+    if let Ok(created_at) = DateTime::parse_rfc3339_str(created_at) {
+      fields.insert("createdAt", created_at);
+    }
+  }
+  if let Some(fork_count) = object.get("forkCount").and_then(Value::as_f64) {
+    fields.insert("forkCount", fork_count);
+  }
+  if let Some(name) = object
+    .get("licenseInfo")
+    .and_then(|license| license.get("name"))
+    .and_then(Value::as_str)
+  {
+    fields.insert("licenseInfo", doc! { "name": name });
+  }
+  if let Some(language) = object.get("primaryLanguage") {
+    if let (Some(name), Some(color)) = (
+      language.get("name").and_then(Value::as_str),
+      language.get("color").and_then(Value::as_str),
+    ) {
+      fields.insert("primaryLanguage", doc! { "name": name, "color": color });
+    }
+  }
+
+  fields
+}
+
+/// The `ObjectId` of a GitHub node that isn't stored yet, always the same for the same node
+pub fn object_id_of(node_id: &str) -> ObjectId {
+  let first = fnv1a(node_id.as_bytes(), 0xcbf2_9ce4_8422_2325);
+  let second = fnv1a(node_id.as_bytes(), first);
+  let mut bytes = [0; 12];
+  bytes[..8].copy_from_slice(&first.to_be_bytes());
+  bytes[8..].copy_from_slice(&second.to_be_bytes()[..4]);
+  ObjectId::from_bytes(bytes)
+}
+
+fn fnv1a(bytes: &[u8], offset: u64) -> u64 {
+  bytes.iter().fold(offset, |hash, byte| {
+    (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+  })
+}
+
+/// The update of a document: the fields found in the dump are set, the required ones that are missing are only set
+/// when the document is created and the relations are added to the ones already stored
+#[derive(Debug, PartialEq)]
+pub struct Upsert {
+  pub collection: &'static str,
+  pub id: ObjectId,
+  pub set: Document,
+  pub set_on_insert: Document,
+  pub add_to_set: Document,
+}
+impl Upsert {
+  /// The document created when there is none with this id
+  pub fn inserted_document(&self) -> Document {
+    let mut document = doc! { "_id": self.id };
+    document.extend(self.set_on_insert.clone());
+    document.extend(self.set.clone());
+    document.extend(self.add_to_set.clone());
+    document
+  }
+
+  fn update(&self) -> Document {
+    let mut update = doc! { "$set": &self.set };
+    if !self.set_on_insert.is_empty() {
+      update.insert("$setOnInsert", &self.set_on_insert);
+    }
+    let add_to_set: Document = self
+      .add_to_set
+      .iter()
+      .map(|(field, references)| (field.clone(), Bson::Document(doc! { "$each": references })))
+      .collect();
+    if !add_to_set.is_empty() {
+      update.insert("$addToSet", add_to_set);
+    }
+    update
+  }
+}
+
+/// Builds the updates of every node, `ids` has the ids of the nodes already stored and the others get `object_id_of`.
+/// The repositories whose owner isn't in the dump can't be created, they are only updated when already stored.
+pub fn upserts(dump: &GithubDump, ids: &HashMap<String, ObjectId>) -> Vec<Upsert> {
+  let id_of = |node_id: &str| ids.get(node_id).copied().unwrap_or_else(|| object_id_of(node_id));
+  let references = |node_ids: Vec<&String>, reference: Option<&str>| -> Vec<Document> {
+    node_ids
+      .into_iter()
+      .map(|node_id| match reference {
+        Some(reference) => doc! { "_id": id_of(node_id), "ref": reference },
+        None => doc! { "_id": id_of(node_id) },
+      })
+      .collect()
+  };
+  let owner_of = |repository: &str| {
+    let owner = dump.owners.get(repository)?;
+    let node = dump
+      .nodes
+      .get(owner)
+      .filter(|node| node.node_type != NodeType::Repository)?;
+    Some(doc! { "_id": id_of(owner), "ref": node.node_type.collection() })
+  };
+  let mut upserts = vec![];
+
+  for (node_id, node) in dump.ids_of(NodeType::User) {
+    let login = node.fields.get_str("login").unwrap_or_default();
+    let mut add_to_set = Document::new();
+    for (field, pairs, forward, node_type) in [
+      ("followers", &dump.follows, false, NodeType::User),
+      ("following", &dump.follows, true, NodeType::User),
+      ("organizations", &dump.memberships, false, NodeType::Organization),
+      ("starredRepositories", &dump.stars, true, NodeType::Repository),
+    ] {
+      let others = dump.related(pairs, node_id, forward, node_type);
+      if !others.is_empty() {
+        add_to_set.insert(field, references(others, None));
+      }
+    }
+    upserts.push(Upsert {
+      collection: "users",
+      id: id_of(node_id),
+      set: node_set(node_id, node, "User"),
+      set_on_insert: fallbacks(&node.fields, login),
+      add_to_set,
+    });
+  }
+
+  for (node_id, node) in dump.ids_of(NodeType::Organization) {
+    let login = node.fields.get_str("login").unwrap_or_default();
+    let people = dump.related(&dump.memberships, node_id, true, NodeType::User);
+    let mut add_to_set = Document::new();
+    if !people.is_empty() {
+      add_to_set.insert("people", references(people, Some("users")));
+    }
+    upserts.push(Upsert {
+      collection: "organizations",
+      id: id_of(node_id),
+      set: node_set(node_id, node, "Organization"),
+      set_on_insert: fallbacks(&node.fields, login),
+      add_to_set,
+    });
+  }
+
+  for (node_id, node) in dump.ids_of(NodeType::Repository) {
+    let mut set = node_set(node_id, node, "Repository");
+    set.remove("__typename");
+    match owner_of(node_id) {
+      Some(owner) => {
+        set.insert("owner", owner);
+      }
+      None if ids.contains_key(node_id) => {}
+      None => {
+        tracing::warn!(node_id = %node_id, "Repository without owner left out");
+        continue;
+      }
+    }
+    let mut set_on_insert = Document::new();
+    if !set.contains_key("forkCount") {
+      set_on_insert.insert("forkCount", 0.0);
+    }
+    upserts.push(Upsert {
+      collection: "repositories",
+      id: id_of(node_id),
+      set,
+      set_on_insert,
+      add_to_set: Document::new(),
+    });
+  }
+
+  upserts
+}
+
+fn node_set(node_id: &str, node: &Node, typename: &str) -> Document {
+  let mut set = node.fields.clone();
+  set.insert("githubId", node_id);
+  set.insert("__typename", typename);
+  set
+}
+
+/// The fields required by the models that a thin node, like the follower of a user, usually lacks
+fn fallbacks(fields: &Document, login: &str) -> Document {
+  let mut fallbacks = Document::new();
+  if !fields.contains_key("url") {
+    fallbacks.insert("url", format!("https://github.com/{}", login));
+  }
+  if !fields.contains_key("avatarUrl") {
+    fallbacks.insert("avatarUrl", format!("https://github.com/{}.png", login));
+  }
+  fallbacks
+}
+
+/// Finds the nodes already stored, by `githubId` or else by login, so that they keep their `_id`
+pub async fn stored_ids(db: &Database, dump: &GithubDump) -> Result<HashMap<String, ObjectId>, GithubError> {
+  let mut ids = HashMap::new();
+
+  for node_type in [NodeType::User, NodeType::Organization, NodeType::Repository] {
+    let nodes: Vec<(&String, &Node)> = dump.ids_of(node_type).collect();
+    let collection = db.collection::<Document>(node_type.collection());
+    for batch in nodes.chunks(LOOKUP_BATCH_SIZE) {
+      let node_ids: Vec<&String> = batch.iter().map(|(node_id, _)| *node_id).collect();
+      let mut by_login: HashMap<&str, &String> = HashMap::new();
+      for (node_id, node) in batch {
+        if let Ok(login) = node.fields.get_str("login") {
+          by_login.insert(login, node_id);
+        }
+      }
+      let logins: Vec<&&str> = by_login.keys().collect();
+      let filter = doc! { "$or": [{ "githubId": { "$in": node_ids } }, { "login": { "$in": logins } }] };
+      let options = FindOptions::builder()
+        .projection(doc! { "_id": 1, "githubId": 1, "login": 1 })
+        .build();
+
+      let mut cursor = collection.find(filter, options).await?;
+      while let Some(document) = cursor.next().await {
+        let document = document?;
+        let id = match document.get_object_id("_id") {
+          Ok(id) => id,
+          Err(_) => continue,
+        };
+        let node_id = match document.get_str("githubId") {
+          Ok(node_id) => Some(node_id.to_string()),
+          Err(_) => document
+            .get_str("login")
+            .ok()
+            .and_then(|login| by_login.get(login))
+            .map(|node_id| node_id.to_string()),
+        };
+        if let Some(node_id) = node_id {
+          ids.insert(node_id, id);
+        }
+      }
+    }
+  }
+
+  Ok(ids)
+}
+
+/// Checks the documents that would be created against their models, returns the problems found
+pub fn validate(upserts: &[Upsert], ids: &HashMap<String, ObjectId>) -> Vec<String> {
+  let stored: HashSet<&ObjectId> = ids.values().collect();
+  upserts
+    .iter()
+    .filter(|upsert| !stored.contains(&upsert.id))
+    .filter_map(|upsert| validate_document(upsert.collection, &upsert.inserted_document()))
+    .collect()
+}
+
+/// Applies the updates, returns the number of created and updated documents by collection
+pub async fn apply(db: &Database, upserts: &[Upsert]) -> Result<BTreeMap<&'static str, (u64, u64)>, GithubError> {
+  let mut counts: BTreeMap<&'static str, (u64, u64)> = BTreeMap::new();
+  for upsert in upserts {
+    let options = UpdateOptions::builder().upsert(true).build();
+    let result = db
+      .collection::<Document>(upsert.collection)
+      .update_one(doc! { "_id": upsert.id }, upsert.update(), options)
+      .await?;
+    let (created, updated) = counts.entry(upsert.collection).or_default();
+    match result.upserted_id {
+      Some(_) => *created += 1,
+      None => *updated += 1,
+    }
+  }
+
+  for (collection, (created, updated)) in &counts {
+    tracing::info!(collection, created, updated, "GitHub dump imported");
+  }
+  Ok(counts)
+}
@@ -0,0 +1,261 @@
+use crate::setup::github::{load_dir, object_id_of, upserts, validate, GithubDump, GithubError, NodeType};
+use mongodb::bson::{doc, oid::ObjectId, DateTime};
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+use std::{collections::HashMap, fs};
+
+fn user_response() -> Value {
+  json!({
+    "data": {
+      "user": {
+        "__typename": "User",
+        "id": "U_foo",
+        "login": "foo",
+        "name": "Foo",
+        "email": "",
+        "bio": null,
+        "avatarUrl": "https://avatars.githubusercontent.com/u/1",
+        "url": "https://github.com/foo",
+        "createdAt": "2015-03-01T10:00:00Z",
+        "followers": {
+          "totalCount": 1,
+          "nodes": [{ "__typename": "User", "id": "U_bar", "login": "bar" }]
+        },
+        "organizations": {
+          "edges": [{ "node": { "__typename": "Organization", "id": "O_acme", "login": "acme" } }]
+        },
+        "starredRepositories": {
+          "nodes": [{ "__typename": "Repository", "id": "R_tux", "name": "tux" }]
+        },
+        "repositories": {
+          "nodes": [{
+            "__typename": "Repository",
+            "id": "R_dot",
+            "name": "dot",
+            "forkCount": 3,
+            "primaryLanguage": { "name": "Rust", "color": "#dea584" },
+            "licenseInfo": { "name": "MIT License" }
+          }]
+        }
+      }
+    }
+  })
+}
+
+fn organization_response() -> Value {
+  json!({
+    "data": {
+      "organization": {
+        "__typename": "Organization",
+        "id": "O_acme",
+        "login": "acme",
+        "description": "Acme Corporation",
+        "avatarUrl": "https://avatars.githubusercontent.com/u/3",
+        "url": "https://github.com/acme",
+        "membersWithRole": {
+          "nodes": [{ "__typename": "User", "id": "U_bar", "login": "bar", "email": "bar@email.com" }]
+        },
+        "repositories": {
+          "nodes": [{ "__typename": "Repository", "id": "R_tux", "name": "tux", "forkCount": 10 }]
+        }
+      }
+    }
+  })
+}
+
+fn dump() -> GithubDump {
+  let mut dump = GithubDump::default();
+  dump.add_response(&user_response());
+  dump.add_response(&organization_response());
+  dump
+}
+
+#[test]
+fn should_read_the_nodes_and_relations_of_the_responses() {
+  let dump = dump();
+
+  assert_eq!(
+    dump.nodes.keys().collect::<Vec<_>>(),
+    vec!["O_acme", "R_dot", "R_tux", "U_bar", "U_foo"]
+  );
+  assert_eq!(dump.nodes["O_acme"].node_type, NodeType::Organization);
+  assert_eq!(
+    dump.nodes["U_foo"].fields,
+    doc! {
+      "login": "foo",
+      "name": "Foo",
+      "avatarUrl": "https://avatars.githubusercontent.com/u/1",
+      "url": "https://github.com/foo",
+      "createdAt": DateTime::parse_rfc3339_str("2015-03-01T10:00:00Z").unwrap(),
+    }
+  );
+  assert_eq!(
+    dump.nodes["O_acme"].fields.get_str("description"),
+    Ok("Acme Corporation")
+  );
+  assert_eq!(
+    dump.follows.iter().cloned().collect::<Vec<_>>(),
+    vec![("U_bar".to_string(), "U_foo".to_string())]
+  );
+  assert_eq!(
+    dump.memberships.iter().cloned().collect::<Vec<_>>(),
+    vec![
+      ("O_acme".to_string(), "U_bar".to_string()),
+      ("O_acme".to_string(), "U_foo".to_string())
+    ]
+  );
+  assert_eq!(
+    dump.stars.iter().cloned().collect::<Vec<_>>(),
+    vec![("U_foo".to_string(), "R_tux".to_string())]
+  );
+  assert_eq!(dump.owners["R_dot"], "U_foo");
+  assert_eq!(dump.owners["R_tux"], "O_acme");
+}
+
+#[test]
+fn should_infer_the_type_of_the_nodes_without_typename() {
+  let mut dump = GithubDump::default();
+  let found = dump.add_response(&json!({
+    "data": {
+      "user": {
+        "id": "MDQ6VXNlcjE=",
+        "login": "foo",
+        "followers": { "nodes": [{ "id": "MDQ6VXNlcjI=", "login": "bar" }] },
+        "starredRepositories": {
+          "edges": [{
+            "node": { "id": "R_tux", "name": "tux", "owner": { "id": "MTI6T3JnYW5pemF0aW9uMw==", "login": "acme" } }
+          }]
+        }
+      }
+    }
+  }));
+  let nothing = dump.add_response(&json!({ "data": { "search": { "nodes": [{ "name": "tux" }] } } }));
+
+  assert_eq!(found, 4);
+  assert_eq!(nothing, 0);
+  assert_eq!(dump.nodes["MDQ6VXNlcjE="].node_type, NodeType::User);
+  assert_eq!(dump.nodes["MDQ6VXNlcjI="].node_type, NodeType::User);
+  assert_eq!(dump.nodes["R_tux"].node_type, NodeType::Repository);
+  assert_eq!(dump.nodes["MTI6T3JnYW5pemF0aW9uMw=="].node_type, NodeType::Organization);
+  assert_eq!(
+    dump.follows.iter().cloned().collect::<Vec<_>>(),
+    vec![("MDQ6VXNlcjI=".to_string(), "MDQ6VXNlcjE=".to_string())]
+  );
+  assert_eq!(dump.owners["R_tux"], "MTI6T3JnYW5pemF0aW9uMw==");
+}
+
+#[test]
+fn should_map_the_node_ids_to_the_same_object_ids() {
+  assert_eq!(object_id_of("U_foo"), object_id_of("U_foo"));
+  assert_ne!(object_id_of("U_foo"), object_id_of("U_bar"));
+}
+
+#[test]
+fn should_build_both_sides_of_the_relations() {
+  let dump = dump();
+  let upserts = upserts(&dump, &HashMap::new());
+  let find = |id: &str| upserts.iter().find(|upsert| upsert.id == object_id_of(id)).unwrap();
+
+  assert_eq!(
+    find("U_foo").add_to_set,
+    doc! {
+      "followers": [{ "_id": object_id_of("U_bar") }],
+      "organizations": [{ "_id": object_id_of("O_acme") }],
+      "starredRepositories": [{ "_id": object_id_of("R_tux") }],
+    }
+  );
+  assert_eq!(
+    find("U_bar").add_to_set,
+    doc! {
+      "following": [{ "_id": object_id_of("U_foo") }],
+      "organizations": [{ "_id": object_id_of("O_acme") }],
+    }
+  );
+  assert_eq!(
+    find("O_acme").add_to_set,
+    doc! {
+      "people": [
+        { "_id": object_id_of("U_bar"), "ref": "users" },
+        { "_id": object_id_of("U_foo"), "ref": "users" },
+      ],
+    }
+  );
+  assert_eq!(
+    find("R_tux").set.get_document("owner").unwrap(),
+    &doc! { "_id": object_id_of("O_acme"), "ref": "organizations" }
+  );
+  assert_eq!(find("R_dot").set.get_f64("forkCount"), Ok(3.0));
+}
+
+#[test]
+fn should_fill_the_required_fields_missing_from_the_dump() {
+  let dump = dump();
+  let upserts = upserts(&dump, &HashMap::new());
+  let bar = upserts
+    .iter()
+    .find(|upsert| upsert.id == object_id_of("U_bar"))
+    .unwrap();
+
+  assert_eq!(
+    bar.inserted_document(),
+    doc! {
+      "_id": object_id_of("U_bar"),
+      "url": "https://github.com/bar",
+      "avatarUrl": "https://github.com/bar.png",
+      "login": "bar",
+      "email": "bar@email.com",
+      "emailVisibility": "public",
+      "githubId": "U_bar",
+      "__typename": "User",
+      "following": [{ "_id": object_id_of("U_foo") }],
+      "organizations": [{ "_id": object_id_of("O_acme") }],
+    }
+  );
+  assert_eq!(validate(&upserts, &HashMap::new()), Vec::<String>::new());
+}
+
+#[test]
+fn should_keep_the_ids_of_the_stored_documents() {
+  let dump = dump();
+  let stored = ObjectId::new();
+  let ids = HashMap::from([("U_bar".to_string(), stored)]);
+
+  let upserts = upserts(&dump, &ids);
+  let foo = upserts
+    .iter()
+    .find(|upsert| upsert.id == object_id_of("U_foo"))
+    .unwrap();
+
+  assert!(upserts.iter().any(|upsert| upsert.id == stored));
+  assert_eq!(
+    foo.add_to_set.get_array("followers").unwrap(),
+    &vec![doc! { "_id": stored }.into()]
+  );
+}
+
+#[test]
+fn should_leave_out_the_repositories_without_owner() {
+  let mut dump = GithubDump::default();
+  dump.add_response(&user_response());
+
+  let upserts = upserts(&dump, &HashMap::new());
+
+  assert!(upserts.iter().all(|upsert| upsert.id != object_id_of("R_tux")));
+  assert!(upserts.iter().any(|upsert| upsert.id == object_id_of("R_dot")));
+}
+
+#[test]
+fn should_load_the_json_files_of_a_dir() {
+  let dir = std::env::temp_dir().join(format!("github_spec_{}", ObjectId::new()));
+  fs::create_dir_all(&dir).unwrap();
+  fs::write(dir.join("foo.json"), user_response().to_string()).unwrap();
+  fs::write(dir.join("acme.json"), organization_response().to_string()).unwrap();
+  fs::write(dir.join("notes.txt"), "not a response").unwrap();
+
+  let loaded = load_dir(&dir).unwrap();
+  fs::write(dir.join("broken.json"), "{").unwrap();
+  let broken = load_dir(&dir);
+
+  assert_eq!(loaded, dump());
+  assert!(matches!(broken, Err(GithubError::Parse(file, _)) if file.ends_with("broken.json")));
+}
@@ -0,0 +1,299 @@
+use crate::model::utils::referenced_ids;
+use crate::setup::seed::SeedData;
+use mongodb::{
+  bson::{doc, oid::ObjectId, Document},
+  error::Error as ModelError,
+  options::FindOptions,
+  Database,
+};
+use std::{collections::HashMap, fmt};
+use tokio_stream::StreamExt;
+use tracing;
+
+/// A list of references like `[{ "_id": ObjectId }]` stored in `field`, with the list of the referenced document that
+/// must point back when the relation goes both ways
+struct Relation {
+  collection: &'static str,
+  field: &'static str,
+  target: &'static str,
+  inverse: Option<&'static str>,
+}
+
+const RELATIONS: [Relation; 5] = [
+  Relation {
+    collection: "users",
+    field: "followers",
+    target: "users",
+    inverse: Some("following"),
+  },
+  Relation {
+    collection: "users",
+    field: "following",
+    target: "users",
+    inverse: Some("followers"),
+  },
+  Relation {
+    collection: "users",
+    field: "organizations",
+    target: "organizations",
+    inverse: Some("people"),
+  },
+  Relation {
+    collection: "users",
+    field: "starredRepositories",
+    target: "repositories",
+    inverse: None,
+  },
+  Relation {
+    collection: "organizations",
+    field: "people",
+    target: "users",
+    inverse: Some("organizations"),
+  },
+];
+
+/// The fields read by the checker, the other fields are not loaded
+const RELATION_FIELDS: [&str; 7] = [
+  "_id",
+  "followers",
+  "following",
+  "organizations",
+  "starredRepositories",
+  "people",
+  "owner",
+];
+
+#[derive(Debug, PartialEq)]
+pub enum IntegrityIssue {
+  /// `field` of the document `id` references `missing`, which doesn't exist in `target`
+  DanglingReference {
+    collection: &'static str,
+    id: ObjectId,
+    field: &'static str,
+    target: &'static str,
+    missing: ObjectId,
+  },
+  /// `field` of the document `id` lacks `from`, whose `inverse` references `id`
+  MissingInverse {
+    collection: &'static str,
+    id: ObjectId,
+    field: &'static str,
+    from: ObjectId,
+    inverse: &'static str,
+  },
+  /// The owner of a repository doesn't exist, it can't be repaired without knowing who the owner should be
+  MissingOwner {
+    id: ObjectId,
+    target: String,
+    owner: ObjectId,
+  },
+}
+impl fmt::Display for IntegrityIssue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      IntegrityIssue::DanglingReference {
+        collection,
+        id,
+        field,
+        target,
+        missing,
+      } => write!(
+        f,
+        "{} {}: {} references the missing {} {}",
+        collection, id, field, target, missing
+      ),
+      IntegrityIssue::MissingInverse {
+        collection,
+        id,
+        field,
+        from,
+        inverse,
+      } => write!(
+        f,
+        "{} {}: {} lacks {} which has it in {}",
+        collection, id, field, from, inverse
+      ),
+      IntegrityIssue::MissingOwner { id, target, owner } => {
+        write!(
+          f,
+          "repositories {}: owner references the missing {} {}",
+          id, target, owner
+        )
+      }
+    }
+  }
+}
+
+/// Finds the references to missing documents and the relations stored only on one side
+pub fn find_issues(data: &SeedData) -> Vec<IntegrityIssue> {
+  let documents: HashMap<&str, HashMap<ObjectId, &Document>> = data
+    .collections()
+    .into_iter()
+    .map(|(collection, documents)| {
+      let by_id = documents
+        .iter()
+        .filter_map(|document| document.get_object_id("_id").ok().map(|id| (id, document)))
+        .collect();
+      (collection, by_id)
+    })
+    .collect();
+  let exists = |collection: &str, id: &ObjectId| documents[collection].contains_key(id);
+  let mut issues = vec![];
+
+  for (collection, by_id) in &documents {
+    for document in by_id.values() {
+      issues.extend(find_dangling_references(collection, document, exists));
+    }
+  }
+
+  for relation in &RELATIONS {
+    let inverse = match relation.inverse {
+      Some(inverse) => inverse,
+      None => continue,
+    };
+    for (id, document) in &documents[relation.collection] {
+      for referenced in referenced_ids(document, relation.field) {
+        if let Some(target) = documents[relation.target].get(&referenced) {
+          if !referenced_ids(target, inverse).contains(id) {
+            issues.push(IntegrityIssue::MissingInverse {
+              collection: relation.target,
+              id: referenced,
+              field: inverse,
+              from: *id,
+              inverse: relation.field,
+            });
+          }
+        }
+      }
+    }
+  }
+
+  issues.sort_by_key(|issue| issue.to_string());
+  issues
+}
+
+/// Finds the references of a document of `collection` to the documents that don't exist, `exists` tells whether the
+/// document of a collection exists. It only needs the ids of the other documents, so it also works on datasets read
+/// one document at a time.
+pub fn find_dangling_references<F>(collection: &str, document: &Document, exists: F) -> Vec<IntegrityIssue>
+where
+  F: Fn(&str, &ObjectId) -> bool,
+{
+  let id = match document.get_object_id("_id") {
+    Ok(id) => id,
+    Err(_) => return vec![],
+  };
+  let mut issues = vec![];
+
+  for relation in RELATIONS.iter().filter(|relation| relation.collection == collection) {
+    for referenced in referenced_ids(document, relation.field) {
+      if !exists(relation.target, &referenced) {
+        issues.push(IntegrityIssue::DanglingReference {
+          collection: relation.collection,
+          id,
+          field: relation.field,
+          target: relation.target,
+          missing: referenced,
+        });
+      }
+    }
+  }
+
+  if collection == "repositories" {
+    let owner = document.get_document("owner").ok();
+    let owner_id = owner.and_then(|owner| owner.get_object_id("_id").ok());
+    let owner_ref = owner.and_then(|owner| owner.get_str("ref").ok());
+    let found = match (owner_id, owner_ref) {
+      (Some(owner_id), Some(target @ ("users" | "organizations"))) => exists(target, &owner_id),
+      (Some(owner_id), _) => exists("users", &owner_id) || exists("organizations", &owner_id),
+      _ => true,
+    };
+    if !found {
+      issues.push(IntegrityIssue::MissingOwner {
+        id,
+        target: owner_ref.unwrap_or("owner").to_string(),
+        owner: owner_id.unwrap(),
+      });
+    }
+  }
+
+  issues
+}
+
+/// The update that fixes the issue as `(collection, filter, update)`: a dangling reference is removed and a relation
+/// stored on one side is added to the other side
+pub fn repair_update(issue: &IntegrityIssue) -> Option<(&'static str, Document, Document)> {
+  match issue {
+    IntegrityIssue::DanglingReference {
+      collection,
+      id,
+      field,
+      missing,
+      ..
+    } => Some((
+      collection,
+      doc! { "_id": id },
+      doc! { "$pull": { *field: { "_id": missing } } },
+    )),
+    IntegrityIssue::MissingInverse {
+      collection,
+      id,
+      field,
+      from,
+      ..
+    } => {
+      // the people of an organization also carry the collection they come from
+      let reference = match *field {
+        "people" => doc! { "_id": from, "ref": "users" },
+        _ => doc! { "_id": from },
+      };
+      Some((
+        collection,
+        doc! { "_id": id },
+        doc! { "$addToSet": { *field: reference } },
+      ))
+    }
+    IntegrityIssue::MissingOwner { .. } => None,
+  }
+}
+
+/// Reads the relation fields of every document
+pub async fn load_relations(db: &Database) -> Result<SeedData, ModelError> {
+  let projection: Document = RELATION_FIELDS
+    .iter()
+    .map(|field| (field.to_string(), 1.into()))
+    .collect();
+  let read = |collection: &'static str| {
+    let options = FindOptions::builder().projection(projection.clone()).build();
+    async move {
+      let mut cursor = db.collection::<Document>(collection).find(None, options).await?;
+      let mut documents = vec![];
+      while let Some(document) = cursor.next().await {
+        documents.push(document?);
+      }
+      Ok::<_, ModelError>(documents)
+    }
+  };
+
+  Ok(SeedData {
+    users: read("users").await?,
+    organizations: read("organizations").await?,
+    repositories: read("repositories").await?,
+  })
+}
+
+/// Applies the repair of every issue that has one and returns how many were repaired
+pub async fn repair_issues(db: &Database, issues: &[IntegrityIssue]) -> Result<usize, ModelError> {
+  let mut repaired = 0;
+  for issue in issues {
+    if let Some((collection, filter, update)) = repair_update(issue) {
+      db.collection::<Document>(collection)
+        .update_one(filter, update, None)
+        .await?;
+      tracing::info!(issue = %issue, "Integrity issue repaired");
+      repaired += 1;
+    }
+  }
+
+  Ok(repaired)
+}
@@ -0,0 +1,141 @@
+use crate::setup::{
+  integrity::{find_issues, repair_update, IntegrityIssue},
+  seed::{generate, load_dir, GenerateOptions, SeedData},
+};
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use pretty_assertions::assert_eq;
+use std::path::Path;
+
+fn dataset() -> SeedData {
+  generate(&GenerateOptions {
+    users: 2,
+    organizations: 1,
+    repositories: 1,
+    seed: 1,
+  })
+}
+
+fn id_of(document: &Document) -> ObjectId {
+  document.get_object_id("_id").unwrap()
+}
+
+#[test]
+fn should_find_no_issues_in_the_project_seed() {
+  let data = load_dir(&Path::new(env!("CARGO_MANIFEST_DIR")).join("database_seed")).unwrap();
+
+  assert_eq!(find_issues(&data), vec![]);
+}
+
+#[test]
+fn should_find_no_issues_in_a_generated_dataset() {
+  let data = generate(&GenerateOptions {
+    users: 200,
+    organizations: 20,
+    repositories: 300,
+    seed: 3,
+  });
+
+  assert_eq!(find_issues(&data), vec![]);
+}
+
+#[test]
+fn should_remove_a_reference_to_a_deleted_document() {
+  let mut data = dataset();
+  let user_id = id_of(&data.users[0]);
+  let deleted_id = ObjectId::new();
+  data.users[0].insert("starredRepositories", vec![doc! { "_id": deleted_id }]);
+
+  let issues = find_issues(&data);
+
+  assert_eq!(
+    issues,
+    vec![IntegrityIssue::DanglingReference {
+      collection: "users",
+      id: user_id,
+      field: "starredRepositories",
+      target: "repositories",
+      missing: deleted_id,
+    }]
+  );
+  assert_eq!(
+    repair_update(&issues[0]),
+    Some((
+      "users",
+      doc! { "_id": user_id },
+      doc! { "$pull": { "starredRepositories": { "_id": deleted_id } } }
+    ))
+  );
+}
+
+#[test]
+fn should_add_the_missing_follower_of_a_followed_user() {
+  let mut data = dataset();
+  let (follower_id, followed_id) = (id_of(&data.users[0]), id_of(&data.users[1]));
+  for user in data.users.iter_mut() {
+    user.insert("followers", Vec::<Document>::new());
+    user.insert("following", Vec::<Document>::new());
+  }
+  data.users[0].insert("following", vec![doc! { "_id": followed_id }]);
+
+  let issues = find_issues(&data);
+
+  assert_eq!(
+    issues,
+    vec![IntegrityIssue::MissingInverse {
+      collection: "users",
+      id: followed_id,
+      field: "followers",
+      from: follower_id,
+      inverse: "following",
+    }]
+  );
+  assert_eq!(
+    repair_update(&issues[0]),
+    Some((
+      "users",
+      doc! { "_id": followed_id },
+      doc! { "$addToSet": { "followers": { "_id": follower_id } } }
+    ))
+  );
+}
+
+#[test]
+fn should_add_the_missing_member_of_an_organization() {
+  let mut data = dataset();
+  let organization_id = id_of(&data.organizations[0]);
+  let user_id = id_of(&data.users[0]);
+  data.organizations[0].insert("people", Vec::<Document>::new());
+  data.users[1].insert("organizations", Vec::<Document>::new());
+  data.users[0].insert("organizations", vec![doc! { "_id": organization_id }]);
+
+  let issues = find_issues(&data);
+
+  assert_eq!(issues.len(), 1);
+  assert_eq!(
+    repair_update(&issues[0]),
+    Some((
+      "organizations",
+      doc! { "_id": organization_id },
+      doc! { "$addToSet": { "people": { "_id": user_id, "ref": "users" } } }
+    ))
+  );
+}
+
+#[test]
+fn should_not_repair_a_repository_without_owner() {
+  let mut data = dataset();
+  let owner_id = ObjectId::new();
+  data.repositories[0].insert("owner", doc! { "_id": owner_id, "ref": "users" });
+
+  let issues = find_issues(&data);
+
+  assert_eq!(
+    issues,
+    vec![IntegrityIssue::MissingOwner {
+      id: id_of(&data.repositories[0]),
+      target: "users".to_string(),
+      owner: owner_id,
+    }]
+  );
+  assert_eq!(repair_update(&issues[0]), None);
+}
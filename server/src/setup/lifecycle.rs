@@ -0,0 +1,61 @@
+use crate::setup::config::ShutdownConfig;
+use actix_web::dev::ServerHandle;
+use std::{
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  time::Duration,
+};
+use tokio::time;
+use tracing;
+
+/// Process wide state shared by the readiness probe and the shutdown sequence
+#[derive(Debug, Default)]
+pub struct Lifecycle {
+  shutting_down: AtomicBool,
+}
+
+impl Lifecycle {
+  pub fn is_shutting_down(&self) -> bool {
+    self.shutting_down.load(Ordering::Relaxed)
+  }
+
+  pub fn begin_shutdown(&self) {
+    self.shutting_down.store(true, Ordering::Relaxed);
+  }
+}
+
+/// Resolves on SIGTERM, sent by orchestrators, or on SIGINT from a terminal
+pub async fn wait_for_termination() {
+  #[cfg(unix)]
+  {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen to SIGTERM.");
+    tokio::select! {
+      _ = terminate.recv() => tracing::info!("SIGTERM received"),
+      _ = tokio::signal::ctrl_c() => tracing::info!("SIGINT received"),
+    }
+  }
+
+  #[cfg(not(unix))]
+  {
+    tokio::signal::ctrl_c().await.expect("Failed to listen to Ctrl+C.");
+    tracing::info!("Ctrl+C received");
+  }
+}
+
+/// Marks the process as not ready, gives the load balancer `readiness_delay_ms` to stop routing new requests and then
+/// stops the server, which waits up to its shutdown timeout for the in-flight requests to complete
+pub async fn shutdown_gracefully(server: ServerHandle, lifecycle: Arc<Lifecycle>, config: ShutdownConfig) {
+  lifecycle.begin_shutdown();
+  tracing::info!(
+    readiness_delay_ms = config.readiness_delay_ms,
+    timeout_secs = config.timeout_secs,
+    "Shutting down, draining in-flight requests"
+  );
+
+  time::sleep(Duration::from_millis(config.readiness_delay_ms)).await;
+  server.stop(true).await;
+}
@@ -0,0 +1,60 @@
+use crate::setup::{
+  config::ShutdownConfig,
+  lifecycle::{shutdown_gracefully, Lifecycle},
+};
+use actix_web::{rt, web, App, HttpResponse, HttpServer};
+use pretty_assertions::assert_eq;
+use std::{sync::Arc, time::Duration};
+use tokio::{
+  io::{AsyncReadExt, AsyncWriteExt},
+  net::TcpStream,
+  time,
+};
+
+#[actix_rt::test]
+async fn should_complete_in_flight_requests_before_stopping() {
+  let server = HttpServer::new(|| {
+    App::new().route(
+      "/slow",
+      web::get().to(|| async {
+        time::sleep(Duration::from_millis(500)).await;
+        HttpResponse::Ok().body("done")
+      }),
+    )
+  })
+  .workers(1)
+  .shutdown_timeout(5)
+  .disable_signals()
+  .bind("127.0.0.1:0")
+  .unwrap();
+  let address = server.addrs()[0];
+  let server = server.run();
+  let handle = server.handle();
+  let running_server = rt::spawn(server);
+
+  let mut stream = TcpStream::connect(address).await.unwrap();
+  stream
+    .write_all(b"GET /slow HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+    .await
+    .unwrap();
+  time::sleep(Duration::from_millis(100)).await;
+
+  let lifecycle = Arc::new(Lifecycle::default());
+  let shutdown = rt::spawn(shutdown_gracefully(
+    handle,
+    lifecycle.clone(),
+    ShutdownConfig {
+      readiness_delay_ms: 0,
+      ..ShutdownConfig::default()
+    },
+  ));
+  let mut response = String::new();
+  stream.read_to_string(&mut response).await.unwrap();
+  shutdown.await.unwrap();
+  running_server.await.unwrap().unwrap();
+
+  assert_eq!(lifecycle.is_shutting_down(), true);
+  assert!(response.starts_with("HTTP/1.1 200 OK"));
+  assert!(response.ends_with("done"));
+  assert!(TcpStream::connect(address).await.is_err());
+}
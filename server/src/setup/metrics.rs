@@ -0,0 +1,121 @@
+use crate::lib::cursor_connection::Direction;
+use prometheus::{
+  exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+
+const LATENCY_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+pub struct Metrics {
+  registry: Registry,
+  pub http_requests_total: IntCounterVec,
+  pub http_request_duration_seconds: HistogramVec,
+  pub http_requests_in_flight: IntGauge,
+  pub db_command_duration_seconds: HistogramVec,
+  pub db_pool_connections_open: IntGauge,
+  pub db_pool_connections_in_use: IntGauge,
+  pub pagination_page_size: HistogramVec,
+  pub cache_requests_total: IntCounterVec,
+  pub cache_evictions_total: IntCounterVec,
+  pub cache_entries: IntGauge,
+}
+
+impl Metrics {
+  pub fn new() -> Self {
+    let registry = Registry::new();
+
+    let http_requests_total = IntCounterVec::new(
+      Opts::new("http_requests_total", "Number of HTTP requests handled"),
+      &["method", "route", "status"],
+    )
+    .unwrap();
+    let http_request_duration_seconds = HistogramVec::new(
+      HistogramOpts::new("http_request_duration_seconds", "HTTP request latency").buckets(LATENCY_BUCKETS.to_vec()),
+      &["method", "route", "status"],
+    )
+    .unwrap();
+    let http_requests_in_flight =
+      IntGauge::new("http_requests_in_flight", "Number of HTTP requests being handled").unwrap();
+    let db_command_duration_seconds = HistogramVec::new(
+      HistogramOpts::new("db_command_duration_seconds", "MongoDB command latency").buckets(LATENCY_BUCKETS.to_vec()),
+      &["collection", "command", "outcome"],
+    )
+    .unwrap();
+    let db_pool_connections_open =
+      IntGauge::new("db_pool_connections_open", "Number of open MongoDB pool connections").unwrap();
+    let db_pool_connections_in_use = IntGauge::new(
+      "db_pool_connections_in_use",
+      "Number of MongoDB pool connections checked out",
+    )
+    .unwrap();
+    let pagination_page_size = HistogramVec::new(
+      HistogramOpts::new("pagination_page_size", "Page size requested on paginated routes")
+        .buckets(exponential_buckets(1.0, 2.0, 8).unwrap()),
+      &["direction"],
+    )
+    .unwrap();
+    let cache_requests_total = IntCounterVec::new(
+      Opts::new("cache_requests_total", "Number of store queries looked up in the cache"),
+      &["query", "outcome"],
+    )
+    .unwrap();
+    let cache_evictions_total = IntCounterVec::new(
+      Opts::new("cache_evictions_total", "Number of entries removed from the cache"),
+      &["reason"],
+    )
+    .unwrap();
+    let cache_entries = IntGauge::new("cache_entries", "Number of entries in the cache").unwrap();
+
+    registry.register(Box::new(http_requests_total.clone())).unwrap();
+    registry
+      .register(Box::new(http_request_duration_seconds.clone()))
+      .unwrap();
+    registry.register(Box::new(http_requests_in_flight.clone())).unwrap();
+    registry
+      .register(Box::new(db_command_duration_seconds.clone()))
+      .unwrap();
+    registry.register(Box::new(db_pool_connections_open.clone())).unwrap();
+    registry.register(Box::new(db_pool_connections_in_use.clone())).unwrap();
+    registry.register(Box::new(pagination_page_size.clone())).unwrap();
+    registry.register(Box::new(cache_requests_total.clone())).unwrap();
+    registry.register(Box::new(cache_evictions_total.clone())).unwrap();
+    registry.register(Box::new(cache_entries.clone())).unwrap();
+
+    Self {
+      registry,
+      http_requests_total,
+      http_request_duration_seconds,
+      http_requests_in_flight,
+      db_command_duration_seconds,
+      db_pool_connections_open,
+      db_pool_connections_in_use,
+      pagination_page_size,
+      cache_requests_total,
+      cache_evictions_total,
+      cache_entries,
+    }
+  }
+
+  pub fn observe_page_size(&self, direction: &Direction, page_size: u32) {
+    let direction = match direction {
+      Direction::Forward => "forward",
+      Direction::Backward => "backward",
+    };
+    self
+      .pagination_page_size
+      .with_label_values(&[direction])
+      .observe(page_size as f64);
+  }
+
+  /// Renders every registered metric in the Prometheus text exposition format
+  pub fn encode(&self) -> String {
+    let mut buffer = vec![];
+    TextEncoder::new().encode(&self.registry.gather(), &mut buffer).unwrap();
+    String::from_utf8(buffer).unwrap()
+  }
+}
+
+impl Default for Metrics {
+  fn default() -> Self {
+    Self::new()
+  }
+}
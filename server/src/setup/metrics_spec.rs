@@ -0,0 +1,26 @@
+use crate::lib::cursor_connection::Direction;
+use crate::setup::metrics::Metrics;
+use pretty_assertions::assert_eq;
+
+#[test]
+fn should_observe_the_page_size_by_direction() {
+  let metrics = Metrics::new();
+
+  metrics.observe_page_size(&Direction::Forward, 15);
+  metrics.observe_page_size(&Direction::Backward, 100);
+
+  let forward = metrics.pagination_page_size.with_label_values(&["forward"]);
+  assert_eq!(forward.get_sample_count(), 1);
+  assert_eq!(forward.get_sample_sum(), 15.0);
+}
+
+#[test]
+fn should_encode_the_metrics_in_the_text_format() {
+  let metrics = Metrics::new();
+
+  metrics.http_requests_in_flight.inc();
+  let text = metrics.encode();
+
+  assert!(text.contains("# TYPE http_requests_in_flight gauge"));
+  assert!(text.contains("http_requests_in_flight 1"));
+}
@@ -0,0 +1,276 @@
+mod m001_add_created_at;
+
+use crate::setup::{
+  config::{MigrationsConfig, RetryConfig},
+  db::backoff_delays,
+};
+use futures::future::BoxFuture;
+use mongodb::{
+  bson::{doc, oid::ObjectId, Bson, DateTime, Document},
+  error::{Error as ModelError, ErrorKind, WriteFailure},
+  Database,
+};
+use std::{fmt, time::Duration};
+use tokio::time;
+use tokio_stream::StreamExt;
+use tracing;
+
+const MIGRATIONS_COLLECTION: &str = "_migrations";
+const LOCK_COLLECTION: &str = "_migrations_lock";
+const LOCK_ID: &str = "migrations";
+const DUPLICATE_KEY_ERROR: i32 = 11000;
+
+/// A versioned change of the stored data. Versions are applied in ascending order and recorded in the `_migrations`
+/// collection, `down` must undo what `up` did so that a release can be rolled back.
+pub trait Migration: Send + Sync {
+  fn version(&self) -> u32;
+  fn name(&self) -> &'static str;
+  fn up<'a>(&'a self, db: &'a Database) -> BoxFuture<'a, Result<(), ModelError>>;
+  fn down<'a>(&'a self, db: &'a Database) -> BoxFuture<'a, Result<(), ModelError>>;
+}
+
+/// Every migration of the project, new ones are appended with the next version
+pub fn migrations() -> Vec<Box<dyn Migration>> {
+  vec![Box::new(m001_add_created_at::AddCreatedAt)]
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MigrationStatus {
+  pub version: u32,
+  pub name: &'static str,
+  pub applied_at: Option<DateTime>,
+}
+
+#[derive(Debug)]
+pub enum MigrationError {
+  Locked(String),
+  UnknownVersion(u32),
+  InvalidRecord(Document),
+  Database(ModelError),
+}
+impl fmt::Display for MigrationError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      MigrationError::Locked(owner) => write!(f, "the migrations are locked by {}", owner),
+      MigrationError::UnknownVersion(version) => write!(f, "there is no migration with the version {}", version),
+      MigrationError::InvalidRecord(record) => write!(f, "the migration record {} has no valid version", record),
+      MigrationError::Database(err) => write!(f, "{}", err),
+    }
+  }
+}
+impl From<ModelError> for MigrationError {
+  fn from(err: ModelError) -> Self {
+    MigrationError::Database(err)
+  }
+}
+
+/// Startup step, when another instance holds the lock it waits until the lock is released, or expired and taken
+/// over, so that the server never starts on a schema whose migrations are still running
+pub async fn migrate_at_startup(
+  db: &Database,
+  config: &MigrationsConfig,
+  retry: &RetryConfig,
+) -> Result<(), MigrationError> {
+  if !config.run_at_startup {
+    return Ok(());
+  }
+
+  let mut delays = backoff_delays(retry);
+  loop {
+    match up(db, None, Duration::from_secs(config.lock_timeout_secs)).await {
+      Ok(applied) => {
+        tracing::info!(applied = ?applied, "Migrations up to date");
+        return Ok(());
+      }
+      Err(MigrationError::Locked(owner)) => {
+        let delay = delays.next().unwrap_or_default();
+        tracing::warn!(
+          owner = %owner,
+          delay_ms = delay.as_millis() as u64,
+          "Waiting for the migrations applied by another instance"
+        );
+        time::sleep(delay).await;
+      }
+      Err(err) => return Err(err),
+    }
+  }
+}
+
+pub async fn status(db: &Database) -> Result<Vec<MigrationStatus>, MigrationError> {
+  let applied = applied_migrations(db).await?;
+  let status = migrations()
+    .iter()
+    .map(|migration| MigrationStatus {
+      version: migration.version(),
+      name: migration.name(),
+      applied_at: applied
+        .iter()
+        .find(|(version, _)| *version == migration.version())
+        .map(|(_, applied_at)| *applied_at),
+    })
+    .collect();
+
+  Ok(status)
+}
+
+/// Applies the pending migrations up to `target`, or all of them, and returns the applied versions
+pub async fn up(db: &Database, target: Option<u32>, lock_timeout: Duration) -> Result<Vec<u32>, MigrationError> {
+  let all = migrations();
+  if let Some(target) = target {
+    ensure_known_version(&all, target)?;
+  }
+
+  with_lock(db, lock_timeout, async {
+    let applied: Vec<u32> = applied_migrations(db)
+      .await?
+      .into_iter()
+      .map(|(version, _)| version)
+      .collect();
+    let mut done = vec![];
+    for version in pending_versions(&all, &applied, target) {
+      let migration = all.iter().find(|migration| migration.version() == version).unwrap();
+      tracing::info!(version, name = migration.name(), "Applying migration");
+      migration.up(db).await?;
+      let record = doc! { "_id": version as i64, "name": migration.name(), "appliedAt": DateTime::now() };
+      db.collection::<Document>(MIGRATIONS_COLLECTION)
+        .insert_one(record, None)
+        .await?;
+      done.push(version);
+    }
+    Ok(done)
+  })
+  .await
+}
+
+/// Reverts the applied migrations newer than `target`, or only the last one, and returns the reverted versions
+pub async fn down(db: &Database, target: Option<u32>, lock_timeout: Duration) -> Result<Vec<u32>, MigrationError> {
+  let all = migrations();
+  if let Some(target) = target.filter(|target| *target > 0) {
+    ensure_known_version(&all, target)?;
+  }
+
+  with_lock(db, lock_timeout, async {
+    let applied: Vec<u32> = applied_migrations(db)
+      .await?
+      .into_iter()
+      .map(|(version, _)| version)
+      .collect();
+    let mut done = vec![];
+    for version in versions_to_revert(&applied, target) {
+      let migration = match all.iter().find(|migration| migration.version() == version) {
+        Some(migration) => migration,
+        None => return Err(MigrationError::UnknownVersion(version)),
+      };
+      tracing::info!(version, name = migration.name(), "Reverting migration");
+      migration.down(db).await?;
+      db.collection::<Document>(MIGRATIONS_COLLECTION)
+        .delete_one(doc! { "_id": version as i64 }, None)
+        .await?;
+      done.push(version);
+    }
+    Ok(done)
+  })
+  .await
+}
+
+pub fn pending_versions(all: &[Box<dyn Migration>], applied: &[u32], target: Option<u32>) -> Vec<u32> {
+  let mut versions: Vec<u32> = all
+    .iter()
+    .map(|migration| migration.version())
+    .filter(|version| !applied.contains(version))
+    .filter(|version| matches!(target, Some(target) if *version <= target) || target.is_none())
+    .collect();
+  versions.sort_unstable();
+  versions
+}
+
+pub fn versions_to_revert(applied: &[u32], target: Option<u32>) -> Vec<u32> {
+  let mut versions = applied.to_vec();
+  versions.sort_unstable_by(|a, b| b.cmp(a));
+  match target {
+    Some(target) => versions.into_iter().filter(|version| *version > target).collect(),
+    None => versions.into_iter().take(1).collect(),
+  }
+}
+
+fn ensure_known_version(all: &[Box<dyn Migration>], version: u32) -> Result<(), MigrationError> {
+  match all.iter().any(|migration| migration.version() == version) {
+    true => Ok(()),
+    false => Err(MigrationError::UnknownVersion(version)),
+  }
+}
+
+async fn applied_migrations(db: &Database) -> Result<Vec<(u32, DateTime)>, MigrationError> {
+  let mut cursor = db
+    .collection::<Document>(MIGRATIONS_COLLECTION)
+    .find(None, None)
+    .await?;
+  let mut applied = vec![];
+  while let Some(record) = cursor.next().await {
+    let record = record?;
+    let version = record_version(&record)?;
+    let applied_at = record.get_datetime("appliedAt").copied().unwrap_or(DateTime::MIN);
+    applied.push((version, applied_at));
+  }
+
+  Ok(applied)
+}
+
+/// The version is the `_id` of the record, the shell writes it as an Int32 and the migrations as an Int64
+pub fn record_version(record: &Document) -> Result<u32, MigrationError> {
+  let version = match record.get("_id") {
+    Some(Bson::Int32(version)) => u32::try_from(*version).ok(),
+    Some(Bson::Int64(version)) => u32::try_from(*version).ok(),
+    _ => None,
+  };
+  version.ok_or_else(|| MigrationError::InvalidRecord(record.clone()))
+}
+
+/// Runs `task` holding the lock document, so that two instances starting together don't apply the same migration. A
+/// lock older than `lock_timeout` is considered abandoned by a crashed process and is taken over.
+async fn with_lock<F, T>(db: &Database, lock_timeout: Duration, task: F) -> Result<T, MigrationError>
+where
+  F: std::future::Future<Output = Result<T, MigrationError>>,
+{
+  let locks = db.collection::<Document>(LOCK_COLLECTION);
+  let owner = format!("{}-{}", std::process::id(), ObjectId::new());
+  let expires_at = DateTime::from_millis(DateTime::now().timestamp_millis() + lock_timeout.as_millis() as i64);
+  let lock = doc! { "_id": LOCK_ID, "owner": &owner, "expiresAt": expires_at };
+
+  if let Err(err) = locks.insert_one(lock.clone(), None).await {
+    if !is_duplicate_key(&err) {
+      return Err(err.into());
+    }
+    let abandoned = doc! { "_id": LOCK_ID, "expiresAt": { "$lt": DateTime::now() } };
+    match locks.find_one_and_delete(abandoned, None).await? {
+      Some(_) => {
+        tracing::warn!("Taking over an expired migrations lock");
+        locks.insert_one(lock, None).await?;
+      }
+      None => {
+        let holder = locks.find_one(doc! { "_id": LOCK_ID }, None).await?;
+        let holder = holder.and_then(|holder| holder.get_str("owner").ok().map(str::to_string));
+        return Err(MigrationError::Locked(
+          holder.unwrap_or_else(|| "another process".to_string()),
+        ));
+      }
+    }
+  }
+
+  let result = task.await;
+  if let Err(err) = locks.delete_one(doc! { "_id": LOCK_ID, "owner": &owner }, None).await {
+    tracing::error!(
+      "The migrations lock is not released, it expires in {:?}\n{}",
+      lock_timeout,
+      err
+    );
+  }
+  result
+}
+
+fn is_duplicate_key(err: &ModelError) -> bool {
+  matches!(
+    &*err.kind,
+    ErrorKind::Write(WriteFailure::WriteError(write_error)) if write_error.code == DUPLICATE_KEY_ERROR
+  )
+}
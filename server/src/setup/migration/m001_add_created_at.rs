@@ -0,0 +1,47 @@
+use crate::setup::migration::Migration;
+use futures::future::BoxFuture;
+use mongodb::{
+  bson::{doc, Document},
+  error::Error as ModelError,
+  Database,
+};
+
+const COLLECTIONS: &[&str] = &["organizations", "repositories", "users"];
+
+/// Adds `createdAt` to every document, taken from the creation time embedded in its `ObjectId`
+pub struct AddCreatedAt;
+
+impl Migration for AddCreatedAt {
+  fn version(&self) -> u32 {
+    1
+  }
+
+  fn name(&self) -> &'static str {
+    "add_created_at"
+  }
+
+  fn up<'a>(&'a self, db: &'a Database) -> BoxFuture<'a, Result<(), ModelError>> {
+    Box::pin(async move {
+      for collection in COLLECTIONS {
+        let filter = doc! { "createdAt": { "$exists": false } };
+        let update = vec![doc! { "$set": { "createdAt": { "$toDate": "$_id" } } }];
+        db.collection::<Document>(collection)
+          .update_many(filter, update, None)
+          .await?;
+      }
+      Ok(())
+    })
+  }
+
+  fn down<'a>(&'a self, db: &'a Database) -> BoxFuture<'a, Result<(), ModelError>> {
+    Box::pin(async move {
+      for collection in COLLECTIONS {
+        let update = doc! { "$unset": { "createdAt": "" } };
+        db.collection::<Document>(collection)
+          .update_many(doc! {}, update, None)
+          .await?;
+      }
+      Ok(())
+    })
+  }
+}
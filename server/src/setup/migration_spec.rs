@@ -0,0 +1,118 @@
+use crate::setup::{
+  config::{MigrationsConfig, RetryConfig},
+  migration::{
+    migrate_at_startup, migrations, pending_versions, record_version, status, versions_to_revert, MigrationError,
+  },
+  mock,
+};
+use mongodb::bson::{doc, DateTime, Document};
+use pretty_assertions::assert_eq;
+use std::time::Duration;
+use tokio::time;
+
+#[test]
+fn should_declare_the_migrations_with_unique_ascending_versions() {
+  let versions: Vec<u32> = migrations().iter().map(|migration| migration.version()).collect();
+  let mut expected = versions.clone();
+  expected.sort_unstable();
+  expected.dedup();
+
+  assert_eq!(versions, expected);
+  assert_eq!(versions.first(), Some(&1));
+}
+
+#[test]
+fn should_list_every_migration_as_pending_when_nothing_was_applied() {
+  let all = migrations();
+  let expected: Vec<u32> = all.iter().map(|migration| migration.version()).collect();
+
+  assert_eq!(pending_versions(&all, &[], None), expected);
+}
+
+#[test]
+fn should_not_list_the_applied_migrations_as_pending() {
+  let all = migrations();
+
+  assert_eq!(pending_versions(&all, &[1], None), Vec::<u32>::new());
+}
+
+#[test]
+fn should_not_list_the_migrations_newer_than_the_target_as_pending() {
+  let all = migrations();
+
+  assert_eq!(pending_versions(&all, &[], Some(0)), Vec::<u32>::new());
+  assert_eq!(pending_versions(&all, &[], Some(1)), vec![1]);
+}
+
+#[test]
+fn should_revert_only_the_last_migration_without_target() {
+  assert_eq!(versions_to_revert(&[1, 3, 2], None), vec![3]);
+}
+
+#[test]
+fn should_revert_the_migrations_newer_than_the_target_newest_first() {
+  assert_eq!(versions_to_revert(&[1, 3, 2], Some(1)), vec![3, 2]);
+  assert_eq!(versions_to_revert(&[1, 3, 2], Some(0)), vec![3, 2, 1]);
+  assert_eq!(versions_to_revert(&[], Some(0)), Vec::<u32>::new());
+}
+
+#[test]
+fn should_read_the_version_of_a_migration_record_written_as_any_integer() {
+  assert_eq!(record_version(&doc! { "_id": 1_i32 }).unwrap(), 1);
+  assert_eq!(record_version(&doc! { "_id": 1_i64 }).unwrap(), 1);
+}
+
+#[test]
+fn should_refuse_a_migration_record_without_a_valid_version() {
+  for record in [
+    doc! { "_id": "1" },
+    doc! { "_id": 1.0 },
+    doc! { "_id": -1_i64 },
+    doc! {},
+  ] {
+    assert!(matches!(record_version(&record), Err(MigrationError::InvalidRecord(_))));
+  }
+}
+
+#[actix_rt::test]
+async fn should_wait_for_the_migrations_locked_by_another_instance_at_startup() {
+  let db = mock::setup(&mock::random_sufix()).await;
+  let locks = db.collection::<Document>("_migrations_lock");
+  let expires_at = DateTime::from_millis(DateTime::now().timestamp_millis() + 60_000);
+  locks.delete_many(doc! {}, None).await.unwrap();
+  locks
+    .insert_one(
+      doc! { "_id": "migrations", "owner": "other", "expiresAt": expires_at },
+      None,
+    )
+    .await
+    .unwrap();
+  let release = actix_rt::spawn({
+    let locks = locks.clone();
+    async move {
+      time::sleep(Duration::from_millis(300)).await;
+      locks.delete_one(doc! { "owner": "other" }, None).await.unwrap();
+    }
+  });
+  let config = MigrationsConfig {
+    run_at_startup: true,
+    ..MigrationsConfig::default()
+  };
+  let retry = RetryConfig {
+    initial_delay_ms: 50,
+    max_delay_ms: 100,
+    ..RetryConfig::default()
+  };
+
+  migrate_at_startup(&db, &config, &retry).await.unwrap();
+  release.await.unwrap();
+  let applied: Vec<bool> = status(&db)
+    .await
+    .unwrap()
+    .iter()
+    .map(|migration| migration.applied_at.is_some())
+    .collect();
+
+  assert_eq!(applied, vec![true; migrations().len()]);
+  assert_eq!(locks.count_documents(None, None).await.unwrap(), 0);
+}
@@ -1,11 +1,16 @@
-use crate::{http::AppState, setup::db::db_client_connection};
-use actix_web::{dev::ServiceResponse, test, web, App, Scope};
+use crate::{
+  http::{viewer::Viewer, AppState},
+  model::store::{MemoryStore, MongoStore, SqliteStore, Store},
+  setup::{config::Config, db::db_client, lifecycle::Lifecycle, metrics::Metrics, seed::SeedData, sqlite},
+};
+use actix_web::{dev::ServiceResponse, test, web, App, HttpMessage, Scope};
 use mongodb::{
   bson::{self, doc, oid::ObjectId},
   error::Error as ModelError,
   results::InsertOneResult,
-  Database,
+  Client, Database,
 };
+use std::{path::Path, sync::Arc};
 
 pub enum HttpMethod {
   Get,
@@ -13,9 +18,18 @@ pub enum HttpMethod {
 
 pub async fn make_request(method: HttpMethod, uri: &str, scope: Scope, sufix: &str) -> ServiceResponse {
   let db = setup(sufix).await;
+  let config = Config::load().unwrap();
   let app = test::init_service(
     App::new()
-      .app_data(web::Data::new(AppState { db: db.clone() }))
+      .app_data(web::Data::new(AppState {
+        store: Arc::new(MongoStore::new(db.clone())),
+        cache: None,
+        db,
+        config,
+        metrics: Arc::new(Metrics::new()),
+        log_filter: None,
+        lifecycle: Arc::new(Lifecycle::default()),
+      }))
       .service(scope),
   )
   .await;
@@ -27,8 +41,57 @@ pub async fn make_request(method: HttpMethod, uri: &str, scope: Scope, sufix: &s
   test::call_service(&app, req).await
 }
 
+/// Calls the routes of `configure` with a store that doesn't need a database
+pub async fn make_store_request(
+  req: test::TestRequest,
+  configure: fn(&mut web::ServiceConfig),
+  store: Arc<dyn Store>,
+  config: Config,
+) -> ServiceResponse {
+  make_viewer_request(req, None, configure, store, config).await
+}
+
+/// Calls the routes of `configure` with the viewer the authentication layer would insert into the request
+pub async fn make_viewer_request(
+  req: test::TestRequest,
+  viewer: Option<Viewer>,
+  configure: fn(&mut web::ServiceConfig),
+  store: Arc<dyn Store>,
+  config: Config,
+) -> ServiceResponse {
+  // the client connects lazily, these requests never reach the database
+  let db = Client::with_uri_str(&config.database.uri)
+    .await
+    .unwrap()
+    .database(&config.database.name);
+  let app = test::init_service(
+    App::new()
+      .app_data(web::Data::new(AppState {
+        db,
+        store,
+        cache: None,
+        config,
+        metrics: Arc::new(Metrics::new()),
+        log_filter: None,
+        lifecycle: Arc::new(Lifecycle::default()),
+      }))
+      .configure(configure),
+  )
+  .await;
+
+  let req = req.to_request();
+  if let Some(viewer) = viewer {
+    req.extensions_mut().insert(viewer);
+  }
+  test::call_service(&app, req).await
+}
+
 pub async fn setup(sufix: &str) -> Database {
-  let db = db_client_connection().await.unwrap();
+  let config = Config::load().unwrap();
+  let db = db_client(&config.database, Arc::new(Metrics::new()))
+    .await
+    .unwrap()
+    .database(config.database.name.as_str());
   insert_mocked_data(&db, sufix).await.unwrap();
 
   db
@@ -42,7 +105,39 @@ pub fn random_sufix() -> String {
   ObjectId::new().to_string()
 }
 
+/// A store holding the mocked data in memory, for the tests that don't need a database
+pub fn memory_store(sufix: &str) -> MemoryStore {
+  let data = mocked_data(sufix);
+  MemoryStore::new(data.users, data.organizations, data.repositories)
+}
+
+/// A store holding the mocked data in an in-memory SQLite database
+pub async fn mongo_store(sufix: &str) -> MongoStore {
+  MongoStore::new(setup(sufix).await)
+}
+
+pub fn sqlite_store(sufix: &str) -> SqliteStore {
+  let mut connection = sqlite::open(Path::new(":memory:")).unwrap();
+  sqlite::import(&mut connection, &mocked_data(sufix), false).unwrap();
+  SqliteStore::new(connection)
+}
+
 async fn insert_mocked_data(db: &Database, sufix: &str) -> Result<(), ModelError> {
+  let data = mocked_data(sufix);
+  for organization in data.organizations {
+    insert_organization(db, organization).await?;
+  }
+  for user in data.users {
+    insert_user(db, user).await?;
+  }
+  for repository in data.repositories {
+    insert_repository(db, repository).await?;
+  }
+
+  Ok(())
+}
+
+fn mocked_data(sufix: &str) -> SeedData {
   let organization_foo_id = random_id();
   let organization_acme_id = random_id();
   let user_foo_id = random_id();
@@ -102,6 +197,7 @@ async fn insert_mocked_data(db: &Database, sufix: &str) -> Result<(), ModelError
     "_id": user_bar_id,
     "avatarUrl": "https://bar.com/avatar.jpg",
     "email": "bar@email.com",
+    "emailVisibility": "public",
     "following": vec![
       doc! { "_id": user_foo_id }
     ],
@@ -162,19 +258,11 @@ async fn insert_mocked_data(db: &Database, sufix: &str) -> Result<(), ModelError
     "owner": { "_id": user_dee_id, "ref": "users" }
   };
 
-  insert_organization(db, organization_acme).await?;
-  insert_organization(db, organization_foo).await?;
-  insert_organization(db, organization_empty_org).await?;
-  insert_user(db, user_foo).await?;
-  insert_user(db, user_bar).await?;
-  insert_user(db, user_dee).await?;
-  insert_user(db, user_empty_user).await?;
-  insert_repository(db, repository_tux).await?;
-  insert_repository(db, repository_mar).await?;
-  insert_repository(db, repository_bar).await?;
-  insert_repository(db, repository_dee).await?;
-
-  Ok(())
+  SeedData {
+    users: vec![user_foo, user_bar, user_dee, user_empty_user],
+    organizations: vec![organization_acme, organization_foo, organization_empty_org],
+    repositories: vec![repository_tux, repository_mar, repository_bar, repository_dee],
+  }
 }
 
 async fn insert_organization(db: &Database, document: bson::Document) -> Result<InsertOneResult, ModelError> {
@@ -1,8 +1,52 @@
+pub mod backup;
+pub mod config;
 pub mod db;
+pub mod db_events;
+pub mod github;
+pub mod integrity;
+pub mod lifecycle;
+pub mod metrics;
+pub mod migration;
+pub mod schema;
+pub mod seed;
+pub mod sqlite;
 pub mod tracing;
 
+#[cfg(test)]
+mod backup_spec;
+
+#[cfg(test)]
+mod config_spec;
+
+#[cfg(test)]
+mod db_events_spec;
+
 #[cfg(test)]
 mod db_spec;
 
+#[cfg(test)]
+mod github_spec;
+
+#[cfg(test)]
+mod integrity_spec;
+
+#[cfg(test)]
+mod lifecycle_spec;
+
+#[cfg(test)]
+mod metrics_spec;
+
+#[cfg(test)]
+mod migration_spec;
+
+#[cfg(test)]
+mod schema_spec;
+
+#[cfg(test)]
+mod seed_spec;
+
+#[cfg(test)]
+mod sqlite_spec;
+
 #[cfg(test)]
 pub mod mock;
@@ -0,0 +1,130 @@
+use crate::model::schema::{collection_schemas, CollectionSchema};
+use crate::setup::config::{SchemaConfig, SchemaValidation};
+use mongodb::{
+  bson::{doc, Document},
+  error::Error as ModelError,
+  Database,
+};
+use std::{collections::HashMap, fmt};
+use tracing;
+
+#[derive(Debug, PartialEq)]
+pub enum SchemaChange {
+  CreateCollection(&'static str),
+  CreateIndex(&'static str, String),
+  SetValidator(&'static str, SchemaValidation),
+}
+impl fmt::Display for SchemaChange {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SchemaChange::CreateCollection(collection) => write!(f, "create the collection {}", collection),
+      SchemaChange::CreateIndex(collection, index) => write!(f, "create the index {} on {}", index, collection),
+      SchemaChange::SetValidator(collection, validation) => {
+        write!(
+          f,
+          "set the validator of {} with the action {:?}",
+          collection, validation
+        )
+      }
+    }
+  }
+}
+
+/// Startup step, a failure is logged instead of stopping the server because the queries still work without indexes
+pub async fn prepare_database(db: &Database, config: &SchemaConfig) {
+  match ensure_schema(db, config).await {
+    Ok(changes) if changes.is_empty() => tracing::info!("Database schema up to date"),
+    Ok(_) => {}
+    Err(err) => tracing::error!("Database schema setup fails with the error\n{}", err),
+  }
+}
+
+pub async fn ensure_schema(db: &Database, config: &SchemaConfig) -> Result<Vec<SchemaChange>, ModelError> {
+  let schemas = collection_schemas();
+  let mut existing_indexes = HashMap::new();
+  for collection in db.list_collection_names(None).await? {
+    if schemas.iter().any(|schema| schema.collection == collection) {
+      let indexes = db.collection::<Document>(&collection).list_index_names().await?;
+      existing_indexes.insert(collection, indexes);
+    }
+  }
+
+  let changes = plan_schema_changes(&schemas, &existing_indexes, config);
+  for change in &changes {
+    if config.dry_run {
+      tracing::info!("[dry run] would {}", change);
+      continue;
+    }
+    apply_schema_change(db, &schemas, change).await?;
+    tracing::info!("Database schema: {}", change);
+  }
+
+  Ok(changes)
+}
+
+/// Compares the schemas with the collections and their indexes, keyed by collection name, found in the database
+pub fn plan_schema_changes(
+  schemas: &[CollectionSchema],
+  existing_indexes: &HashMap<String, Vec<String>>,
+  config: &SchemaConfig,
+) -> Vec<SchemaChange> {
+  let mut changes = vec![];
+
+  for schema in schemas {
+    let indexes = existing_indexes.get(schema.collection);
+    if indexes.is_none() && config.validation != SchemaValidation::Off {
+      changes.push(SchemaChange::CreateCollection(schema.collection));
+    }
+    if config.ensure_indexes {
+      let missing_indexes = schema
+        .indexes
+        .iter()
+        .filter_map(|index| index.options.as_ref().and_then(|options| options.name.clone()))
+        .filter(|name| !indexes.into_iter().flatten().any(|index| index == name))
+        .map(|name| SchemaChange::CreateIndex(schema.collection, name));
+      changes.extend(missing_indexes);
+    }
+    if config.validation != SchemaValidation::Off {
+      changes.push(SchemaChange::SetValidator(schema.collection, config.validation));
+    }
+  }
+
+  changes
+}
+
+async fn apply_schema_change(
+  db: &Database,
+  schemas: &[CollectionSchema],
+  change: &SchemaChange,
+) -> Result<(), ModelError> {
+  let schema_of = |collection: &str| schemas.iter().find(|schema| schema.collection == collection).unwrap();
+
+  match change {
+    SchemaChange::CreateCollection(collection) => db.create_collection(collection, None).await,
+    SchemaChange::CreateIndex(collection, name) => {
+      let index = schema_of(collection)
+        .indexes
+        .iter()
+        .find(|index| matches!(&index.options, Some(options) if options.name.as_ref() == Some(name)))
+        .unwrap()
+        .clone();
+      db.collection::<Document>(collection).create_index(index, None).await?;
+      Ok(())
+    }
+    SchemaChange::SetValidator(collection, validation) => {
+      let action = match validation {
+        SchemaValidation::Error => "error",
+        _ => "warn",
+      };
+      let command = doc! {
+        "collMod": *collection,
+        "validator": { "$jsonSchema": schema_of(collection).json_schema.clone() },
+        // documents already invalid can still be updated, only new violations are rejected
+        "validationLevel": "moderate",
+        "validationAction": action,
+      };
+      db.run_command(command, None).await?;
+      Ok(())
+    }
+  }
+}
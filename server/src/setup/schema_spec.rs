@@ -0,0 +1,72 @@
+use crate::model::schema::collection_schemas;
+use crate::setup::{
+  config::{SchemaConfig, SchemaValidation},
+  schema::{plan_schema_changes, SchemaChange},
+};
+use pretty_assertions::assert_eq;
+use std::collections::HashMap;
+
+fn all_indexes_of(collection: &str) -> Vec<String> {
+  collection_schemas()
+    .into_iter()
+    .find(|schema| schema.collection == collection)
+    .unwrap()
+    .indexes
+    .into_iter()
+    .map(|index| index.options.unwrap().name.unwrap())
+    .collect()
+}
+
+#[test]
+fn should_plan_only_the_missing_indexes() {
+  let existing_indexes = HashMap::from([
+    ("organizations".to_string(), all_indexes_of("organizations")),
+    ("repositories".to_string(), all_indexes_of("repositories")),
+    (
+      "users".to_string(),
+      vec!["_id_".to_string(), "login_unique".to_string()],
+    ),
+  ]);
+  let changes = plan_schema_changes(&collection_schemas(), &existing_indexes, &SchemaConfig::default());
+
+  assert_eq!(
+    changes,
+    vec![SchemaChange::CreateIndex("users", "search_text".to_string())]
+  );
+}
+
+#[test]
+fn should_create_the_missing_collections_before_setting_their_validators() {
+  let existing_indexes = HashMap::from([
+    ("organizations".to_string(), all_indexes_of("organizations")),
+    ("users".to_string(), all_indexes_of("users")),
+  ]);
+  let config = SchemaConfig {
+    validation: SchemaValidation::Warn,
+    ..SchemaConfig::default()
+  };
+  let changes = plan_schema_changes(&collection_schemas(), &existing_indexes, &config);
+
+  assert_eq!(
+    changes,
+    vec![
+      SchemaChange::SetValidator("organizations", SchemaValidation::Warn),
+      SchemaChange::CreateCollection("repositories"),
+      SchemaChange::CreateIndex("repositories", "owner_id".to_string()),
+      SchemaChange::CreateIndex("repositories", "search_text".to_string()),
+      SchemaChange::SetValidator("repositories", SchemaValidation::Warn),
+      SchemaChange::SetValidator("users", SchemaValidation::Warn),
+    ]
+  );
+}
+
+#[test]
+fn should_plan_nothing_when_indexes_and_validators_are_disabled() {
+  let config = SchemaConfig {
+    ensure_indexes: false,
+    ..SchemaConfig::default()
+  };
+  let changes = plan_schema_changes(&collection_schemas(), &HashMap::new(), &config);
+
+  assert_eq!(changes, vec![]);
+}
@@ -0,0 +1,387 @@
+use crate::model::{organization::Organization, repository::Repository, user::User};
+use crate::setup::integrity::find_issues;
+use mongodb::{
+  bson::{self, doc, oid::ObjectId, Bson, Document},
+  error::{Error as ModelError, ErrorKind},
+  options::InsertManyOptions,
+  Database,
+};
+use serde::de::DeserializeOwned;
+use std::{
+  collections::{BTreeSet, HashSet},
+  fmt, fs, io,
+  path::Path,
+};
+use tracing;
+
+const INSERT_BATCH_SIZE: usize = 1000;
+const DUPLICATE_KEY_ERROR: i32 = 11000;
+const FIRST_TIMESTAMP: u32 = 1_577_836_800;
+const TIMESTAMP_RANGE: u64 = 3 * 365 * 24 * 60 * 60;
+
+/// The documents of every collection, in the same shape they are stored in the database
+#[derive(Debug, Default, PartialEq)]
+pub struct SeedData {
+  pub users: Vec<Document>,
+  pub organizations: Vec<Document>,
+  pub repositories: Vec<Document>,
+}
+impl SeedData {
+  pub fn collections(&self) -> [(&'static str, &Vec<Document>); 3] {
+    [
+      ("users", &self.users),
+      ("organizations", &self.organizations),
+      ("repositories", &self.repositories),
+    ]
+  }
+}
+
+#[derive(Debug)]
+pub enum SeedError {
+  Io(String, io::Error),
+  Parse { file: String, line: usize, message: String },
+  Database(ModelError),
+}
+impl fmt::Display for SeedError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SeedError::Io(path, err) => write!(f, "{}: {}", path, err),
+      SeedError::Parse { file, line, message } => write!(f, "{}:{}: {}", file, line, message),
+      SeedError::Database(err) => write!(f, "{}", err),
+    }
+  }
+}
+impl From<ModelError> for SeedError {
+  fn from(err: ModelError) -> Self {
+    SeedError::Database(err)
+  }
+}
+
+/// Reads `users.json`, `organizations.json` and `repositories.json` from `dir`, a missing file is an empty collection
+pub fn load_dir(dir: &Path) -> Result<SeedData, SeedError> {
+  let read = |collection: &str| -> Result<Vec<Document>, SeedError> {
+    let path = dir.join(format!("{}.json", collection));
+    let file = path.display().to_string();
+    match fs::read_to_string(&path) {
+      Ok(content) => parse_documents(&file, &content),
+      Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+      Err(err) => Err(SeedError::Io(file, err)),
+    }
+  };
+
+  Ok(SeedData {
+    users: read("users")?,
+    organizations: read("organizations")?,
+    repositories: read("repositories")?,
+  })
+}
+
+/// Parses one document per line in the extended JSON format used by `mongoimport` and `mongoexport`
+pub fn parse_documents(file: &str, content: &str) -> Result<Vec<Document>, SeedError> {
+  let parse_error = |line: usize, message: String| SeedError::Parse {
+    file: file.to_string(),
+    line,
+    message,
+  };
+
+  let mut documents = vec![];
+  for (index, line) in content.lines().enumerate() {
+    if line.trim().is_empty() {
+      continue;
+    }
+    documents.push(parse_document(line).map_err(|message| parse_error(index + 1, message))?);
+  }
+
+  Ok(documents)
+}
+
+/// Parses a document in extended JSON, canonical or relaxed
+pub fn parse_document(line: &str) -> Result<Document, String> {
+  let value: serde_json::Value = serde_json::from_str(line).map_err(|err| err.to_string())?;
+  match Bson::try_from(value).map_err(|err| err.to_string())? {
+    Bson::Document(document) => Ok(document),
+    _ => Err("expected a document".to_string()),
+  }
+}
+
+/// Writes the collections in the same format `load_dir` reads
+pub fn write_dir(dir: &Path, data: &SeedData) -> Result<(), SeedError> {
+  fs::create_dir_all(dir).map_err(|err| SeedError::Io(dir.display().to_string(), err))?;
+  for (collection, documents) in data.collections() {
+    let path = dir.join(format!("{}.json", collection));
+    let content: String = documents
+      .iter()
+      .map(|document| format!("{}\n", Bson::Document(document.clone()).into_relaxed_extjson()))
+      .collect();
+    fs::write(&path, content).map_err(|err| SeedError::Io(path.display().to_string(), err))?;
+  }
+
+  Ok(())
+}
+
+/// Checks every document against its model and that the relations between them are consistent, returns the problems
+/// found, one per line
+pub fn validate(data: &SeedData) -> Vec<String> {
+  let mut issues = vec![];
+
+  for (collection, documents) in data.collections() {
+    issues.extend(
+      documents
+        .iter()
+        .filter_map(|document| validate_document(collection, document)),
+    );
+  }
+
+  for (collection, documents) in data.collections() {
+    issues.extend(duplicated_values(collection, documents, "_id"));
+    if collection != "repositories" {
+      issues.extend(duplicated_values(collection, documents, "login"));
+    }
+  }
+
+  issues.extend(find_issues(data).iter().map(ToString::to_string));
+
+  issues
+}
+
+/// Checks a document against the model of its collection, returns the problem found
+pub fn validate_document(collection: &str, document: &Document) -> Option<String> {
+  // the references are checked on their own, the models read them through lookups and don't deserialize them
+  let result = match collection {
+    "users" => to_model::<User>(document.clone()),
+    "organizations" => {
+      let mut document = document.clone();
+      document.remove("people");
+      to_model::<Organization>(document)
+    }
+    "repositories" => to_model::<Repository>(document.clone()),
+    _ => Err(format!("unknown collection {}", collection)),
+  };
+
+  result
+    .err()
+    .map(|err| format!("{} {}: {}", collection, id_of(document), err))
+}
+
+fn to_model<T: DeserializeOwned>(document: Document) -> Result<(), String> {
+  bson::from_document::<T>(document)
+    .map(|_| ())
+    .map_err(|err| err.to_string())
+}
+
+fn duplicated_values(collection: &str, documents: &[Document], field: &str) -> Vec<String> {
+  let mut seen = HashSet::new();
+  documents
+    .iter()
+    .filter_map(|document| document.get(field))
+    .filter(|value| !seen.insert(value.to_string()))
+    .map(|value| format!("{}: duplicated {} {}", collection, field, value))
+    .collect()
+}
+
+fn id_of(document: &Document) -> String {
+  document
+    .get("_id")
+    .map(|id| id.to_string())
+    .unwrap_or_else(|| "without _id".to_string())
+}
+
+/// Inserts the documents, those whose `_id` or `login` already exist are skipped like `mongoimport` does. Returns the
+/// number of inserted documents by collection.
+pub async fn insert(db: &Database, data: &SeedData, drop: bool) -> Result<Vec<(&'static str, usize)>, SeedError> {
+  let mut inserted = vec![];
+  for (collection_name, documents) in data.collections() {
+    let collection = db.collection::<Document>(collection_name);
+    if drop {
+      collection.delete_many(doc! {}, None).await?;
+    }
+
+    let mut count = 0;
+    for batch in documents.chunks(INSERT_BATCH_SIZE) {
+      let options = InsertManyOptions::builder().ordered(false).build();
+      count += match collection.insert_many(batch, options).await {
+        Ok(_) => batch.len(),
+        Err(err) => batch.len() - duplicated_keys(&err).ok_or(err)?,
+      };
+    }
+    tracing::info!(collection = collection_name, inserted = count, "Seed inserted");
+    inserted.push((collection_name, count));
+  }
+
+  Ok(inserted)
+}
+
+/// The number of rejected documents when the only errors are duplicated keys
+fn duplicated_keys(err: &ModelError) -> Option<usize> {
+  match &*err.kind {
+    ErrorKind::BulkWrite(failure) if failure.write_concern_error.is_none() => {
+      let write_errors = failure.write_errors.as_deref().unwrap_or_default();
+      match write_errors.iter().all(|error| error.code == DUPLICATE_KEY_ERROR) {
+        true => Some(write_errors.len()),
+        false => None,
+      }
+    }
+    _ => None,
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GenerateOptions {
+  pub users: usize,
+  pub organizations: usize,
+  pub repositories: usize,
+  /// The same seed always generates the same dataset
+  pub seed: u64,
+}
+
+/// Generates a consistent dataset of the requested size for load testing. Repositories are only generated when there
+/// is at least one user or organization to own them.
+pub fn generate(options: &GenerateOptions) -> SeedData {
+  let mut random = SplitMix64(options.seed);
+  let user_ids: Vec<ObjectId> = (0..options.users).map(|_| random.object_id()).collect();
+  let organization_ids: Vec<ObjectId> = (0..options.organizations).map(|_| random.object_id()).collect();
+  let repository_count = match user_ids.len() + organization_ids.len() {
+    0 => 0,
+    _ => options.repositories,
+  };
+  let repository_ids: Vec<ObjectId> = (0..repository_count).map(|_| random.object_id()).collect();
+
+  let mut following: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); user_ids.len()];
+  let mut followers: Vec<Vec<usize>> = vec![vec![]; user_ids.len()];
+  let mut memberships: Vec<Vec<usize>> = vec![vec![]; user_ids.len()];
+  let mut people: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); organization_ids.len()];
+
+  for (user, followed) in following.iter_mut().enumerate() {
+    for _ in 0..random.below(6) {
+      let other = random.below(user_ids.len());
+      if other != user && followed.insert(other) {
+        followers[other].push(user);
+      }
+    }
+  }
+  for (organization, members) in people.iter_mut().enumerate() {
+    for _ in 0..random.below(6) {
+      if user_ids.is_empty() {
+        break;
+      }
+      let user = random.below(user_ids.len());
+      if members.insert(user) {
+        memberships[user].push(organization);
+      }
+    }
+  }
+
+  let users = user_ids
+    .iter()
+    .enumerate()
+    .map(|(index, id)| {
+      let login = format!("user{}", index);
+      let starred: Vec<Bson> = match repository_ids.is_empty() {
+        true => vec![],
+        false => (0..random.below(4))
+          .map(|_| random.below(repository_ids.len()))
+          .collect::<BTreeSet<usize>>()
+          .into_iter()
+          .map(|repository| Bson::Document(doc! { "_id": repository_ids[repository] }))
+          .collect(),
+      };
+      doc! {
+        "_id": *id,
+        "login": &login,
+        "name": format!("User {}", index),
+        "email": format!("{}@example.com", login),
+        "emailVisibility": if random.below(2) == 0 { "public" } else { "private" },
+        "bio": format!("Bio of {}", login),
+        "avatarUrl": format!("https://example.com/{}/avatar.jpg", login),
+        "url": format!("https://github.com/{}", login),
+        "organizations": references(&organization_ids, memberships[index].iter().copied(), None),
+        "followers": references(&user_ids, followers[index].iter().copied(), None),
+        "following": references(&user_ids, following[index].iter().copied(), None),
+        "starredRepositories": starred,
+        "__typename": "User",
+      }
+    })
+    .collect();
+
+  let organizations = organization_ids
+    .iter()
+    .enumerate()
+    .map(|(index, id)| {
+      let login = format!("organization{}", index);
+      doc! {
+        "_id": *id,
+        "login": &login,
+        "name": format!("Organization {}", index),
+        "description": format!("Description of {}", login),
+        "avatarUrl": format!("https://example.com/{}/avatar.jpg", login),
+        "url": format!("https://github.com/{}", login),
+        "people": references(&user_ids, people[index].iter().copied(), Some("users")),
+        "__typename": "Organization",
+      }
+    })
+    .collect();
+
+  let repositories = repository_ids
+    .iter()
+    .enumerate()
+    .map(|(index, id)| {
+      let owner = random.below(user_ids.len() + organization_ids.len());
+      let owner = match owner < user_ids.len() {
+        true => doc! { "_id": user_ids[owner], "ref": "users" },
+        false => doc! { "_id": organization_ids[owner - user_ids.len()], "ref": "organizations" },
+      };
+      doc! {
+        "_id": *id,
+        "name": format!("repository{}", index),
+        "description": format!("Description of repository{}", index),
+        "forkCount": random.below(10_000) as f64,
+        "primaryLanguage": { "color": "#dea584", "name": "Rust" },
+        "owner": owner,
+      }
+    })
+    .collect();
+
+  SeedData {
+    users,
+    organizations,
+    repositories,
+  }
+}
+
+fn references(ids: &[ObjectId], indexes: impl IntoIterator<Item = usize>, collection: Option<&str>) -> Vec<Bson> {
+  indexes
+    .into_iter()
+    .map(|index| match collection {
+      Some(collection) => Bson::Document(doc! { "_id": ids[index], "ref": collection }),
+      None => Bson::Document(doc! { "_id": ids[index] }),
+    })
+    .collect()
+}
+
+/// Small deterministic generator, the datasets only need to look random
+struct SplitMix64(u64);
+impl SplitMix64 {
+  fn next(&mut self) -> u64 {
+    self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = self.0;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+  }
+
+  fn below(&mut self, bound: usize) -> usize {
+    match bound {
+      0 => 0,
+      _ => (self.next() % bound as u64) as usize,
+    }
+  }
+
+  /// The timestamp part falls between 2020 and 2022, it is read as the creation date of the document
+  fn object_id(&mut self) -> ObjectId {
+    let timestamp = FIRST_TIMESTAMP + (self.next() % TIMESTAMP_RANGE) as u32;
+    let mut bytes = [0; 12];
+    bytes[..4].copy_from_slice(&timestamp.to_be_bytes());
+    bytes[4..].copy_from_slice(&self.next().to_be_bytes());
+    ObjectId::from_bytes(bytes)
+  }
+}
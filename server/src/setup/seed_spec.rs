@@ -0,0 +1,145 @@
+use crate::setup::seed::{generate, load_dir, parse_documents, validate, write_dir, GenerateOptions, SeedData};
+use mongodb::bson::{doc, oid::ObjectId};
+use pretty_assertions::assert_eq;
+use std::path::Path;
+
+fn options() -> GenerateOptions {
+  GenerateOptions {
+    users: 50,
+    organizations: 5,
+    repositories: 80,
+    seed: 42,
+  }
+}
+
+#[test]
+fn should_load_the_project_seed_without_issues() {
+  let data = load_dir(&Path::new(env!("CARGO_MANIFEST_DIR")).join("database_seed")).unwrap();
+
+  assert_eq!(data.users.len(), 4);
+  assert_eq!(data.organizations.len(), 1);
+  assert_eq!(data.repositories.len(), 3);
+  assert_eq!(validate(&data), Vec::<String>::new());
+}
+
+#[test]
+fn should_parse_extended_json_lines() {
+  let content = r#"{ "_id": {"$oid":"5e5580d6f72291487ec648ce"}, "forkCount": 8612.0 }
+
+{ "_id": {"$oid":"5e5580d6f72291487ec648eb"} }"#;
+  let documents = parse_documents("users.json", content).unwrap();
+
+  assert_eq!(documents.len(), 2);
+  assert_eq!(
+    documents[0].get_object_id("_id").unwrap(),
+    ObjectId::parse_str("5e5580d6f72291487ec648ce").unwrap()
+  );
+  assert_eq!(documents[0].get_f64("forkCount").unwrap(), 8612.0);
+}
+
+#[test]
+fn should_report_the_line_of_an_invalid_document() {
+  let content = "{ \"_id\": 1 }\n{ \"_id\": \n";
+  let err = parse_documents("users.json", content).unwrap_err().to_string();
+
+  assert!(err.starts_with("users.json:2:"), "{}", err);
+}
+
+#[test]
+fn should_report_documents_that_dont_match_the_model() {
+  let data = SeedData {
+    users: vec![doc! { "_id": ObjectId::new(), "login": "foo" }],
+    ..SeedData::default()
+  };
+  let issues = validate(&data);
+
+  assert_eq!(issues.len(), 1);
+  assert!(issues[0].starts_with("users "), "{}", issues[0]);
+}
+
+#[test]
+fn should_report_missing_references() {
+  let missing_id = ObjectId::new();
+  let mut data = generate(&GenerateOptions {
+    users: 1,
+    organizations: 1,
+    repositories: 1,
+    seed: 1,
+  });
+  data.users[0].insert("following", vec![doc! { "_id": missing_id }]);
+  data.repositories[0].insert("owner", doc! { "_id": missing_id, "ref": "organizations" });
+
+  let issues = validate(&data);
+
+  assert_eq!(
+    issues,
+    vec![
+      format!(
+        "repositories {}: owner references the missing organizations {}",
+        data.repositories[0].get_object_id("_id").unwrap(),
+        missing_id
+      ),
+      format!(
+        "users {}: following references the missing users {}",
+        data.users[0].get_object_id("_id").unwrap(),
+        missing_id
+      ),
+    ]
+  );
+}
+
+#[test]
+fn should_report_duplicated_logins() {
+  let mut data = generate(&GenerateOptions {
+    users: 2,
+    organizations: 0,
+    repositories: 0,
+    seed: 1,
+  });
+  data.users[1].insert("login", "user0");
+
+  assert_eq!(validate(&data), vec!["users: duplicated login \"user0\"".to_string()]);
+}
+
+#[test]
+fn should_generate_a_valid_dataset_of_the_requested_size() {
+  let data = generate(&options());
+
+  assert_eq!(data.users.len(), 50);
+  assert_eq!(data.organizations.len(), 5);
+  assert_eq!(data.repositories.len(), 80);
+  assert_eq!(validate(&data), Vec::<String>::new());
+}
+
+#[test]
+fn should_generate_the_same_dataset_from_the_same_seed() {
+  assert_eq!(generate(&options()), generate(&options()));
+  assert_ne!(
+    generate(&options()),
+    generate(&GenerateOptions { seed: 7, ..options() })
+  );
+}
+
+#[test]
+fn should_not_generate_repositories_without_owners() {
+  let data = generate(&GenerateOptions {
+    users: 0,
+    organizations: 0,
+    repositories: 10,
+    seed: 1,
+  });
+
+  assert_eq!(data, SeedData::default());
+}
+
+#[test]
+fn should_read_back_the_written_dataset() {
+  let dir = std::env::temp_dir().join(format!("seed_spec_{}", ObjectId::new()));
+  let data = generate(&options());
+
+  write_dir(&dir, &data).unwrap();
+  let loaded = load_dir(&dir).unwrap();
+  std::fs::remove_dir_all(&dir).unwrap();
+
+  assert_eq!(loaded, data);
+}
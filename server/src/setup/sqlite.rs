@@ -0,0 +1,283 @@
+use crate::model::{organization::Organization, repository::Repository, user::User, utils::referenced_ids};
+use crate::setup::seed::SeedData;
+use mongodb::bson::{self, Document};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::{fmt, path::Path};
+use tracing;
+
+/// The schema changes, the version of the database is the number of them applied and is kept in `user_version`.
+/// New ones are appended, the applied ones must not change.
+const MIGRATIONS: [&str; 1] = [r#"
+  CREATE TABLE users (
+    id TEXT PRIMARY KEY,
+    login TEXT NOT NULL UNIQUE,
+    avatar_url TEXT NOT NULL,
+    bio TEXT,
+    email TEXT,
+    email_visibility TEXT,
+    name TEXT,
+    url TEXT NOT NULL,
+    website_url TEXT
+  );
+  CREATE TABLE organizations (
+    id TEXT PRIMARY KEY,
+    login TEXT NOT NULL UNIQUE,
+    avatar_url TEXT NOT NULL,
+    description TEXT,
+    location TEXT,
+    name TEXT,
+    url TEXT NOT NULL,
+    website_url TEXT
+  );
+  CREATE TABLE repositories (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    fork_count REAL NOT NULL,
+    license_name TEXT,
+    language_name TEXT,
+    language_color TEXT,
+    owner_id TEXT NOT NULL
+  );
+  CREATE INDEX repositories_owner_id ON repositories (owner_id, id);
+  CREATE TABLE follows (
+    follower_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    followed_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    PRIMARY KEY (followed_id, follower_id)
+  );
+  CREATE INDEX follows_follower_id ON follows (follower_id, followed_id);
+  CREATE TABLE memberships (
+    organization_id TEXT NOT NULL REFERENCES organizations (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    PRIMARY KEY (organization_id, user_id)
+  );
+  CREATE INDEX memberships_user_id ON memberships (user_id, organization_id);
+  CREATE TABLE stars (
+    user_id TEXT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    repository_id TEXT NOT NULL REFERENCES repositories (id) ON DELETE CASCADE,
+    PRIMARY KEY (user_id, repository_id)
+  );
+"#];
+
+#[derive(Debug)]
+pub enum SqliteError {
+  /// The database was migrated by a newer release
+  UnknownVersion(usize),
+  Model(String),
+  Database(rusqlite::Error),
+}
+impl fmt::Display for SqliteError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SqliteError::UnknownVersion(version) => write!(
+        f,
+        "the database has the version {}, this release knows up to {}",
+        version,
+        MIGRATIONS.len()
+      ),
+      SqliteError::Model(message) => write!(f, "{}", message),
+      SqliteError::Database(err) => write!(f, "{}", err),
+    }
+  }
+}
+impl From<rusqlite::Error> for SqliteError {
+  fn from(err: rusqlite::Error) -> Self {
+    SqliteError::Database(err)
+  }
+}
+
+/// Opens the database file, creating it when it doesn't exist, and applies the pending migrations
+pub fn open(path: &Path) -> Result<Connection, SqliteError> {
+  let mut connection = Connection::open(path)?;
+  connection.pragma_update(None, "foreign_keys", true)?;
+  connection.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+  migrate(&mut connection)?;
+
+  Ok(connection)
+}
+
+/// Applies the migrations newer than the version of the database, each one in its own transaction
+pub fn migrate(connection: &mut Connection) -> Result<usize, SqliteError> {
+  let version = schema_version(connection)?;
+  if version > MIGRATIONS.len() {
+    return Err(SqliteError::UnknownVersion(version));
+  }
+
+  for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(migration)?;
+    transaction.pragma_update(None, "user_version", index + 1)?;
+    transaction.commit()?;
+    tracing::info!(version = index + 1, "SQLite migration applied");
+  }
+
+  Ok(MIGRATIONS.len() - version)
+}
+
+pub fn schema_version(connection: &Connection) -> Result<usize, SqliteError> {
+  let version: i64 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+  Ok(version as usize)
+}
+
+/// Whether the database has no users, organizations nor repositories
+pub fn is_empty(connection: &Connection) -> Result<bool, SqliteError> {
+  let found = connection
+    .query_row(
+      "SELECT 1 FROM users UNION ALL SELECT 1 FROM organizations UNION ALL SELECT 1 FROM repositories LIMIT 1",
+      [],
+      |_| Ok(()),
+    )
+    .optional()?;
+  Ok(found.is_none())
+}
+
+/// Imports the seed documents in a single transaction, after deleting every row when `drop` is set. The documents
+/// that already exist are updated, the relations are read from both of their sides and the references to missing
+/// documents are left out.
+pub fn import(connection: &mut Connection, data: &SeedData, drop: bool) -> Result<(), SqliteError> {
+  let transaction = connection.transaction()?;
+  if drop {
+    transaction.execute_batch(
+      "DELETE FROM follows; DELETE FROM memberships; DELETE FROM stars;
+       DELETE FROM repositories; DELETE FROM organizations; DELETE FROM users;",
+    )?;
+  }
+
+  for document in &data.users {
+    let user: User = to_model("users", document)?;
+    transaction.execute(
+      "INSERT INTO users (id, login, avatar_url, bio, email, email_visibility, name, url, website_url)
+       VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+       ON CONFLICT (id) DO UPDATE SET login = ?2, avatar_url = ?3, bio = ?4, email = ?5, email_visibility = ?6,
+       name = ?7, url = ?8, website_url = ?9",
+      params![
+        user._id.to_hex(),
+        user.login,
+        user.avatar_url,
+        user.bio,
+        user.email.map(|email| email.0),
+        document.get_str("emailVisibility").ok(),
+        user.name,
+        user.url,
+        user.website_url,
+      ],
+    )?;
+  }
+
+  for document in &data.organizations {
+    let mut document = document.clone();
+    document.remove("people");
+    let organization: Organization = to_model("organizations", &document)?;
+    transaction.execute(
+      "INSERT INTO organizations (id, login, avatar_url, description, location, name, url, website_url)
+       VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+       ON CONFLICT (id) DO UPDATE SET login = ?2, avatar_url = ?3, description = ?4, location = ?5, name = ?6,
+       url = ?7, website_url = ?8",
+      params![
+        organization._id.to_hex(),
+        organization.login,
+        organization.avatar_url,
+        organization.description,
+        organization.location,
+        organization.name,
+        organization.url,
+        organization.website_url,
+      ],
+    )?;
+  }
+
+  for document in &data.repositories {
+    let repository: Repository = to_model("repositories", document)?;
+    let (language_name, language_color) = match repository.primary_language {
+      Some(language) => (Some(language.name), Some(language.color)),
+      None => (None, None),
+    };
+    transaction.execute(
+      "INSERT INTO repositories
+       (id, name, description, fork_count, license_name, language_name, language_color, owner_id)
+       VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+       ON CONFLICT (id) DO UPDATE SET name = ?2, description = ?3, fork_count = ?4, license_name = ?5,
+       language_name = ?6, language_color = ?7, owner_id = ?8",
+      params![
+        repository._id.to_hex(),
+        repository.name,
+        repository.description,
+        repository.fork_count,
+        repository.license_info.map(|license| license.name),
+        language_name,
+        language_color,
+        repository.owner._id.to_hex(),
+      ],
+    )?;
+  }
+
+  let relations = [
+    (
+      &data.users,
+      "followers",
+      "follows (followed_id, follower_id)",
+      "users",
+      "users",
+    ),
+    (
+      &data.users,
+      "following",
+      "follows (follower_id, followed_id)",
+      "users",
+      "users",
+    ),
+    (
+      &data.users,
+      "organizations",
+      "memberships (user_id, organization_id)",
+      "users",
+      "organizations",
+    ),
+    (
+      &data.users,
+      "starredRepositories",
+      "stars (user_id, repository_id)",
+      "users",
+      "repositories",
+    ),
+    (
+      &data.organizations,
+      "people",
+      "memberships (organization_id, user_id)",
+      "organizations",
+      "users",
+    ),
+  ];
+  for (documents, field, table, owner_table, target_table) in relations {
+    let sql = format!(
+      "INSERT OR IGNORE INTO {table} SELECT ?1, ?2
+       WHERE EXISTS (SELECT 1 FROM {owner_table} WHERE id = ?1) AND EXISTS (SELECT 1 FROM {target_table} WHERE id = ?2)"
+    );
+    let mut statement = transaction.prepare(&sql)?;
+    for document in documents {
+      let id = id_of(document);
+      for referenced in referenced_ids(document, field) {
+        statement.execute(params![id, referenced.to_hex()])?;
+      }
+    }
+  }
+
+  transaction.commit()?;
+  tracing::info!(
+    users = data.users.len(),
+    organizations = data.organizations.len(),
+    repositories = data.repositories.len(),
+    "Seed imported into SQLite"
+  );
+
+  Ok(())
+}
+
+fn to_model<T: serde::de::DeserializeOwned>(collection: &str, document: &Document) -> Result<T, SqliteError> {
+  bson::from_document(document.clone())
+    .map_err(|err| SqliteError::Model(format!("{} {}: {}", collection, id_of(document), err)))
+}
+
+fn id_of(document: &Document) -> String {
+  document.get_object_id("_id").map(|id| id.to_hex()).unwrap_or_default()
+}
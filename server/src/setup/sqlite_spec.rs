@@ -0,0 +1,122 @@
+use crate::setup::{
+  seed::{generate, GenerateOptions, SeedData},
+  sqlite::{import, is_empty, migrate, open, schema_version, SqliteError},
+};
+use mongodb::bson::{doc, oid::ObjectId};
+use pretty_assertions::assert_eq;
+use rusqlite::Connection;
+use std::path::Path;
+
+fn count(connection: &Connection, table: &str) -> i64 {
+  connection
+    .query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| row.get(0))
+    .unwrap()
+}
+
+fn data() -> SeedData {
+  generate(&GenerateOptions {
+    users: 20,
+    organizations: 3,
+    repositories: 30,
+    seed: 3,
+  })
+}
+
+#[test]
+fn should_migrate_a_new_database_once() {
+  let mut connection = open(Path::new(":memory:")).unwrap();
+
+  assert_eq!(schema_version(&connection).unwrap(), 1);
+  assert_eq!(migrate(&mut connection).unwrap(), 0);
+  assert_eq!(is_empty(&connection).unwrap(), true);
+}
+
+#[test]
+fn should_refuse_a_database_migrated_by_a_newer_release() {
+  let mut connection = Connection::open_in_memory().unwrap();
+  connection.pragma_update(None, "user_version", 99).unwrap();
+
+  let result = migrate(&mut connection);
+
+  assert!(matches!(result, Err(SqliteError::UnknownVersion(99))));
+}
+
+#[test]
+fn should_import_the_documents_and_their_relations() {
+  let data = data();
+  let mut connection = open(Path::new(":memory:")).unwrap();
+
+  import(&mut connection, &data, false).unwrap();
+
+  let stars: usize = data
+    .users
+    .iter()
+    .map(|user| user.get_array("starredRepositories").map(Vec::len).unwrap_or(0))
+    .sum();
+  assert_eq!(count(&connection, "users"), 20);
+  assert_eq!(count(&connection, "organizations"), 3);
+  assert_eq!(count(&connection, "repositories"), 30);
+  assert_eq!(count(&connection, "stars"), stars as i64);
+  assert_eq!(is_empty(&connection).unwrap(), false);
+}
+
+#[test]
+fn should_import_the_same_data_twice_without_duplicates() {
+  let data = data();
+  let mut connection = open(Path::new(":memory:")).unwrap();
+  import(&mut connection, &data, false).unwrap();
+  let follows = count(&connection, "follows");
+
+  import(&mut connection, &data, false).unwrap();
+
+  assert_eq!(count(&connection, "users"), 20);
+  assert_eq!(count(&connection, "follows"), follows);
+}
+
+#[test]
+fn should_replace_every_row_when_dropping() {
+  let mut connection = open(Path::new(":memory:")).unwrap();
+  import(&mut connection, &data(), false).unwrap();
+  let other = generate(&GenerateOptions {
+    users: 2,
+    organizations: 0,
+    repositories: 0,
+    seed: 9,
+  });
+
+  import(&mut connection, &other, true).unwrap();
+
+  assert_eq!(count(&connection, "users"), 2);
+  assert_eq!(count(&connection, "repositories"), 0);
+}
+
+#[test]
+fn should_leave_out_references_to_missing_documents() {
+  let mut data = generate(&GenerateOptions {
+    users: 1,
+    organizations: 0,
+    repositories: 0,
+    seed: 1,
+  });
+  data.users[0].insert("following", vec![doc! { "_id": ObjectId::new() }]);
+  data.users[0].insert("followers", Vec::<mongodb::bson::Document>::new());
+  let mut connection = open(Path::new(":memory:")).unwrap();
+
+  import(&mut connection, &data, false).unwrap();
+
+  assert_eq!(count(&connection, "follows"), 0);
+}
+
+#[test]
+fn should_report_a_document_that_doesnt_match_the_model() {
+  let data = SeedData {
+    users: vec![doc! { "_id": ObjectId::new(), "login": "foo" }],
+    ..SeedData::default()
+  };
+  let mut connection = open(Path::new(":memory:")).unwrap();
+
+  let result = import(&mut connection, &data, false);
+
+  assert!(matches!(result, Err(SqliteError::Model(message)) if message.starts_with("users ")));
+  assert_eq!(is_empty(&connection).unwrap(), true);
+}
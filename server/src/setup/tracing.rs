@@ -1,28 +1,191 @@
-use opentelemetry::{runtime::TokioCurrentThread, sdk::propagation::TraceContextPropagator};
-use std::env;
+use crate::setup::config::{LogFormat, OtlpProtocol, TracingConfig, TracingExporter};
+use opentelemetry::{
+  runtime::TokioCurrentThread,
+  sdk::{
+    export::trace::stdout,
+    propagation::TraceContextPropagator,
+    trace::{self, Sampler, Tracer},
+    Resource,
+  },
+  trace::TraceError,
+  KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use std::sync::{
+  atomic::{AtomicBool, Ordering},
+  Mutex,
+};
+use std::time::{Duration, SystemTime};
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 use tracing_subscriber::layer::SubscriberExt;
-use tracing_subscriber::{EnvFilter, Registry};
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
 
-pub fn start_tracing() {
-  let app_name = "rust_web_server";
+const APP_NAME: &str = "rust_web_server";
+const RECENT_ERROR_WINDOW: Duration = Duration::from_secs(60);
 
+static TRACING_STARTED: AtomicBool = AtomicBool::new(false);
+static TRACING_DISABLED: AtomicBool = AtomicBool::new(false);
+static LAST_EXPORT_ERROR: Mutex<Option<(SystemTime, String)>> = Mutex::new(None);
+static LAST_LOGGED_EXPORT_ERROR: Mutex<Option<SystemTime>> = Mutex::new(None);
+
+#[derive(Debug, PartialEq)]
+pub enum TracingStatus {
+  Running,
+  NotStarted,
+  Disabled,
+  Failing(String),
+}
+
+/// Changes the `EnvFilter` of the installed subscriber without restarting the process
+#[derive(Clone)]
+pub struct LogFilterHandle(reload::Handle<EnvFilter, Registry>);
+impl LogFilterHandle {
+  /// The directive in use, in the same format as `RUST_LOG`
+  pub fn current(&self) -> String {
+    self.0.with_current(|filter| filter.to_string()).unwrap_or_default()
+  }
+
+  pub fn set(&self, directive: &str) -> Result<String, String> {
+    let filter = EnvFilter::try_new(directive).map_err(|err| err.to_string())?;
+    self.0.reload(filter).map_err(|err| err.to_string())?;
+    Ok(self.current())
+  }
+}
+
+pub fn log_filter_layer(filter: EnvFilter) -> (reload::Layer<EnvFilter, Registry>, LogFilterHandle) {
+  let (layer, handle) = reload::Layer::new(filter);
+  (layer, LogFilterHandle(handle))
+}
+
+pub fn start_tracing(config: &TracingConfig) -> LogFilterHandle {
   opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+  opentelemetry::global::set_error_handler(|err| record_export_error(err.to_string()))
+    .expect("Failed to install OpenTelemetry error handler.");
 
-  let tracer = opentelemetry_jaeger::new_agent_pipeline()
-    .with_service_name(app_name)
-    .install_batch(TokioCurrentThread)
-    .expect("Failed to install OpenTelemetry tracer.");
+  let tracer = install_tracer(config).expect("Failed to install OpenTelemetry tracer.");
 
-  let env_filter = EnvFilter::try_from_default_env().unwrap_or(EnvFilter::new("info"));
-  let telemetry = tracing_opentelemetry::layer().with_tracer(tracer);
-  let formatting_layer = BunyanFormattingLayer::new(app_name.into(), std::io::stdout);
+  let (env_filter, log_filter) = log_filter_layer(EnvFilter::try_from_default_env().unwrap_or(EnvFilter::new("info")));
+  // `Option<Layer>` is a no-op layer when `None`, this keeps a single subscriber type for every combination
+  let telemetry = tracer.map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer));
+  let (json_storage, bunyan_layer, pretty_layer) = match config.log_format {
+    LogFormat::Bunyan => (
+      Some(JsonStorageLayer),
+      Some(BunyanFormattingLayer::new(APP_NAME.into(), std::io::stdout)),
+      None,
+    ),
+    LogFormat::Pretty => (None, None, Some(fmt::layer().pretty())),
+  };
   let subscriber = Registry::default()
     .with(env_filter)
     .with(telemetry)
-    .with(JsonStorageLayer)
-    .with(formatting_layer);
+    .with(json_storage)
+    .with(bunyan_layer)
+    .with(pretty_layer);
+
+  tracing::subscriber::set_global_default(subscriber).expect("Failed to install `tracing` subscriber.");
+  TRACING_STARTED.store(true, Ordering::Relaxed);
+  TRACING_DISABLED.store(config.exporter == TracingExporter::None, Ordering::Relaxed);
+
+  tracing::info!(exporter = ?config.exporter, sampling_ratio = config.sampling_ratio, "tracing started");
+  if let Some(tracing_ui) = &config.ui {
+    tracing::info!("tracing UI running at {}", tracing_ui);
+  }
+
+  log_filter
+}
+
+fn install_tracer(config: &TracingConfig) -> Result<Option<Tracer>, TraceError> {
+  let trace_config = trace::config()
+    .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+      config.sampling_ratio,
+    ))))
+    .with_resource(Resource::new(vec![KeyValue::new("service.name", APP_NAME)]));
+
+  let tracer = match (config.exporter, config.otlp_protocol) {
+    (TracingExporter::None, _) => return Ok(None),
+    (TracingExporter::Otlp, OtlpProtocol::Grpc) => {
+      let mut exporter = opentelemetry_otlp::new_exporter().tonic();
+      if let Some(endpoint) = &config.endpoint {
+        exporter = exporter.with_endpoint(endpoint);
+      }
+      opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(exporter)
+        .with_trace_config(trace_config)
+        .install_batch(TokioCurrentThread)?
+    }
+    (TracingExporter::Otlp, OtlpProtocol::Http) => {
+      let mut exporter = opentelemetry_otlp::new_exporter().http();
+      if let Some(endpoint) = &config.endpoint {
+        exporter = exporter.with_endpoint(endpoint);
+      }
+      opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(exporter)
+        .with_trace_config(trace_config)
+        .install_batch(TokioCurrentThread)?
+    }
+    (TracingExporter::Jaeger, _) => {
+      let mut pipeline = opentelemetry_jaeger::new_agent_pipeline()
+        .with_service_name(APP_NAME)
+        .with_trace_config(trace_config);
+      if let Some(endpoint) = &config.endpoint {
+        pipeline = pipeline.with_endpoint(endpoint);
+      }
+      pipeline.install_batch(TokioCurrentThread)?
+    }
+    (TracingExporter::Stdout, _) => stdout::new_pipeline()
+      .with_pretty_print(true)
+      .with_trace_config(trace_config)
+      .install_simple(),
+  };
+
+  Ok(Some(tracer))
+}
+
+/// Keeps the error for the readiness probe, a collector that is down fails every export so it is logged once per
+/// `RECENT_ERROR_WINDOW`
+fn record_export_error(message: String) {
+  let now = SystemTime::now();
+  let should_log = {
+    let mut last_logged = LAST_LOGGED_EXPORT_ERROR.lock().unwrap();
+    let should_log = match *last_logged {
+      Some(logged_at) => now.duration_since(logged_at).unwrap_or_default() >= RECENT_ERROR_WINDOW,
+      None => true,
+    };
+    if should_log {
+      *last_logged = Some(now);
+    }
+    should_log
+  };
+  if should_log {
+    tracing::warn!(error = %message, "OpenTelemetry export fails");
+  }
+  *LAST_EXPORT_ERROR.lock().unwrap() = Some((now, message));
+}
+
+/// Reports whether spans are being exported, an export error in the last minute marks the pipeline as failing
+pub fn tracing_status() -> TracingStatus {
+  if !TRACING_STARTED.load(Ordering::Relaxed) {
+    return TracingStatus::NotStarted;
+  }
+  if TRACING_DISABLED.load(Ordering::Relaxed) {
+    return TracingStatus::Disabled;
+  }
+
+  match &*LAST_EXPORT_ERROR.lock().unwrap() {
+    Some((failed_at, message)) if failed_at.elapsed().unwrap_or_default() < RECENT_ERROR_WINDOW => {
+      TracingStatus::Failing(message.clone())
+    }
+    _ => TracingStatus::Running,
+  }
+}
 
+/// Plain logs on stderr for the command line tools, which don't export spans
+pub fn start_cli_logging() {
+  let subscriber = fmt()
+    .with_env_filter(EnvFilter::try_from_default_env().unwrap_or(EnvFilter::new("info")))
+    .with_writer(std::io::stderr)
+    .finish();
   tracing::subscriber::set_global_default(subscriber).expect("Failed to install `tracing` subscriber.");
-  tracing::info!("tracing UI running at {}", env::var("TRACING_UI").unwrap());
 }